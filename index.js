@@ -4,6 +4,7 @@
 
 const help = require('./lib/help');
 const settings = require('./package.json');
+const Misc = require('./lib/util/misc');
 
 if (require.main === module) {
     const argv = require('minimist')(process.argv, {
@@ -94,35 +95,38 @@ if (require.main === module) {
             break;
         case ('convert'): {
             const convert_arg = require('minimist')(process.argv, {
-                string: ['input', 'output']
+                string: ['input', 'output', 'input-format', 'output-format']
             });
 
             require('./native/index.node').convert({
                 input: convert_arg.input,
-                output: convert_arg.output
+                output: convert_arg.output,
+                input_format: convert_arg['input-format'],
+                output_format: convert_arg['output-format']
             });
 
             break;
         }
         case ('dedupe'): {
             const dedupe_arg = require('minimist')(process.argv, {
-                string: ['buildings', 'input', 'output', 'languages', 'db', 'country', 'region'],
-                boolean: ['hecate'],
+                string: ['buildings', 'input', 'output', 'errors', 'invalid', 'invalid-limit', 'crs', 'languages', 'db', 'schema', 'country', 'region'],
+                boolean: ['hecate', 'memory'],
                 alias: {
                     database: 'db'
                 }
             });
 
             let context = undefined;
-            if (dedupe_arg.country) {
+            if (dedupe_arg.country || dedupe_arg.crs) {
                 context = {
                     country: dedupe_arg.country,
-                    region: dedupe_arg.region
+                    region: dedupe_arg.region,
+                    crs: dedupe_arg.crs
                 };
             }
 
-            if (!dedupe_arg.db) {
-                console.error('--db <DATABASE> argument required');
+            if (!dedupe_arg.db && !dedupe_arg.memory) {
+                console.error('--db <DATABASE> or --memory argument required');
                 process.exit(1);
             }
 
@@ -130,18 +134,21 @@ if (require.main === module) {
                 buildings: dedupe_arg.buildings,
                 input: dedupe_arg.input,
                 output: dedupe_arg.output,
+                errors: dedupe_arg.errors,
+                invalid: Misc.invalidPolicy(dedupe_arg.invalid, dedupe_arg['invalid-limit']),
                 languages: dedupe_arg.languages,
                 hecate: dedupe_arg.hecate,
                 context: context,
-                db: dedupe_arg.db
+                db: Misc.withSchema(dedupe_arg.db || 'dedupe', dedupe_arg.schema),
+                backend: dedupe_arg.memory ? 'memory' : 'postgres'
             });
 
             break;
         }
         case ('classify'): {
             const classify_arg = require('minimist')(process.argv, {
-                string: ['buildings', 'parcels', 'input', 'output', 'db'],
-                boolean: ['hecate'],
+                string: ['buildings', 'parcels', 'input', 'output', 'errors', 'invalid', 'invalid-limit', 'crs', 'db', 'schema'],
+                boolean: ['hecate', 'memory'],
                 alias: {
                     database: 'db',
                     buildings: 'building',
@@ -149,8 +156,8 @@ if (require.main === module) {
                 }
             });
 
-            if (!classify_arg.db) {
-                console.error('--db <DATABASE> argument required');
+            if (!classify_arg.db && !classify_arg.memory) {
+                console.error('--db <DATABASE> or --memory argument required');
                 process.exit(1);
             } else if (!classify_arg.input) {
                 console.error('--input <FILE> argument required');
@@ -168,8 +175,12 @@ if (require.main === module) {
                 parcels: classify_arg.parcels,
                 input: classify_arg.input,
                 output: classify_arg.output,
-                db: classify_arg.db,
-                hecate: classify_arg.hecate
+                errors: classify_arg.errors,
+                invalid: Misc.invalidPolicy(classify_arg.invalid, classify_arg['invalid-limit']),
+                crs: classify_arg.crs,
+                db: Misc.withSchema(classify_arg.db || 'classify', classify_arg.schema),
+                hecate: classify_arg.hecate,
+                backend: classify_arg.memory ? 'memory' : 'postgres'
             });
 
             break;
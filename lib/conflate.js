@@ -1,7 +1,6 @@
 'use strict';
 
 const path = require('path');
-const pg = require('pg');
 const fs = require('fs');
 const os = require('os');
 const Compare = require('./conflate/compare');
@@ -12,6 +11,7 @@ const Index = require('./conflate/index');
 
 const CPUS = process.env.CI ? 10 : Math.min(16, os.cpus().length);
 const tokenize = require('./util/tokenize');
+const Misc = require('./util/misc');
 
 /**
  * Main entrance to conflate module which conflates a new address source against an existing file
@@ -60,12 +60,7 @@ function main(argv, cb) {
     }
     const tokens = tokenize.createReplacer(argv.languages);
 
-    const pool = new pg.Pool({
-        max: CPUS,
-        user: 'postgres',
-        database: argv.db,
-        idleTimeoutMillis: 30000
-    });
+    const pool = Misc.pool(Misc.poolConf(argv.db, CPUS));
 
     createIndex();
 
@@ -9,15 +9,23 @@ module.exports = function(argv) {
             console.log('');
             console.log('usage: index.js classify [--buildings <buildings.geojson> ] [--parcels <parcels.geojson> ]');
             console.log('                         [--input|-i <addresses.geojson>] [--output|-o <OUTFILE>]');
-            console.log('                         [--database|--db <DATABASE>] [--hecate]');
+            console.log('                         [--database|--db <DATABASE>] [--memory] [--hecate] [--errors <FILE>]');
             console.log('');
             console.log('[options]:');
             console.log('   --input|-i <FILE>         Addresses to classify in PT2ITP Format');
             console.log('   --output|-o <OUTFILE>     File to write classified addresses to in hecate format ');
+            console.log('   --errors <FILE>           Write rejected features to a file as line-delimited JSON');
+            console.log('   --invalid <strict|skip>   Stop at or skip input lines that are not valid GeoJSON, defaults to strict');
+            console.log('   --invalid-limit <N>       Skip invalid input lines, stopping once more than N are read');
+            console.log('   --crs <CRS>               Coordinate reference system of the inputs, ie: EPSG:26918, defaults to EPSG:4326');
             console.log('   --db  <DATABASE>          Database to use as a backend');
+            console.log('   --schema <SCHEMA>         Postgres schema to create tables in, defaults to public');
+            console.log('   --memory                  Run in memory instead of using a database, for small inputs');
             console.log('   --buildings <FILE>        GeoJSONLD Building Polygons');
             console.log('   --parcels <FILE>          GeoJSONLD Parcel Polygons');
             console.log('   --hecate                  Expect Hecate input & produce hecate output');
+            console.log('');
+            console.log('   Any input or output with a .gpkg extension is read or written as a GeoPackage');
             break;
         case ('testcsv'):
             console.log('');
@@ -77,12 +85,23 @@ module.exports = function(argv) {
             console.log('usage: index.js map [--in-network=<FILE.geojson>] [--in-address=<FILE.geojson>] [--output=<FILE.geojson>]');
             console.log('                    [--error-network <FILE>] [--error-address <FILE>] [--languages=<CODE, ...>]');
             console.log('                    [--post <cardinality>,...] [--intersections] [--props <prop>,...] [--warn <FILE]');
+            console.log('                    [--resume] [--csv] [--csv-delimiter <CHAR>] [--csv-columns <column>=<HEADER>,...]');
+            console.log('                    [--osm-highways <highway>,...]');
             console.log('');
             console.log('[options]:');
             console.log('   --in-network=<FILE.geojson>              geojson of street network');
             console.log('   --in-address=<FILE.geojson>              geojson of address points');
-            console.log('   --db="<DATABASE>"                        Name of database to connect to w/ user "postgres"');
-            console.log('   --output=<FILE.geojson>                  output generated ITP lines');
+            console.log('   --csv                                    [optional] --in-address is a CSV with a header row, by default using');
+            console.log('                                                OpenAddresses LON,LAT,NUMBER,STREET,UNIT,CITY,POSTCODE,ID columns');
+            console.log('   --csv-delimiter=<CHAR>                   [optional] CSV delimiter, defaults to ","');
+            console.log('   --csv-columns=<column>=<HEADER>,...      [optional] Map lon, lat, number, street, unit, city, postcode or id');
+            console.log('                                                to a different CSV header ie: number=HOUSENUM,street=STREET_NAME');
+            console.log('   --osm-highways=<highway>,...             [optional] highway tag values imported from a .pbf --in-network');
+            console.log('                                                defaults to roads from motorway through service');
+            console.log('   --db="<DATABASE>"                        Database name or postgres:// URL, see PG* env vars');
+            console.log('   --schema=<SCHEMA>                        [optional] Postgres schema to create tables in, allowing');
+            console.log('                                                multiple jobs to share one database');
+            console.log('   --output=<FILE.geojson>                  output generated ITP lines, as a GeoPackage if <FILE.gpkg>');
             console.log('   --label=<Label>                          Default text standardization strategy - defaults to titlecase');
             console.log('   --post <cardinality>,...                 [optional] Optional PostProcessing Steps');
             console.log('          cardinality                          Add cardinal prefix/postfix as synonyms');
@@ -96,7 +115,17 @@ module.exports = function(argv) {
             console.log('   --region=<ISO3166-2>                     [optional] Used by some "map" scripts to alter input text. IE state highways');
             console.log('   --intersections                          [optional] Generate and include intersections in the final output');
             console.log('   --debug                                  [optional] Gives much richer info for `debug` mode module');
+            console.log('   --resume                                 [optional] Skip stages completed by a previous run with the same inputs');
             console.log('   --error-network <FILE>                   [optional] Output invalid features to a given file');
+            console.log('   --invalid=<strict|skip>                  [optional] Stop at or skip input lines that are not valid GeoJSON');
+            console.log('                                                defaults to strict');
+            console.log('   --invalid-limit=<N>                      [optional] Skip invalid input lines, stopping once more than N are read');
+            console.log('   --crs=<CRS>                              [optional] Coordinate reference system of the inputs, ie: EPSG:26918');
+            console.log('                                                defaults to EPSG:4326');
+            console.log('   --postcode-property=<Prop>               [optional] Input property holding the postcode of a feature');
+            console.log('                                                defaults to postcode');
+            console.log('   --locality-property=<Prop>               [optional] Input property holding the locality of a feature');
+            console.log('                                                defaults to city');
             console.log('   --error-map <FILE>                       [optional] Output invalid features to a given file');
             console.log('   --warn <FILE>                            [optional] Output build warnings to a file');
             break;
@@ -111,7 +140,7 @@ module.exports = function(argv) {
             console.log('[options]:');
             console.log('   --in-addresses=<FILE.geojson>             line-delimited geojson of new address features');
             console.log('   --in-persistent=<FILE.geojson>            line-delimited geojson of persistent/existing address');
-            console.log('   --db="<DATABASE>"                         Name of database to connect to w/ user "postgres"');
+            console.log('   --db="<DATABASE>"                         Database name or postgres:// URL, see PG* env vars');
             console.log('   --output=<FILE.geojson>                   output line-delimited geojson diff of new addresses');
             console.log('   --error-persistent=<FILE>                 [optional] File to log persistent address import errors');
             console.log('   --languages=<ISO 639-1,ISO 639-1,...>     [optional] Language codes of abbreviation tokens to match');
@@ -123,18 +152,26 @@ module.exports = function(argv) {
             console.log('Remove duplicates witin a given address file');
             console.log('');
             console.log('usage: index.js dedupe [--input=<FILE>] [--output <FILE>] [--languages=<CODE, ...>]');
-            console.log('                   [--db <DATABASE] [--hecate] [--buildings=<FILE>]');
+            console.log('                   [--db <DATABASE] [--memory] [--hecate] [--buildings=<FILE>]');
             console.log('');
             console.log('Note: by default will read from STDIN and output to STDOUT');
             console.log('');
             console.log('[options]:');
             console.log('   --db <DATABASE>                           [optional] Postgres database to run analysis in');
+            console.log('   --schema <SCHEMA>                         [optional] Postgres schema to create tables in');
+            console.log('   --memory                                  [optional] Run analysis in memory instead of Postgres');
             console.log('   --input=<FILE.geojson>                    [optional] line-delimited geojson of address features');
             console.log('   --buildings=<FILE.geojson>                [optional] line-delimited geojson of polygonal buildings');
             console.log('                                               to use as bounds for deduping');
             console.log('   --output=<FILE.geojson>                   [optional] line-delimited geojson of deduped features to write to');
+            console.log('   --errors=<FILE>                           [optional] line-delimited JSON of rejected features to write to');
+            console.log('   --invalid=<strict|skip>                   [optional] Stop at or skip input lines that are not valid GeoJSON');
+            console.log('   --invalid-limit=<N>                       [optional] Skip invalid input lines, stopping once more than N are read');
+            console.log('   --crs=<CRS>                               [optional] Coordinate reference system of the inputs, ie: EPSG:26918');
             console.log('   --languages=<ISO 639-1,ISO 639-1,...>     [optional] Language codes of abbreviation tokens to match');
             console.log('   --hecate                                  [optional] output duplicates as hecate deletions');
+            console.log('');
+            console.log('   Any input or output with a .gpkg extension is read or written as a GeoPackage');
             break;
         case ('strip'):
             console.log('');
@@ -147,15 +184,18 @@ module.exports = function(argv) {
             break;
         case ('convert'):
             console.log('');
-            console.log('Convert a Line-Delimited GeoJSON Features into a single FeatureCollection');
+            console.log('Convert between Line-Delimited GeoJSON Features, GeoJSON Text Sequences & a single FeatureCollection');
             console.log('');
             console.log('Note: by default will read from STDIN and output to STDOUT');
             console.log('');
             console.log('usage: index.js convert [--input=<FILE.geojson>] [--output=<FILE.geojson>]');
+            console.log('                        [--input-format=<FORMAT>] [--output-format=<FORMAT>]');
             console.log('');
             console.log('[options]:');
-            console.log('   --output=<FILE.geojson>         Single GeoJSON FeatureCollection');
+            console.log('   --output=<FILE.geojson>         Single GeoJSON FeatureCollection, or a GeoPackage if <FILE.gpkg>');
             console.log('   --input=<FILE.geojson>          Line delimited GeoJSON FeatureCollections');
+            console.log('   --input-format=<FORMAT>         [optional] featurecollection, geojsonseq or geojsonld, detected by default');
+            console.log('   --output-format=<FORMAT>        [optional] featurecollection, geojsonseq or geojsonld, defaults to featurecollection');
             console.log('');
             break;
         case ('analyze'):
@@ -171,7 +211,7 @@ module.exports = function(argv) {
             console.log('');
             console.log('<command>:');
             console.log('    help                      Displays this message');
-            console.log('    convert  [--help]         Convert default line delimited geojson to featurecollection');
+            console.log('    convert  [--help]         Convert between line delimited geojson, geojsonseq & featurecollection');
             console.log('    conflate [--help]         Given a new address file, apply it to an existing address file');
             console.log('    clean    [--help]         Run address or network data through a given map script, returning the result');
             console.log('    dedupe   [--help]         Dedupe like addresses from a given input file');
@@ -4,7 +4,6 @@ const Queue = require('d3-queue').queue;
 const CP = require('child_process');
 const Prog = require('progress');
 const path = require('path');
-const pg = require('pg');
 const fs = require('fs');
 const os = require('os');
 
@@ -13,11 +12,15 @@ const {
     import_net,
     cluster_addr,
     cluster_net,
-    intersections
+    intersections,
+    stage_begin,
+    stage_complete,
+    convert
 } = require('../native/index.node');
 
 const CPUS = process.env.CI ? 10 : Math.min(16, os.cpus().length);
 
+const Misc = require('./util/misc');
 const Orphan = require('./map/orphan');
 const split = require('./map/split');
 
@@ -49,12 +52,23 @@ function main(argv, cb) {
                 'languages',
                 'country',
                 'region',
-                'db'
+                'db',
+                'schema',
+                'csv-delimiter',
+                'csv-columns',
+                'osm-highways',
+                'postcode-property',
+                'locality-property',
+                'invalid',
+                'invalid-limit',
+                'crs'
             ],
             boolean: [
                 'name',
                 'debug',
-                'intersections'
+                'intersections',
+                'resume',
+                'csv'
             ],
             alias: {
                 'intersections': 'intersection',
@@ -79,6 +93,8 @@ function main(argv, cb) {
         return cb(new Error('--db=<DATABASE> argument required'));
     }
 
+    argv.db = Misc.withSchema(argv.db, argv.schema);
+
     if (!argv.post) {
         argv.post = [];
     } else if (typeof argv.post === 'string') {
@@ -103,20 +119,52 @@ function main(argv, cb) {
         argv.languages = argv.languages.split(',');
     }
 
-    const output = fs.createWriteStream(path.resolve(__dirname, '..', argv.output));
+    // Addresses provided as CSV, by default using OpenAddresses column names
+    let csv;
+    if (argv.csv) {
+        csv = {};
+
+        if (argv['csv-delimiter']) csv.delimiter = argv['csv-delimiter'];
+
+        if (argv['csv-columns']) {
+            for (const mapping of argv['csv-columns'].split(',')) {
+                const [column, header] = mapping.split('=');
+
+                if (['lon', 'lat', 'number', 'street', 'unit', 'city', 'postcode', 'id'].indexOf(column) === -1 || !header) {
+                    return cb(new Error(`invalid column mapping '${mapping}' in --csv-columns flag`));
+                }
+
+                csv[column] = header;
+            }
+        }
+    }
+
+    // Lines of GeoJSON input that cannot be parsed stop the import unless skipped
+    let invalid;
+    try {
+        invalid = Misc.invalidPolicy(argv.invalid, argv['invalid-limit']);
+    } catch (err) {
+        return cb(err);
+    }
+
+    // Inputs with a .pbf extension are read as OpenStreetMap PBF extracts
+    const osm = {};
+    if (argv['osm-highways']) osm.highways = argv['osm-highways'].split(',');
+
+    const isPbf = (input) => path.extname(input).toLowerCase() === '.pbf';
+
+    // GeoPackage output is written as line delimited GeoJSON & converted once complete
+    const outpath = path.resolve(__dirname, '..', argv.output);
+    const gpkg = path.extname(outpath).toLowerCase() === '.gpkg';
+    const output = fs.createWriteStream(gpkg ? `${outpath}.geojson` : outpath);
 
     if (argv.warn) {
         argv.warn = String(path.resolve(__dirname, '..', argv.warn));
     }
 
-    const poolConf = {
-        max: CPUS,
-        user: 'postgres',
-        database: argv.db,
-        idleTimeoutMillis: 30000
-    };
+    const poolConf = Misc.poolConf(argv.db, CPUS);
 
-    const pool = new pg.Pool(poolConf);
+    const pool = Misc.pool(poolConf);
 
     let opts = { pool: pool };
     const orphan = new Orphan(pool, argv, output);
@@ -124,16 +172,26 @@ function main(argv, cb) {
     const context = {
         country: argv.country ? argv.country : '',
         region: argv.region ? argv.region : '',
-        languages: argv.languages
+        languages: argv.languages,
+        crs: argv.crs,
+        postcode: argv['postcode-property'],
+        locality: argv['locality-property']
     };
 
+    // Skip stages that completed in a previous run with the same inputs
+    const resume = !!argv.resume;
+
     console.time('ok - address imported');
     import_addr({
         db: argv.db,
         seq: true,
         input: argv['in-address'],
         context: context,
-        errors: argv['error-address']
+        errors: argv['error-address'],
+        invalid: invalid,
+        resume: resume,
+        csv: csv,
+        osm: isPbf(argv['in-address']) ? osm : undefined
     });
     console.timeEnd('ok - address imported');
 
@@ -143,16 +201,19 @@ function main(argv, cb) {
         seq: true,
         input: argv['in-network'],
         context: context,
-        errors: argv['error-network']
+        errors: argv['error-network'],
+        invalid: invalid,
+        resume: resume,
+        osm: isPbf(argv['in-network']) ? osm : undefined
     });
     console.timeEnd('ok - network imported');
 
     console.time('ok - clustered networks');
-    cluster_net(argv.db);
+    cluster_net(argv.db, false, resume);
     console.timeEnd('ok - clustered networks');
 
     console.time('ok - generated intersections');
-    intersections(argv.db);
+    intersections(argv.db, resume);
     console.timeEnd('ok - generated intersections');
 
     matcher();
@@ -161,6 +222,11 @@ function main(argv, cb) {
      * Match network clusters with a proximal and textually similiar address cluster
      */
     function matcher() {
+        if (!stage_begin(argv.db, 'match', resume)) {
+            console.log('ok - match stage already complete, skipping');
+            return cluster();
+        }
+
         pool.query('SELECT MAX(id) AS max FROM address', (err, res) => {
             if (err) return cb(err);
 
@@ -198,16 +264,9 @@ function main(argv, cb) {
 
                     if (!active) {
                         console.timeEnd('ok - cross matched data');
+                        stage_complete(argv.db, 'match');
 
-                        console.time('ok - clustered addresses');
-                        cluster_addr(argv.db, false);
-                        console.timeEnd('ok - clustered addresses');
-
-                        console.time('ok - clustered orphan addresses');
-                        cluster_addr(argv.db, true);
-                        console.timeEnd('ok - clustered orphan addresses');
-
-                        return splitter();
+                        return cluster();
                     }
                 });
 
@@ -239,6 +298,25 @@ function main(argv, cb) {
         });
     }
 
+    /**
+     * Cluster matched & unmatched addresses and networks
+     */
+    function cluster() {
+        console.time('ok - clustered addresses');
+        cluster_addr(argv.db, false, resume);
+        console.timeEnd('ok - clustered addresses');
+
+        console.time('ok - clustered orphan addresses');
+        cluster_addr(argv.db, true, resume);
+        console.timeEnd('ok - clustered orphan addresses');
+
+        console.time('ok - clustered orphan networks');
+        cluster_net(argv.db, true, resume);
+        console.timeEnd('ok - clustered orphan networks');
+
+        return splitter();
+    }
+
     /**
      * Find closest Segment in network for each address point in matched cluster and generate ITP output
      */
@@ -367,11 +445,25 @@ function main(argv, cb) {
         if (err) return cb(err);
 
         console.time('ok - Doing a bit of housecleaning');
-        output.end();
-        pool.end(() => {
-            console.timeEnd('ok - Doing a bit of housecleaning');
+        output.end(() => {
+            if (gpkg) {
+                try {
+                    convert({
+                        input: `${outpath}.geojson`,
+                        output: outpath
+                    });
+                } catch (err) {
+                    return cb(err);
+                }
 
-            return cb();
+                fs.unlinkSync(`${outpath}.geojson`);
+            }
+
+            pool.end(() => {
+                console.timeEnd('ok - Doing a bit of housecleaning');
+
+                return cb();
+            });
         });
     }
 
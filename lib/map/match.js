@@ -1,7 +1,7 @@
 'use strict';
 
 const linker = require('./linker');
-const pg = require('pg');
+const Misc = require('../util/misc');
 const Q = require('d3-queue').queue;
 
 let pool, id;
@@ -42,7 +42,7 @@ function kill() {
  * @return {boolean} Returns true after match is initialized
  */
 function init(o) {
-    pool = new pg.Pool(o.pool);
+    pool = Misc.pool(o.pool);
     id = o.id;
 
     return true;
@@ -68,7 +68,10 @@ class Orphan {
                         json_build_object(
                             'id', address.id,
                             'number', address.number,
-                            'props', address.props,
+                            'props', address.props || jsonb_strip_nulls(jsonb_build_object(
+                                'postcode', address.postcode,
+                                'locality', address.locality
+                            )),
                             'output', address.output
                         ) as props
                     FROM
@@ -165,7 +168,9 @@ class Orphan {
     }
 
     /**
-     * Output all network_clusters not matched to an address_cluster
+     * Output all network_orphan_clusters - networks not matched to an address_cluster
+     * Intersections are generated against network_cluster so they are mapped back onto
+     * the orphan cluster via the shared network source ids
      * @param {Function} cb Callback in (err, res)
      * @return {Function} cb
      */
@@ -177,39 +182,39 @@ class Orphan {
 
             const cursor = client.query(new Cursor(`
                 SELECT
-                    network_cluster.id AS nid,
+                    network_orphan_cluster.id AS nid,
                     names AS name,
                     ST_AsGeoJSON(geom)::JSON AS geom,
+                    postcode,
+                    locality,
                     (
                         SELECT
                             json_agg(json_build_object(
                                  'id', i.id,
-                                 'a_id', i.a_id,
-                                 'b_id', i.b_id,
+                                 'a_id', CASE WHEN i.a_id = ANY(c.ids) THEN network_orphan_cluster.id ELSE i.a_id END,
+                                 'b_id', CASE WHEN i.b_id = ANY(c.ids) THEN network_orphan_cluster.id ELSE i.b_id END,
                                  'a_street', i.a_street,
                                  'b_street', i.b_street,
-                                 'geom', i.geom::JSON
+                                 'geom', ST_AsGeoJSON(i.geom)::JSON
                             ))
-                        FROM (
-                            SELECT
-                                intersections.id AS id,
-                                intersections.a_id AS a_id,
-                                intersections.b_id AS b_id,
-                                intersections.a_street AS a_street,
-                                intersections.b_street AS b_street,
-                                ST_AsGeoJSON(intersections.geom)::JSON AS geom
-                            FROM
-                                intersections
-                            WHERE
-                                intersections.a_id = network_cluster.id
-                                OR intersections.b_id = network_cluster.id
-                        ) i
+                        FROM
+                            intersections i,
+                            (
+                                SELECT
+                                    ARRAY_AGG(network_cluster.id) AS ids
+                                FROM
+                                    network_cluster
+                                WHERE
+                                    network_cluster.source_ids && network_orphan_cluster.source_ids
+                            ) c
+                        WHERE
+                            (i.a_id = ANY(c.ids) OR i.b_id = ANY(c.ids))
+                            AND NOT (i.a_id = ANY(c.ids) AND i.b_id = ANY(c.ids))
                     ) AS intersections
                 FROM
-                    network_cluster
+                    network_orphan_cluster
                 WHERE
-                    network_cluster.address IS NULL
-                    AND network_cluster.geom is NOT NULL
+                    network_orphan_cluster.geom is NOT NULL
             `));
 
             return iterate();
@@ -260,6 +265,14 @@ class Orphan {
 
                         if (self.opts.country) feat.properties['carmen:geocoder_stack'] = self.opts.country;
 
+                        // Networks have no address_props, so requested postcode & locality props
+                        // are output when all of the network features in the cluster agree
+                        for (const prop of ['postcode', 'locality']) {
+                            if ((self.opts.props || []).indexOf(prop) !== -1 && row[prop] && row[prop].length === 1) {
+                                feat.properties[prop] = row[prop][0];
+                            }
+                        }
+
                         feat = self.post.feat(feat);
                         if (feat) self.output.write(JSON.stringify(feat) + '\n');
                     }
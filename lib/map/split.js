@@ -7,7 +7,6 @@ const Explode = require('./explode');
 const Cluster = require('./cluster');
 const Queue = require('d3-queue').queue;
 const turf = require('@turf/turf');
-const pg = require('pg');
 
 const Misc = require('../util/misc');
 
@@ -66,7 +65,10 @@ class SplitFeat {
                 json_agg(json_build_object(
                      'id', address.id,
                      'number', address.number,
-                     'props', address.props,
+                     'props', address.props || jsonb_strip_nulls(jsonb_build_object(
+                         'postcode', address.postcode,
+                         'locality', address.locality
+                     )),
                      'output', address.output
                 )) AS address_props,
                 (
@@ -408,7 +410,7 @@ process.on('message', (message) => {
         if (message.type && (message.type === 'end')) {
             pool.end();
         } else {
-            pool = new pg.Pool(message.pool);
+            pool = Misc.pool(message.pool);
             split = new Split(message);
         }
 
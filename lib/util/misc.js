@@ -1,6 +1,7 @@
 'use strict';
 
 const turf = require('@turf/turf');
+const pg = require('pg');
 
 /**
  * @class Misc
@@ -76,6 +77,110 @@ class Misc {
         }
         return false;
     }
+
+    /**
+     * Build a node-pg pool config from a --db argument which can either be a
+     * full postgres:// URL or a database name. Unset fields fall back to the
+     * standard PG* environment variables, matching the native modules
+     *
+     * A `?schema=<name>` option is removed from the db argument & returned as
+     * the `schema` property, see Misc.pool
+     *
+     * @param {string} db Database name or connection URL
+     * @param {number} max Maximum number of pooled clients
+     * @return {Object}
+     */
+    static poolConf(db, max) {
+        let schema;
+        let options = [];
+
+        if (db.indexOf('?') !== -1) {
+            options = db.slice(db.indexOf('?') + 1).split('&').filter((option) => {
+                if (option.indexOf('schema=') === 0) {
+                    schema = option.slice(7);
+                    return false;
+                }
+
+                return !!option.length;
+            });
+
+            db = db.slice(0, db.indexOf('?'));
+        }
+
+        if (schema && !/^[a-z_][a-z0-9_]*$/.test(schema)) throw new Error(`Invalid schema name: ${schema}`);
+
+        let conf;
+        if (/^postgres(ql)?:\/\//.test(db)) {
+            conf = {
+                max: max,
+                connectionString: options.length ? `${db}?${options.join('&')}` : db,
+                idleTimeoutMillis: 30000
+            };
+        } else {
+            conf = {
+                max: max,
+                user: process.env.PGUSER || 'postgres',
+                database: db,
+                idleTimeoutMillis: 30000
+            };
+        }
+
+        if (schema) conf.schema = schema;
+
+        return conf;
+    }
+
+    /**
+     * Append a schema to a --db argument so that both the native modules
+     * and node-pg pools operate within it
+     *
+     * @param {string} db Database name or connection URL
+     * @param {string} schema Postgres schema to use
+     * @return {string}
+     */
+    static withSchema(db, schema) {
+        if (!schema) return db;
+
+        return `${db}${db.indexOf('?') === -1 ? '?' : '&'}schema=${schema}`;
+    }
+
+    /**
+     * Build the native policy for lines of input that cannot be parsed from the
+     * --invalid & --invalid-limit arguments
+     *
+     * @param {string} invalid One of strict or skip, defaults to strict
+     * @param {number|string} limit Number of invalid lines to skip before stopping
+     * @return {string|Object|undefined}
+     */
+    static invalidPolicy(invalid, limit) {
+        if (invalid && ['strict', 'skip'].indexOf(invalid) === -1) throw new Error(`Invalid --invalid policy: ${invalid}`);
+
+        if (limit === undefined || limit === '') return invalid;
+
+        if (invalid === 'strict') throw new Error('--invalid-limit cannot be used with --invalid=strict');
+        if (!/^[0-9]+$/.test(String(limit))) throw new Error(`Invalid --invalid-limit: ${limit}`);
+
+        return { limit: parseInt(limit) };
+    }
+
+    /**
+     * Create a node-pg pool from a Misc.poolConf config, setting the search_path
+     * of each client so that unqualified table names resolve to the configured schema
+     *
+     * @param {Object} conf Pool config from Misc.poolConf
+     * @return {pg.Pool}
+     */
+    static pool(conf) {
+        const pool = new pg.Pool(conf);
+
+        if (conf.schema) {
+            pool.on('connect', (client) => {
+                client.query(`SET search_path TO ${conf.schema}, public`);
+            });
+        }
+
+        return pool;
+    }
 }
 
 module.exports = Misc;
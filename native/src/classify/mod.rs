@@ -1,4 +1,3 @@
-use postgres::{Connection, TlsMode};
 use std::{
     io::{Write, BufWriter},
     collections::HashMap,
@@ -9,10 +8,15 @@ use std::{
 use neon::prelude::*;
 
 use crate::{
+    Error,
+    Progress,
+    progress,
+    mem,
     pg,
     pg::{Table, InputTable},
     Tokens,
-    stream::{GeoStream, AddrStream, PolyStream}
+    Crs,
+    stream::{AddrStream, PolyStream, ErrorSink, Policy, GpkgWriter, gpkg}
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -22,7 +26,14 @@ struct ClassifyArgs {
     buildings: Option<String>,
     parcels: Option<String>,
     input: Option<String>,
-    output: Option<String>
+    output: Option<String>,
+    errors: Option<String>,
+    invalid: Option<Policy>,
+
+    /// Coordinate reference system of every input, EPSG:4326 if not given
+    crs: Option<Crs>,
+
+    backend: Option<mem::Backend>
 }
 
 impl ClassifyArgs {
@@ -33,7 +44,11 @@ impl ClassifyArgs {
             buildings: None,
             parcels: None,
             input: None,
-            output: None
+            output: None,
+            errors: None,
+            invalid: None,
+            crs: None,
+            backend: None
         }
     }
 }
@@ -51,65 +66,185 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         }
     };
 
+    let callback = progress::callback(&mut cx, 1)?;
+
+    match progress::run(&mut cx, callback, move |progress| run(args, progress))? {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn run(mut args: ClassifyArgs, progress: Progress) -> Result<(), Error> {
     let is_hecate = args.hecate.unwrap_or(false);
 
-    let mut output = match args.output {
-        None => panic!("Output file required"),
-        Some(output) => match File::create(output) {
-            Ok(outfile) => BufWriter::new(outfile),
-            Err(err) => panic!("Unable to write to output file: {}", err)
+    let output: Box<dyn Write> = match args.output.take() {
+        None => { return Err(Error::Config(String::from("Output file required"))); },
+        Some(output) => match gpkg::is_gpkg(&output) {
+            true => Box::new(GpkgWriter::new(&output)?),
+            false => Box::new(BufWriter::new(File::create(output)?))
         }
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &args.db).as_str(), TlsMode::None).unwrap();
+    match args.backend.take().unwrap_or_default() {
+        mem::Backend::Postgres => pg_run(args, is_hecate, output, progress),
+        mem::Backend::Memory => mem_run(args, is_hecate, output, progress)
+    }
+}
+
+///
+/// Addresses are classified without regard to their country or language
+///
+fn context(crs: Crs) -> crate::Context {
+    let mut context = crate::Context::new(String::from("xx"), None, Tokens::new(HashMap::new()));
+    context.crs = crs;
+
+    context
+}
+
+fn mem_run(args: ClassifyArgs, is_hecate: bool, mut output: impl Write, progress: Progress) -> Result<(), Error> {
+    let errors = ErrorSink::new(args.errors)?.policy(args.invalid.unwrap_or_default());
+    let crs = args.crs.unwrap_or_default();
+
+    let mut address = mem::Address::new();
+    progress.stage("address");
+    address.input(
+        AddrStream::open(
+            args.input,
+            context(crs.clone()),
+            errors.clone(),
+            progress.clone()
+        )?
+    )?;
+    println!("ok - imported addresses");
+
+    if !is_hecate {
+        // Hecate Addresses will already have ids present
+        // If not hecate, create sequential ids for processing
+        address.seq_id();
+        println!("ok - generated seq id for addresses");
+    }
+
+    let mut buildings = mem::Polygon::new(String::from("buildings"));
+    if let Some(buildings_in) = args.buildings {
+        progress.stage("buildings");
+        buildings.input(PolyStream::open(Some(buildings_in), crs.clone(), errors.clone(), progress.clone())?)?;
+        buildings.index();
+        println!("ok - imported buildings");
+    }
+
+    let mut parcels = mem::Polygon::new(String::from("parcels"));
+    if let Some(parcels_in) = args.parcels {
+        progress.stage("parcels");
+        parcels.input(PolyStream::open(Some(parcels_in), crs.clone(), errors.clone(), progress.clone())?)?;
+        parcels.index();
+        println!("ok - imported parcels");
+    }
+
+    errors.flush()?;
+
+    println!("ok - outputting addresses");
+    progress.stage("output");
+
+    for addr in address.addresses {
+        progress.feature();
+
+        let accuracy = if buildings.intersects(&addr.geom) {
+            "rooftop"
+        } else if parcels.surface_within(&addr.geom, 0.0001) {
+            "parcel"
+        } else {
+            "point"
+        };
+
+        let mut feat = serde_json::Map::new();
+        feat.insert(String::from("id"), serde_json::to_value(addr.id)?);
+        feat.insert(String::from("type"), serde_json::Value::from("Feature"));
+
+        if is_hecate {
+            if addr.props.get("accuracy") == Some(&serde_json::Value::from(accuracy)) {
+                continue;
+            }
+
+            feat.insert(String::from("action"), serde_json::Value::from("modify"));
+            feat.insert(String::from("version"), serde_json::Value::from(addr.version));
+        }
+
+        let mut props = addr.props;
+        props.insert(String::from("accuracy"), serde_json::Value::from(accuracy));
+
+        feat.insert(String::from("properties"), serde_json::Value::Object(props));
+        feat.insert(String::from("geometry"), serde_json::to_value(geojson::Geometry::new(geojson::Value::Point(addr.geom)))?);
+
+        output.write_all(format!("{}\n", serde_json::Value::Object(feat).to_string()).as_bytes())?;
+    }
+
+    output.flush()?;
+
+    Ok(())
+}
+
+fn pg_run(args: ClassifyArgs, is_hecate: bool, mut output: impl Write, progress: Progress) -> Result<(), Error> {
+    let pool = pg::Config::new(&args.db)?.pool()?;
+    let conn = pool.get()?;
+
+    let errors = ErrorSink::new(args.errors)?.policy(args.invalid.unwrap_or_default());
+    let crs = args.crs.unwrap_or_default();
 
     let address = pg::Address::new();
-    address.create(&conn);
+    address.create(&conn)?;
+    progress.stage("address");
     address.input(
         &conn,
-        AddrStream::new(
-            GeoStream::new(args.input),
-            crate::Context::new(String::from("xx"), None, Tokens::new(HashMap::new())),
-            None
-        )
-    );
+        AddrStream::open(
+            args.input,
+            context(crs.clone()),
+            errors.clone(),
+            progress.clone()
+        )?
+    )?;
     println!("ok - imported addresses");
 
     if !is_hecate {
         // Hecate Addresses will already have ids present
         // If not hecate, create sequential ids for processing
-        address.seq_id(&conn);
+        address.seq_id(&conn)?;
         println!("ok - generated seq id for addresses");
     }
 
-    address.index(&conn);
+    address.index(&conn)?;
 
     let buildings = pg::Polygon::new(String::from("buildings"));
-    buildings.create(&conn);
+    buildings.create(&conn)?;
     match args.buildings {
         Some(buildings_in) => {
-            buildings.input(&conn, PolyStream::new(GeoStream::new(Some(buildings_in)), None));
-            buildings.index(&conn);
+            progress.stage("buildings");
+            buildings.input(&conn, PolyStream::open(Some(buildings_in), crs.clone(), errors.clone(), progress.clone())?)?;
+            buildings.index(&conn)?;
             println!("ok - imported buildings");
         },
         None => ()
     };
 
     let parcels = pg::Polygon::new(String::from("parcels"));
-    parcels.create(&conn);
+    parcels.create(&conn)?;
     match args.parcels {
         Some(parcels_in) => {
-            parcels.input(&conn, PolyStream::new(GeoStream::new(Some(parcels_in)), None));
-            parcels.index(&conn);
+            progress.stage("parcels");
+            parcels.input(&conn, PolyStream::open(Some(parcels_in), crs.clone(), errors.clone(), progress.clone())?)?;
+            parcels.index(&conn)?;
             println!("ok - imported parcels");
         },
         None => ()
     };
 
+    errors.flush()?;
+
+    progress.stage("accuracy");
+
     conn.execute("
         ALTER TABLE address
             ADD COLUMN accuracy TEXT
-    ", &[]).unwrap();
+    ", &[])?;
 
     conn.execute("
         UPDATE address
@@ -119,18 +254,18 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                 buildings
             WHERE
                 ST_Intersects(address.geom, buildings.geom)
-    ", &[]).unwrap();
+    ", &[])?;
     println!("ok - calculated accuracy: building");
 
     conn.execute("
         ALTER TABLE parcels
             ADD COLUMN centroid GEOMETRY(POINT, 4326)
-    ", &[]).unwrap();
+    ", &[])?;
 
     conn.execute("
         UPDATE parcels
             SET centroid = ST_PointOnSurface(parcels.geom)
-    ", &[]).unwrap();
+    ", &[])?;
     println!("ok - calculated parcel centroids");
 
     conn.execute("
@@ -142,7 +277,7 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
             WHERE
                 accuracy IS NULL
                 AND ST_DWithin(address.geom, parcels.centroid, 0.0001)
-    ", &[]).unwrap();
+    ", &[])?;
     println!("ok - calculated accuracy: parcel");
 
     conn.execute("
@@ -151,10 +286,10 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                 accuracy = 'point'
             WHERE
                 accuracy IS NULL
-    ", &[]).unwrap();
+    ", &[])?;
     println!("ok - calculated accuracy: point");
 
-    let modified = match is_hecate {
+    let modified: pg::Cursor<serde_json::Value> = match is_hecate {
         true => {
             conn.execute(r#"
                 UPDATE address
@@ -162,7 +297,7 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                         accuracy = NULL
                     WHERE
                         accuracy = props->>'accuracy'
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             conn.execute(r#"
                 UPDATE address
@@ -170,11 +305,11 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                         props = props::JSONB || JSON_Build_Object('accuracy', accuracy)::JSONB
                     WHERE
                         accuracy IS NOT NULL
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             println!("ok - outputting hecate addresses");
 
-            pg::Cursor::new(conn, format!(r#"
+            pg::Cursor::new(conn, r#"
                 SELECT
                     JSON_Build_Object(
                         'id', id,
@@ -188,18 +323,18 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                     address
                 WHERE
                     accuracy IS NOT NULL
-            "#)).unwrap()
+            "#, &[])?
         },
         false => {
             conn.execute(r#"
                 UPDATE address
                     SET
                         props = props::JSONB || JSON_Build_Object('accuracy', accuracy)::JSONB
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             println!("ok - outputting addresses");
 
-            pg::Cursor::new(conn, format!(r#"
+            pg::Cursor::new(conn, r#"
                 SELECT
                     JSON_Build_Object(
                         'id', id,
@@ -209,20 +344,21 @@ pub fn classify(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                     )
                 FROM
                     address
-            "#)).unwrap()
+            "#, &[])?
         }
     };
 
+    progress.stage("output");
+
     for feat in modified {
+        progress.feature();
+
+        let feat: serde_json::Value = feat?;
         let feat = format!("{}\n", feat.to_string());
-        if output.write(feat.as_bytes()).is_err() {
-            panic!("Failed to write to output stream");
-        }
+        output.write_all(feat.as_bytes())?;
     }
 
-    if output.flush().is_err() {
-        panic!("Failed to flush output stream");
-    }
+    output.flush()?;
 
-    Ok(cx.boolean(true))
+    Ok(())
 }
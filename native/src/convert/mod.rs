@@ -4,19 +4,46 @@ use std::convert::From;
 
 use neon::prelude::*;
 
-use super::stream::GeoStream;
+use super::stream::{GeoStream, GpkgWriter, gpkg};
+use crate::Error;
+
+///
+/// Formats that convert reads & writes
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// A single GeoJSON FeatureCollection
+    #[serde(rename = "featurecollection")]
+    Collection,
+
+    /// RFC 8142 GeoJSON Text Sequence, each feature prefixed by an Ascii Record Separator
+    #[serde(rename = "geojsonseq")]
+    Seq,
+
+    /// Line delimited GeoJSON features
+    #[serde(rename = "geojsonld")]
+    Lines
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 struct ConvertArgs {
     input: Option<String>,
     output: Option<String>,
+
+    /// Format of the input, detected if not given
+    input_format: Option<Format>,
+
+    /// Format of the output, a FeatureCollection if not given
+    output_format: Option<Format>
 }
 
 impl ConvertArgs {
     pub fn new() -> Self {
         ConvertArgs {
             input: None,
-            output: None
+            output: None,
+            input_format: None,
+            output_format: None
         }
     }
 }
@@ -34,64 +61,129 @@ pub fn convert(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         }
     };
 
-    let stream = GeoStream::new(args.input);
+    match run(args) {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn run(args: ConvertArgs) -> Result<(), Error> {
+    // GeoJSONSeq is read as lines, with the record separators removed by GeoStream
+    let stream = GeoStream::with_collection(args.input, args.input_format.map(|format| format == Format::Collection))?;
+
+    let format = args.output_format.unwrap_or(Format::Collection);
 
     match args.output {
+        Some(ref outpath) if gpkg::is_gpkg(outpath) => match args.output_format {
+            None => convert_gpkg(stream, GpkgWriter::new(outpath)?),
+            Some(_) => Err(Error::Config(String::from("An output format cannot be given for GeoPackage output")))
+        },
         Some(outpath) => {
-            let outfile = match File::create(outpath) {
-                Ok(outfile) => outfile,
-                Err(err) => { panic!("Unable to write to output file: {}", err); }
-            };
+            let outfile = File::create(outpath)?;
 
-            convert_stream(stream, BufWriter::new(outfile))
+            convert_stream(stream, BufWriter::new(outfile), format)
         },
-        None => convert_stream(stream, io::stdout().lock())
+        None => convert_stream(stream, io::stdout().lock(), format)
+    }
+}
+
+///
+/// Return the features of a GeoJSON object, wrapping a bare geometry as a feature
+///
+fn features(geo: geojson::GeoJson) -> Vec<geojson::Feature> {
+    match geo {
+        geojson::GeoJson::Geometry(geom) => vec![geojson::Feature {
+            id: None,
+            bbox: None,
+            geometry: Some(geom),
+            properties: None,
+            foreign_members: None
+        }],
+        geojson::GeoJson::Feature(feat) => vec![feat],
+        geojson::GeoJson::FeatureCollection(fc) => fc.features
+    }
+}
+
+///
+/// Write each feature of a stream to a GeoPackage layer
+///
+fn convert_gpkg(stream: GeoStream, mut writer: GpkgWriter) -> Result<(), Error> {
+    for geo in stream {
+        for feat in features(geo?) {
+            writer.feature(feat)?;
+        }
     }
 
-    Ok(cx.boolean(true))
+    writer.commit()
 }
 
-fn convert_stream(stream: GeoStream, mut sink: impl Write) {
-    if sink.write(String::from("{ \"type\": \"FeatureCollection\", \"features\": [\n").as_bytes()).is_err() { panic!("Failed to write to output stream"); };
+///
+/// Write each feature of a stream in the given format, one feature at a time
+///
+fn convert_stream(stream: GeoStream, mut sink: impl Write, format: Format) -> Result<(), Error> {
+    if format == Format::Collection {
+        sink.write_all(String::from("{ \"type\": \"FeatureCollection\", \"features\": [\n").as_bytes())?;
+    }
+
     let mut first = true;
 
     for geo in stream {
-        let line = match geo {
-            geojson::GeoJson::Geometry(geom) => {
-                geojson::GeoJson::from(geojson::Feature {
-                    id: None,
-                    bbox: None,
-                    geometry: Some(geom),
-                    properties: None,
-                    foreign_members: None
-                }).to_string()
-            },
-            geojson::GeoJson::Feature(_) => geo.to_string(),
-            geojson::GeoJson::FeatureCollection(fc) => {
-                let mut line = String::new();
-                let mut fcfirst = true;
-
-                for feat in fc.features {
-                    if fcfirst {
-                        line = format!("{}", geojson::GeoJson::from(feat).to_string());
-                        fcfirst = false;
-                    } else {
-                        line = format!("{},\n{}", line, geojson::GeoJson::from(feat).to_string());
-                    }
-                }
-                line
-            }
-        };
+        for feat in features(geo?) {
+            let feat = geojson::GeoJson::from(feat).to_string();
+
+            match format {
+                Format::Collection => match first {
+                    true => sink.write_all(feat.as_bytes())?,
+                    false => sink.write_all(format!("\n,{}", feat).as_bytes())?
+                },
+                Format::Seq => sink.write_all(format!("\u{001E}{}\n", feat).as_bytes())?,
+                Format::Lines => sink.write_all(format!("{}\n", feat).as_bytes())?
+            };
 
-        if first {
-            if sink.write(format!("{}", line).as_bytes()).is_err() { panic!("Failed to write to output stream"); };
             first = false;
-        } else {
-            if sink.write(format!("\n,{}", line).as_bytes()).is_err() { panic!("Failed to write to output stream"); };
         }
     }
 
-    if sink.write(String::from("\n]}\n").as_bytes()).is_err() { panic!("Failed to write to output stream"); };
+    if format == Format::Collection {
+        sink.write_all(String::from("\n]}\n").as_bytes())?;
+    }
+
+    sink.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEATURES: &str = "{ \"type\": \"Feature\", \"properties\": { \"it\": 1 }, \"geometry\": null }\n{ \"type\": \"Point\", \"coordinates\": [1, 2] }\n";
+
+    fn convert(name: &str, input: &str, input_format: Option<Format>, output_format: Format) -> String {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, input).unwrap();
+
+        let mut output = Vec::new();
+        convert_stream(GeoStream::with_collection(Some(path.to_str().unwrap().to_string()), input_format.map(|format| format == Format::Collection)).unwrap(), &mut output, output_format).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_convert_formats() {
+        let collection = convert("pt2itp-convert-collection", FEATURES, None, Format::Collection);
+        assert_eq!(collection, "{ \"type\": \"FeatureCollection\", \"features\": [\n{\"geometry\":null,\"properties\":{\"it\":1},\"type\":\"Feature\"}\n,{\"geometry\":{\"coordinates\":[1.0,2.0],\"type\":\"Point\"},\"properties\":null,\"type\":\"Feature\"}\n]}\n");
+
+        let lines = convert("pt2itp-convert-lines", &collection, Some(Format::Collection), Format::Lines);
+        assert_eq!(lines, "{\"geometry\":null,\"properties\":{\"it\":1},\"type\":\"Feature\"}\n{\"geometry\":{\"coordinates\":[1.0,2.0],\"type\":\"Point\"},\"properties\":null,\"type\":\"Feature\"}\n");
+
+        let seq = convert("pt2itp-convert-seq", &lines, Some(Format::Lines), Format::Seq);
+        assert_eq!(seq, "\u{001E}{\"geometry\":null,\"properties\":{\"it\":1},\"type\":\"Feature\"}\n\u{001E}{\"geometry\":{\"coordinates\":[1.0,2.0],\"type\":\"Point\"},\"properties\":null,\"type\":\"Feature\"}\n");
 
-    if sink.flush().is_err() { panic!("Failed to flush output stream"); }
+        assert_eq!(convert("pt2itp-convert-roundtrip", &seq, Some(Format::Seq), Format::Collection), collection);
+
+        assert_eq!(convert("pt2itp-convert-empty", "", None, Format::Collection), "{ \"type\": \"FeatureCollection\", \"features\": [\n\n]}\n");
+    }
 }
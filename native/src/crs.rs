@@ -0,0 +1,610 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Metres per US survey foot
+const US_FOOT: f64 = 1200.0 / 3937.0;
+
+/// Radius of the sphere used by Web Mercator
+const MERCATOR_RADIUS: f64 = 6378137.0;
+
+/// Furthest extent of Web Mercator coordinates, the projection of 180deg
+const MERCATOR_EXTENT: f64 = 20037508.342789244;
+
+///
+/// Coordinate reference system of an input, able to reproject
+/// coordinates in it to WGS84 longitude/latitude (EPSG:4326)
+///
+/// North American (NAD83) & European (ETRS89) datums are treated as equal to
+/// WGS84, which they are within a metre or two, so no datum shift is applied
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crs {
+    /// Name of the CRS as given, ie: EPSG:26918
+    name: String,
+
+    projection: Projection,
+
+    /// Semi-major axis & flattening of the ellipsoid
+    ellipsoid: (f64, f64),
+
+    /// Metres per unit of the projected coordinates
+    unit: f64
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Projection {
+    /// Longitude/latitude, requiring no reprojection
+    Geographic,
+
+    /// Spherical Mercator as used by web maps, EPSG:3857
+    WebMercator,
+
+    /// Transverse Mercator, including UTM & a number of US State Plane zones
+    TransverseMercator {
+        lat_0: f64,
+        lon_0: f64,
+        k_0: f64,
+        x_0: f64,
+        y_0: f64
+    },
+
+    /// Lambert Conformal Conic with two standard parallels, used by most US State Plane zones
+    LambertConformalConic {
+        lat_1: f64,
+        lat_2: f64,
+        lat_0: f64,
+        lon_0: f64,
+        x_0: f64,
+        y_0: f64
+    }
+}
+
+const GRS80: (f64, f64) = (6378137.0, 1.0 / 298.257222101);
+const WGS84: (f64, f64) = (6378137.0, 1.0 / 298.257223563);
+
+impl Default for Crs {
+    fn default() -> Self {
+        Crs {
+            name: String::from("EPSG:4326"),
+            projection: Projection::Geographic,
+            ellipsoid: WGS84,
+            unit: 1.0
+        }
+    }
+}
+
+impl Crs {
+    ///
+    /// Parse a CRS from an EPSG code, ie: EPSG:26918 or 26918, an OGC URN
+    /// or a PROJ definition such as +proj=lcc +lat_1=... +units=us-ft
+    ///
+    pub fn new(name: &str) -> Result<Self, String> {
+        let trimmed = name.trim();
+
+        let mut crs = if trimmed.starts_with('+') {
+            Crs::proj(trimmed)?
+        } else {
+            let upper = trimmed.to_uppercase();
+
+            if upper.ends_with("CRS84") {
+                Crs::default()
+            } else {
+                let code = upper.rsplit(':').next().unwrap_or("");
+
+                match code.parse::<u32>() {
+                    Ok(code) => Crs::epsg(code)?,
+                    Err(_) => { return Err(format!("Unsupported CRS: {}", name)); }
+                }
+            }
+        };
+
+        crs.name = String::from(trimmed);
+
+        Ok(crs)
+    }
+
+    pub fn is_geographic(&self) -> bool {
+        self.projection == Projection::Geographic
+    }
+
+    fn epsg(code: u32) -> Result<Self, String> {
+        let geographic = |ellipsoid| Crs {
+            name: String::new(),
+            projection: Projection::Geographic,
+            ellipsoid: ellipsoid,
+            unit: 1.0
+        };
+
+        Ok(match code {
+            4326 => geographic(WGS84),
+            4269 | 4258 => geographic(GRS80),
+            3857 | 3785 | 900913 | 102100 => Crs {
+                name: String::new(),
+                projection: Projection::WebMercator,
+                ellipsoid: WGS84,
+                unit: 1.0
+            },
+            32601..=32660 => Crs::utm(code - 32600, false, WGS84),
+            32701..=32760 => Crs::utm(code - 32700, true, WGS84),
+            26901..=26923 => Crs::utm(code - 26900, false, GRS80),
+            25828..=25838 => Crs::utm(code - 25800, false, GRS80),
+            code => match STATE_PLANE.iter().find(|zone| zone.0 == code || zone.1 == code) {
+                Some(zone) => Crs {
+                    name: String::new(),
+                    projection: zone.2.clone(),
+                    ellipsoid: GRS80,
+                    unit: match zone.1 == code {
+                        true => US_FOOT,
+                        false => 1.0
+                    }
+                },
+                None => { return Err(format!("Unsupported CRS: EPSG:{}", code)); }
+            }
+        })
+    }
+
+    fn utm(zone: u32, south: bool, ellipsoid: (f64, f64)) -> Self {
+        Crs {
+            name: String::new(),
+            projection: Projection::TransverseMercator {
+                lat_0: 0.0,
+                lon_0: zone as f64 * 6.0 - 183.0,
+                k_0: 0.9996,
+                x_0: 500000.0,
+                y_0: match south {
+                    true => 10000000.0,
+                    false => 0.0
+                }
+            },
+            ellipsoid: ellipsoid,
+            unit: 1.0
+        }
+    }
+
+    ///
+    /// Parse the subset of PROJ definitions for the projections supported
+    ///
+    fn proj(def: &str) -> Result<Self, String> {
+        let mut params = std::collections::HashMap::new();
+
+        for param in def.split_whitespace() {
+            let param = param.trim_start_matches('+');
+
+            match param.find('=') {
+                Some(pos) => params.insert(&param[..pos], &param[pos + 1..]),
+                None => params.insert(param, "")
+            };
+        }
+
+        let num = |key: &str, default: Option<f64>| -> Result<f64, String> {
+            match params.get(key) {
+                Some(value) => value.parse::<f64>().map_err(|_| format!("Invalid +{} in CRS: {}", key, def)),
+                None => default.ok_or_else(|| format!("Missing +{} in CRS: {}", key, def))
+            }
+        };
+
+        let ellipsoid = match params.get("ellps").or(params.get("datum")) {
+            None => WGS84,
+            Some(&"WGS84") => WGS84,
+            Some(&"GRS80") | Some(&"NAD83") => GRS80,
+            Some(ellps) => { return Err(format!("Unsupported ellipsoid {} in CRS: {}", ellps, def)); }
+        };
+
+        let unit = match params.get("units") {
+            None | Some(&"m") => 1.0,
+            Some(&"us-ft") => US_FOOT,
+            Some(&"ft") => 0.3048,
+            Some(units) => { return Err(format!("Unsupported units {} in CRS: {}", units, def)); }
+        };
+
+        let projection = match params.get("proj") {
+            Some(&"longlat") | Some(&"latlong") => Projection::Geographic,
+            Some(&"merc") if params.get("a") == Some(&"6378137") || params.get("R") == Some(&"6378137") => Projection::WebMercator,
+            Some(&"utm") => {
+                let zone = num("zone", None)? as u32;
+
+                if zone < 1 || zone > 60 {
+                    return Err(format!("Invalid +zone in CRS: {}", def));
+                }
+
+                return Ok(Crs::utm(zone, params.contains_key("south"), ellipsoid));
+            },
+            Some(&"tmerc") => Projection::TransverseMercator {
+                lat_0: num("lat_0", Some(0.0))?,
+                lon_0: num("lon_0", Some(0.0))?,
+                k_0: num("k_0", None).or(num("k", Some(1.0)))?,
+                x_0: num("x_0", Some(0.0))?,
+                y_0: num("y_0", Some(0.0))?
+            },
+            Some(&"lcc") => {
+                let lat_1 = num("lat_1", None)?;
+
+                Projection::LambertConformalConic {
+                    lat_1: lat_1,
+                    lat_2: num("lat_2", Some(lat_1))?,
+                    lat_0: num("lat_0", Some(lat_1))?,
+                    lon_0: num("lon_0", Some(0.0))?,
+                    x_0: num("x_0", Some(0.0))?,
+                    y_0: num("y_0", Some(0.0))?
+                }
+            },
+            _ => { return Err(format!("Unsupported CRS: {}", def)); }
+        };
+
+        Ok(Crs {
+            name: String::new(),
+            projection: projection,
+            ellipsoid: ellipsoid,
+            unit: unit
+        })
+    }
+
+    ///
+    /// Reproject a single coordinate to WGS84 longitude/latitude
+    ///
+    pub fn unproject(&self, x: f64, y: f64) -> Result<(f64, f64), String> {
+        if !x.is_finite() || !y.is_finite() {
+            return Err(format!("Invalid coordinates in {}: [{}, {}]", self.name, x, y));
+        }
+
+        let (x, y) = (x * self.unit, y * self.unit);
+        let (a, f) = self.ellipsoid;
+        let e2 = f * (2.0 - f);
+        let e = e2.sqrt();
+
+        let (lon, lat) = match self.projection {
+            Projection::Geographic => (x, y),
+            Projection::WebMercator => {
+                if x.abs() > MERCATOR_EXTENT || y.abs() > MERCATOR_EXTENT {
+                    return Err(format!("Coordinates outside the valid range of {}: [{}, {}]", self.name, x, y));
+                }
+
+                (
+                    // Clamped as the projection of the extent is not exactly 180deg
+                    (x / MERCATOR_RADIUS).to_degrees().max(-180.0).min(180.0),
+                    (2.0 * (y / MERCATOR_RADIUS).exp().atan() - FRAC_PI_2).to_degrees()
+                )
+            },
+            Projection::TransverseMercator { lat_0, lon_0, k_0, x_0, y_0 } => {
+                let ep2 = e2 / (1.0 - e2);
+                let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+                let m = meridian(a, e2, lat_0.to_radians()) + (y - y_0) / k_0;
+                let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+
+                let phi = mu
+                    + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+                    + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+                    + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+                    + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+                let c = ep2 * phi.cos().powi(2);
+                let t = phi.tan().powi(2);
+                let n = a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+                let r = a * (1.0 - e2) / (1.0 - e2 * phi.sin().powi(2)).powf(1.5);
+                let d = (x - x_0) / (n * k_0);
+
+                let lat = phi - (n * phi.tan() / r) * (
+                    d.powi(2) / 2.0
+                    - (5.0 + 3.0 * t + 10.0 * c - 4.0 * c.powi(2) - 9.0 * ep2) * d.powi(4) / 24.0
+                    + (61.0 + 90.0 * t + 298.0 * c + 45.0 * t.powi(2) - 252.0 * ep2 - 3.0 * c.powi(2)) * d.powi(6) / 720.0
+                );
+
+                let lon = (
+                    d
+                    - (1.0 + 2.0 * t + c) * d.powi(3) / 6.0
+                    + (5.0 - 2.0 * c + 28.0 * t - 3.0 * c.powi(2) + 8.0 * ep2 + 24.0 * t.powi(2)) * d.powi(5) / 120.0
+                ) / phi.cos();
+
+                (lon_0 + lon.to_degrees(), lat.to_degrees())
+            },
+            Projection::LambertConformalConic { lat_1, lat_2, lat_0, lon_0, x_0, y_0 } => {
+                let (lat_1, lat_2, lat_0) = (lat_1.to_radians(), lat_2.to_radians(), lat_0.to_radians());
+
+                let m = |phi: f64| phi.cos() / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+                let t = |phi: f64| (FRAC_PI_4 - phi / 2.0).tan() / ((1.0 - e * phi.sin()) / (1.0 + e * phi.sin())).powf(e / 2.0);
+
+                let n = match (lat_1 - lat_2).abs() < 1e-10 {
+                    true => lat_1.sin(),
+                    false => (m(lat_1).ln() - m(lat_2).ln()) / (t(lat_1).ln() - t(lat_2).ln())
+                };
+
+                let big_f = m(lat_1) / (n * t(lat_1).powf(n));
+                let rho_0 = a * big_f * t(lat_0).powf(n);
+
+                let dx = x - x_0;
+                let dy = rho_0 - (y - y_0);
+
+                let rho = n.signum() * (dx.powi(2) + dy.powi(2)).sqrt();
+                let theta = (n.signum() * dx).atan2(n.signum() * dy);
+                let ts = (rho / (a * big_f)).powf(1.0 / n);
+
+                let mut phi = FRAC_PI_2 - 2.0 * ts.atan();
+                for _ in 0..15 {
+                    let next = FRAC_PI_2 - 2.0 * (ts * ((1.0 - e * phi.sin()) / (1.0 + e * phi.sin())).powf(e / 2.0)).atan();
+
+                    if (next - phi).abs() < 1e-12 {
+                        phi = next;
+                        break;
+                    }
+
+                    phi = next;
+                }
+
+                (lon_0 + (theta / n).to_degrees(), phi.to_degrees())
+            }
+        };
+
+        if !lon.is_finite() || !lat.is_finite() || lon < -180.0 || lon > 180.0 || lat < -90.0 || lat > 90.0 {
+            return Err(format!("Coordinates outside the valid range of {}: [{}, {}]", self.name, x / self.unit, y / self.unit));
+        }
+
+        Ok((lon, lat))
+    }
+
+    ///
+    /// Reproject the geometry of a GeoJSON feature or geometry to WGS84
+    ///
+    /// Geographic coordinates are passed through unchanged, but are still
+    /// rejected if they are outside of the valid lon/lat range
+    ///
+    pub fn feature(&self, geojson: geojson::GeoJson) -> Result<geojson::GeoJson, String> {
+        Ok(match geojson {
+            geojson::GeoJson::Feature(mut feat) => {
+                feat.bbox = None;
+
+                if let Some(ref mut geom) = feat.geometry {
+                    self.geometry(geom)?;
+                }
+
+                geojson::GeoJson::Feature(feat)
+            },
+            geojson::GeoJson::Geometry(mut geom) => {
+                self.geometry(&mut geom)?;
+
+                geojson::GeoJson::Geometry(geom)
+            },
+            geojson::GeoJson::FeatureCollection(mut fc) => {
+                fc.bbox = None;
+
+                for feat in fc.features.iter_mut() {
+                    feat.bbox = None;
+
+                    if let Some(ref mut geom) = feat.geometry {
+                        self.geometry(geom)?;
+                    }
+                }
+
+                geojson::GeoJson::FeatureCollection(fc)
+            }
+        })
+    }
+
+    fn geometry(&self, geom: &mut geojson::Geometry) -> Result<(), String> {
+        geom.bbox = None;
+
+        match geom.value {
+            geojson::Value::Point(ref mut pt) => self.coord(pt)?,
+            geojson::Value::MultiPoint(ref mut pts) | geojson::Value::LineString(ref mut pts) => self.coords(pts)?,
+            geojson::Value::MultiLineString(ref mut lns) | geojson::Value::Polygon(ref mut lns) => {
+                for ln in lns.iter_mut() {
+                    self.coords(ln)?;
+                }
+            },
+            geojson::Value::MultiPolygon(ref mut pys) => {
+                for py in pys.iter_mut() {
+                    for ring in py.iter_mut() {
+                        self.coords(ring)?;
+                    }
+                }
+            },
+            geojson::Value::GeometryCollection(ref mut geoms) => {
+                for geom in geoms.iter_mut() {
+                    self.geometry(geom)?;
+                }
+            }
+        };
+
+        Ok(())
+    }
+
+    fn coords(&self, coords: &mut Vec<Vec<f64>>) -> Result<(), String> {
+        for coord in coords.iter_mut() {
+            self.coord(coord)?;
+        }
+
+        Ok(())
+    }
+
+    fn coord(&self, coord: &mut Vec<f64>) -> Result<(), String> {
+        if coord.len() < 2 {
+            return Err(String::from("Geometry must have 2 coordinates"));
+        }
+
+        let (lon, lat) = self.unproject(coord[0], coord[1])?;
+
+        coord[0] = lon;
+        coord[1] = lat;
+
+        Ok(())
+    }
+}
+
+///
+/// Distance along the meridian from the equator to the given latitude
+///
+fn meridian(a: f64, e2: f64, phi: f64) -> f64 {
+    a * (
+        (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0) * phi
+        - (3.0 * e2 / 8.0 + 3.0 * e2.powi(2) / 32.0 + 45.0 * e2.powi(3) / 1024.0) * (2.0 * phi).sin()
+        + (15.0 * e2.powi(2) / 256.0 + 45.0 * e2.powi(3) / 1024.0) * (4.0 * phi).sin()
+        - (35.0 * e2.powi(3) / 3072.0) * (6.0 * phi).sin()
+    )
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Serialize for Crs {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Crs {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        Crs::new(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+fn lcc(lat_1: f64, lat_2: f64, lat_0: f64, lon_0: f64, x_0: f64, y_0: f64) -> Projection {
+    Projection::LambertConformalConic { lat_1, lat_2, lat_0, lon_0, x_0, y_0 }
+}
+
+fn tmerc(lat_0: f64, lon_0: f64, k_0: f64, x_0: f64, y_0: f64) -> Projection {
+    Projection::TransverseMercator { lat_0, lon_0, k_0, x_0, y_0 }
+}
+
+lazy_static! {
+    ///
+    /// NAD83 US State Plane zones as (metre EPSG code, US survey foot EPSG code, projection),
+    /// with false eastings & northings in metres. Zones not listed can be given as a PROJ definition
+    ///
+    static ref STATE_PLANE: Vec<(u32, u32, Projection)> = vec![
+        (26941, 2225, lcc(41.66666666666666, 40.0, 39.33333333333334, -122.0, 2000000.0, 500000.0)), // California 1
+        (26942, 2226, lcc(39.83333333333334, 38.33333333333334, 37.66666666666666, -122.0, 2000000.0, 500000.0)), // California 2
+        (26943, 2227, lcc(38.43333333333333, 37.06666666666667, 36.5, -120.5, 2000000.0, 500000.0)), // California 3
+        (26944, 2228, lcc(37.25, 36.0, 35.33333333333334, -119.0, 2000000.0, 500000.0)), // California 4
+        (26945, 2229, lcc(35.46666666666667, 34.03333333333333, 33.5, -118.0, 2000000.0, 500000.0)), // California 5
+        (26946, 2230, lcc(33.88333333333333, 32.78333333333333, 32.16666666666666, -116.25, 2000000.0, 500000.0)), // California 6
+        (26954, 2232, lcc(39.75, 38.45, 37.83333333333334, -105.5, 914401.8289, 304800.6096)), // Colorado Central
+        (26958, 2236, tmerc(24.33333333333333, -81.0, 0.999941177, 200000.0, 0.0)), // Florida East
+        (26959, 2237, tmerc(24.33333333333333, -82.0, 0.999941177, 200000.0, 0.0)), // Florida West
+        (26966, 2239, tmerc(30.0, -82.16666666666667, 0.9999, 200000.0, 0.0)), // Georgia East
+        (26967, 2240, tmerc(30.0, -84.16666666666667, 0.9999, 700000.0, 0.0)), // Georgia West
+        (26971, 3435, tmerc(36.66666666666666, -88.33333333333333, 0.999975, 300000.0, 0.0)), // Illinois East
+        (26972, 3436, tmerc(36.66666666666666, -90.16666666666667, 0.999941177, 700000.0, 0.0)), // Illinois West
+        (26985, 2248, lcc(39.45, 38.3, 37.66666666666666, -77.0, 400000.0, 0.0)), // Maryland
+        (26986, 2249, lcc(42.68333333333333, 41.71666666666667, 41.0, -71.5, 200000.0, 750000.0)), // Massachusetts Mainland
+        (32111, 3424, tmerc(38.83333333333334, -74.5, 0.9999, 150000.0, 0.0)), // New Jersey
+        (32115, 2260, tmerc(38.83333333333334, -74.5, 0.9999, 150000.0, 0.0)), // New York East
+        (32118, 2263, lcc(41.03333333333333, 40.66666666666666, 40.16666666666666, -74.0, 300000.0, 0.0)), // New York Long Island
+        (32119, 2264, lcc(36.16666666666666, 34.33333333333334, 33.75, -79.0, 609601.22, 0.0)), // North Carolina
+        (32122, 3734, lcc(41.7, 40.43333333333333, 39.66666666666666, -82.5, 600000.0, 0.0)), // Ohio North
+        (32123, 3735, lcc(40.03333333333333, 38.73333333333333, 38.0, -82.5, 600000.0, 0.0)), // Ohio South
+        (32128, 2271, lcc(41.95, 40.88333333333333, 40.16666666666666, -77.75, 600000.0, 0.0)), // Pennsylvania North
+        (32129, 2272, lcc(40.96666666666667, 39.93333333333333, 39.33333333333334, -77.75, 600000.0, 0.0)), // Pennsylvania South
+        (32137, 2275, lcc(36.18333333333333, 34.65, 34.0, -101.5, 200000.0, 1000000.0)), // Texas North
+        (32138, 2276, lcc(33.96666666666667, 32.13333333333333, 31.66666666666667, -98.5, 600000.0, 2000000.0)), // Texas North Central
+        (32139, 2277, lcc(31.88333333333333, 30.11666666666667, 29.66666666666667, -100.3333333333333, 700000.0, 3000000.0)), // Texas Central
+        (32140, 2278, lcc(30.28333333333333, 28.38333333333333, 27.83333333333333, -99.0, 600000.0, 4000000.0)), // Texas South Central
+        (32141, 2279, lcc(27.83333333333333, 26.16666666666667, 25.66666666666667, -98.5, 300000.0, 5000000.0)), // Texas South
+        (32146, 2283, lcc(39.2, 38.03333333333333, 37.66666666666666, -78.5, 3500000.0, 2000000.0)), // Virginia North
+        (32147, 2284, lcc(37.96666666666667, 36.76666666666667, 36.33333333333334, -78.5, 3500000.0, 1000000.0)), // Virginia South
+        (32148, 2285, lcc(48.73333333333333, 47.5, 47.0, -120.8333333333333, 500000.0, 0.0)), // Washington North
+        (32149, 2286, lcc(47.33333333333334, 45.83333333333334, 45.33333333333334, -120.5, 500000.0, 0.0)) // Washington South
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: (f64, f64), expected: (f64, f64)) {
+        assert!((actual.0 - expected.0).abs() < 1e-6 && (actual.1 - expected.1).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_crs_new() {
+        assert!(Crs::new("EPSG:4326").unwrap().is_geographic());
+        assert!(Crs::new("urn:ogc:def:crs:OGC:1.3:CRS84").unwrap().is_geographic());
+        assert!(!Crs::new("urn:ogc:def:crs:EPSG::3857").unwrap().is_geographic());
+        assert_eq!(Crs::new("26918").unwrap().to_string(), "26918");
+
+        assert_eq!(Crs::new("EPSG:2000").unwrap_err(), "Unsupported CRS: EPSG:2000");
+        assert_eq!(Crs::new("Mercator").unwrap_err(), "Unsupported CRS: Mercator");
+        assert_eq!(Crs::new("+proj=lcc +lat_0=1").unwrap_err(), "Missing +lat_1 in CRS: +proj=lcc +lat_0=1");
+
+        assert_eq!(serde_json::from_str::<Crs>("\"EPSG:32618\"").unwrap(), Crs::new("EPSG:32618").unwrap());
+        assert_eq!(serde_json::to_string(&Crs::new("EPSG:32618").unwrap()).unwrap(), "\"EPSG:32618\"");
+    }
+
+    #[test]
+    fn test_crs_web_mercator() {
+        let crs = Crs::new("EPSG:3857").unwrap();
+
+        assert_near(crs.unproject(0.0, 0.0).unwrap(), (0.0, 0.0));
+        assert_near(crs.unproject(MERCATOR_EXTENT / 2.0, 5621521.486192066).unwrap(), (90.0, 45.0));
+        assert_near(crs.unproject(-MERCATOR_EXTENT, 0.0).unwrap(), (-180.0, 0.0));
+
+        assert!(crs.unproject(0.0, 30000000.0).is_err());
+    }
+
+    #[test]
+    fn test_crs_utm() {
+        // The equator on the central meridian & 3deg west of it
+        assert_near(Crs::new("EPSG:32631").unwrap().unproject(500000.0, 0.0).unwrap(), (3.0, 0.0));
+        assert_near(Crs::new("EPSG:32631").unwrap().unproject(166021.4431, 0.0).unwrap(), (0.0, 0.0));
+        assert_near(Crs::new("+proj=utm +zone=31").unwrap().unproject(166021.4431, 0.0).unwrap(), (0.0, 0.0));
+
+        let (lon, lat) = Crs::new("EPSG:32718").unwrap().unproject(500000.0, 10000000.0).unwrap();
+        assert_near((lon, lat), (-75.0, 0.0));
+
+        assert!(Crs::new("EPSG:32618").unwrap().unproject(500000.0, 100000000.0).is_err());
+        assert!(Crs::new("EPSG:32618").unwrap().unproject(std::f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_crs_state_plane() {
+        // The origin of each zone is at its false easting & northing
+        assert_near(Crs::new("EPSG:26985").unwrap().unproject(400000.0, 0.0).unwrap(), (-77.0, 37.66666666666666));
+        assert_near(Crs::new("EPSG:2248").unwrap().unproject(400000.0 / US_FOOT, 0.0).unwrap(), (-77.0, 37.66666666666666));
+        assert_near(Crs::new("EPSG:3435").unwrap().unproject(300000.0 / US_FOOT, 0.0).unwrap(), (-88.33333333333333, 36.66666666666666));
+
+        assert_near(
+            Crs::new("+proj=lcc +lat_1=39.45 +lat_2=38.3 +lat_0=37.66666666666666 +lon_0=-77 +x_0=400000 +y_0=0 +ellps=GRS80 +units=us-ft").unwrap().unproject(400000.0 / US_FOOT, 0.0).unwrap(),
+            (-77.0, 37.66666666666666)
+        );
+
+        // North of the origin on the central meridian
+        let (lon, lat) = Crs::new("EPSG:26985").unwrap().unproject(400000.0, 136905.3014).unwrap();
+        assert!((lon + 77.0).abs() < 1e-9);
+        assert!((lat - 38.9).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_crs_feature() {
+        let feat: geojson::GeoJson = r#"{ "type": "Feature", "bbox": [0, 0, 1, 1], "properties": {}, "geometry": { "type": "LineString", "coordinates": [[500000, 0], [500000, 0, 10]] } }"#.parse().unwrap();
+
+        match Crs::new("EPSG:32631").unwrap().feature(feat).unwrap() {
+            geojson::GeoJson::Feature(feat) => {
+                assert_eq!(feat.bbox, None);
+
+                match feat.geometry.unwrap().value {
+                    geojson::Value::LineString(ln) => {
+                        assert_near((ln[0][0], ln[0][1]), (3.0, 0.0));
+                        assert_eq!(ln[1].len(), 3);
+                    },
+                    _ => panic!("Not a LineString")
+                }
+            },
+            _ => panic!("Not a Feature")
+        }
+
+        let feat: geojson::GeoJson = r#"{ "type": "Feature", "properties": {}, "geometry": { "type": "Point", "coordinates": [0, 30000000] } }"#.parse().unwrap();
+        assert_eq!(Crs::new("EPSG:3857").unwrap().feature(feat).unwrap_err(), "Coordinates outside the valid range of EPSG:3857: [0, 30000000]");
+
+        let feat: geojson::GeoJson = r#"{ "type": "Feature", "properties": {}, "geometry": { "type": "Point", "coordinates": [-77.0, 38.9] } }"#.parse().unwrap();
+        assert_eq!(Crs::default().feature(feat.clone()).unwrap(), feat);
+
+        let feat: geojson::GeoJson = r#"{ "type": "Feature", "properties": {}, "geometry": { "type": "LineString", "coordinates": [[-77.0, 38.9], [500.0, 38.9]] } }"#.parse().unwrap();
+        assert_eq!(Crs::default().feature(feat).unwrap_err(), "Coordinates outside the valid range of EPSG:4326: [500, 38.9]");
+    }
+}
@@ -1,5 +1,4 @@
 use std::convert::From;
-use postgres::{Connection, TlsMode};
 use std::collections::HashMap;
 use std::thread;
 use std::fs::File;
@@ -8,9 +7,13 @@ use std::io::{BufWriter, Write};
 use neon::prelude::*;
 
 use crate::{
+    Error,
     Address,
-    types::hecate,
-    stream::{GeoStream, AddrStream, PolyStream}
+    Progress,
+    progress,
+    mem,
+    types::{hecate, Grammar},
+    stream::{AddrStream, PolyStream, ErrorSink, Policy, GpkgWriter, gpkg}
 };
 
 use super::pg;
@@ -23,8 +26,11 @@ struct DedupeArgs {
     buildings: Option<String>,
     input: Option<String>,
     output: Option<String>,
+    errors: Option<String>,
+    invalid: Option<Policy>,
     languages: Option<String>,
-    hecate: Option<bool>
+    hecate: Option<bool>,
+    backend: Option<mem::Backend>
 }
 
 impl DedupeArgs {
@@ -35,8 +41,11 @@ impl DedupeArgs {
             buildings: None,
             input: None,
             output: None,
+            errors: None,
+            invalid: None,
             languages: None,
-            hecate: None
+            hecate: None,
+            backend: None
         }
     }
 }
@@ -54,38 +63,105 @@ pub fn dedupe(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         }
     };
 
-    let is_hecate = args.hecate.unwrap_or(false);
+    let callback = progress::callback(&mut cx, 1)?;
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &args.db).as_str(), TlsMode::None).unwrap();
+    match progress::run(&mut cx, callback, move |progress| run(args, progress))? {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn run(mut args: DedupeArgs, progress: Progress) -> Result<(), Error> {
+    let is_hecate = args.hecate.unwrap_or(false);
 
-    let context = match args.context {
+    let context = match args.context.take() {
         Some(context) => crate::Context::from(context),
         None => crate::Context::new(String::from(""), None, crate::Tokens::new(HashMap::new()))
     };
 
+    match args.backend.take().unwrap_or_default() {
+        mem::Backend::Postgres => pg_run(args, is_hecate, context, progress),
+        mem::Backend::Memory => mem_run(args, is_hecate, context, progress)
+    }
+}
+
+fn mem_run(args: DedupeArgs, is_hecate: bool, context: crate::Context, progress: Progress) -> Result<(), Error> {
+    let errors = ErrorSink::new(args.errors)?.policy(args.invalid.unwrap_or_default());
+
+    let mut address = mem::Address::new();
+    progress.stage("address");
+    address.input(AddrStream::open(args.input, context, errors.clone(), progress.clone())?)?;
+    errors.flush()?;
+
+    progress.stage("address_index");
+
+    if !is_hecate {
+        // Hecate Addresses will already have ids present
+        // If not hecate, create sequential ids for processing
+        address.seq_id();
+    }
+
+    address.index();
+
+    // Buildings are not yet used to determine duplicates
+    // so they are not loaded when running in memory
+
+    progress.stage("dedupe");
+
+    let mut results = Vec::new();
+
+    for feat in address.addresses.iter() {
+        let dup_feats: Vec<Address> = address.within(&feat.geom, 0.00001).into_iter().cloned().collect();
+
+        results.extend(exact(is_hecate, feat.clone(), dup_feats));
+    }
+
+    write(args.output, is_hecate, results.into_iter(), &progress)
+}
+
+fn pg_run(args: DedupeArgs, is_hecate: bool, context: crate::Context, progress: Progress) -> Result<(), Error> {
+    let pool = pg::Config::new(&args.db)?.pool()?;
+    let conn = pool.get()?;
+
+    let errors = ErrorSink::new(args.errors)?.policy(args.invalid.unwrap_or_default());
+
+    // Buildings are in the same coordinate reference system as the addresses
+    let crs = context.crs.clone();
+
+    // Numbers read back from the database are parsed with the grammar they were imported with
+    let grammar = Grammar::new(&context);
+
     let address = pg::Address::new();
-    address.create(&conn);
-    address.input(&conn, AddrStream::new(GeoStream::new(args.input), context, None));
+    address.create(&conn)?;
+    progress.stage("address");
+    address.input(&conn, AddrStream::open(args.input, context, errors.clone(), progress.clone())?)?;
+
+    progress.stage("address_index");
 
     if !is_hecate {
         // Hecate Addresses will already have ids present
         // If not hecate, create sequential ids for processing
-        address.seq_id(&conn);
+        address.seq_id(&conn)?;
     }
 
-    address.index(&conn);
+    address.index(&conn)?;
 
     match args.buildings {
         Some(buildings) => {
             let polygon = pg::Polygon::new(String::from("buildings"));
-            polygon.create(&conn);
-            polygon.input(&conn, PolyStream::new(GeoStream::new(Some(buildings)), None));
-            polygon.index(&conn);
+            polygon.create(&conn)?;
+            progress.stage("buildings");
+            polygon.input(&conn, PolyStream::open(Some(buildings), crs, errors.clone(), progress.clone())?)?;
+            polygon.index(&conn)?;
         },
         None => ()
     };
 
-    let count = address.count(&conn);
+    errors.flush()?;
+
+    progress.stage("dedupe");
+
+    let count = address.count(&conn)?;
     let cpus = num_cpus::get() as i64;
     let mut web = Vec::new();
 
@@ -95,10 +171,10 @@ pub fn dedupe(mut cx: FunctionContext) -> JsResult<JsBoolean> {
     let (tx, rx) = crossbeam::channel::unbounded();
 
     for cpu in 0..cpus {
-        let db_conn = args.db.clone();
+        let db_pool = pool.clone();
         let tx_n = tx.clone();
 
-        let strand = match thread::Builder::new().name(format!("Exact Dup #{}", &cpu)).spawn(move || {
+        let strand = thread::Builder::new().name(format!("Exact Dup #{}", &cpu)).spawn(move || -> Result<(), Error> {
             let mut min_id = batch * cpu;
             let max_id = batch * cpu + batch + batch_extra;
 
@@ -106,61 +182,68 @@ pub fn dedupe(mut cx: FunctionContext) -> JsResult<JsBoolean> {
                 min_id = min_id + batch_extra + 1;
             }
 
-            let conn = match Connection::connect(format!("postgres://postgres@localhost:5432/{}", &db_conn).as_str(), TlsMode::None) {
-                Ok(conn) => conn,
-                Err(err) => panic!("Connection Error: {}", err.to_string())
-            };
+            let conn = db_pool.get()?;
 
-            exact_batch(is_hecate, min_id, max_id, conn, tx_n);
-        }) {
-            Ok(strand) => strand,
-            Err(err) => panic!("Thread Creation Error: {}", err.to_string())
-        };
+            exact_batch(is_hecate, grammar, min_id, max_id, conn, tx_n)
+        })?;
 
         web.push(strand);
     }
 
     drop(tx);
 
-    match args.output {
-        Some(outpath) => {
-            let outfile = match File::create(outpath) {
-                Ok(outfile) => outfile,
-                Err(err) => { panic!("Unable to write to output file: {}", err); }
-            };
-
-            output(is_hecate, rx, BufWriter::new(outfile))
-        },
-        None => output(is_hecate, rx, std::io::stdout().lock())
-    }
+    let written = write(args.output, is_hecate, rx.iter(), &progress);
 
     for strand in web {
-        strand.join().unwrap();
+        match strand.join() {
+            Ok(res) => res?,
+            Err(_) => { return Err(Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "Exact Dup thread panicked"))); }
+        };
     }
 
-    Ok(cx.boolean(true))
+    written
+}
+
+fn write(output_path: Option<String>, is_hecate: bool, results: impl Iterator<Item = Address>, progress: &Progress) -> Result<(), Error> {
+    match output_path {
+        Some(outpath) if gpkg::is_gpkg(&outpath) => output(is_hecate, results, GpkgWriter::new(&outpath)?, progress),
+        Some(outpath) => match File::create(outpath) {
+            Ok(outfile) => output(is_hecate, results, BufWriter::new(outfile), progress),
+            Err(err) => Err(Error::Io(err))
+        },
+        None => output(is_hecate, results, std::io::stdout().lock(), progress)
+    }
 }
 
-fn output(is_hecate: bool, receive: crossbeam::Receiver<Address>, mut sink: impl Write) {
-    for result in receive.iter() {
+fn output(is_hecate: bool, results: impl Iterator<Item = Address>, mut sink: impl Write, progress: &Progress) -> Result<(), Error> {
+    for result in results {
+        progress.feature();
 
         let result: String = match is_hecate {
             true => geojson::GeoJson::Feature(result.to_geojson(hecate::Action::Delete)).to_string(),
             false => geojson::GeoJson::Feature(result.to_geojson(hecate::Action::None)).to_string()
         };
 
-        if sink.write(format!("{}\n", result).as_bytes()).is_err() {
-            panic!("Failed to write to output stream");
-        }
+        sink.write_all(format!("{}\n", result).as_bytes())?;
     }
 
-    if sink.flush().is_err() {
-        panic!("Failed to flush output stream");
-    }
+    sink.flush()?;
+
+    Ok(())
+}
+
+///
+/// An address and all addresses within the exact dup distance of it, as rows
+/// to be read with Address::from_value
+///
+#[derive(Deserialize)]
+struct Proximal {
+    primary: serde_json::Value,
+    proximal: Vec<serde_json::Value>
 }
 
-fn exact_batch(is_hecate: bool, min_id: i64, max_id: i64, conn: postgres::Connection, tx: crossbeam::Sender<Address>) {
-    let exact_dups = match pg::Cursor::new(conn, format!(r#"
+fn exact_batch(is_hecate: bool, grammar: Grammar, min_id: i64, max_id: i64, conn: pg::PooledConnection, tx: crossbeam::Sender<Address>) -> Result<(), Error> {
+    let exact_dups: pg::Cursor<Proximal> = pg::Cursor::new(conn, r#"
         SELECT
             JSON_Build_Object(
                 'primary', JSON_Build_Object(
@@ -168,6 +251,9 @@ fn exact_batch(is_hecate: bool, min_id: i64, max_id: i64, conn: postgres::Connec
                     'version', a.version,
                     'names', a.names,
                     'number', a.number,
+                    'unit', a.unit,
+                    'postcode', a.postcode,
+                    'locality', a.locality,
                     'source', a.source,
                     'output', a.output,
                     'props', a.props,
@@ -180,6 +266,9 @@ fn exact_batch(is_hecate: bool, min_id: i64, max_id: i64, conn: postgres::Connec
                             'version', version,
                             'names', names,
                             'number', number,
+                            'unit', unit,
+                            'postcode', postcode,
+                            'locality', locality,
                             'source', source,
                             'output', output,
                             'props', props,
@@ -194,85 +283,80 @@ fn exact_batch(is_hecate: bool, min_id: i64, max_id: i64, conn: postgres::Connec
         FROM
             address a
         WHERE
-            a.id >= {min_id}
-            AND a.id <= {max_id}
-    "#,
-        min_id = min_id,
-        max_id = max_id
-    )) {
-        Ok(cursor) => cursor,
-        Err(err) => panic!("ERR: {}", err.to_string())
-    };
+            a.id >= $1
+            AND a.id <= $2
+    "#, &[&min_id, &max_id])?;
 
     for dup_feats in exact_dups {
-        let mut dup_feats = match dup_feats {
-            serde_json::value::Value::Object(object) => object,
-            _ => panic!("result must be JSON Object")
-        };
-
-        let feat: Address = match Address::from_value(dup_feats.remove(&String::from("primary")).unwrap()) {
-            Ok(feat) => feat,
-            Err(err) => panic!("Address Error: {}", err.to_string())
-        };
-
-        let mut dup_feats: Vec<Address> = match dup_feats.remove(&String::from("proximal")).unwrap() {
-            serde_json::value::Value::Array(feats) => {
-                let mut addrfeats = Vec::with_capacity(feats.len());
-
-                for feat in feats {
-                    addrfeats.push(match Address::from_value(feat) {
-                        Ok(feat) => feat,
-                        Err(err) => panic!("Vec<Address> Error: {}", err.to_string())
-                    });
-                }
-
-                addrfeats
-            },
-            _ => panic!("Duplicate Features should be Vec<Value>")
-        };
+        let Proximal {
+            primary: feat,
+            proximal: dup_feats
+        } = dup_feats?;
 
-        //
-        // For now the dup logic is rather simple & strict
-        // - Number must be the same - apt numbers included
-        // - Text synonyms must match
-        dup_feats = dup_feats.into_iter().filter(|dup_feat| {
-            dup_feat.number == feat.number
-            && dup_feat.names == feat.names
-        }).collect();
-
-        dup_feats.sort_by(|a, b| {
-            if a.id.unwrap() < b.id.unwrap() {
-                std::cmp::Ordering::Less
-            } else if a.id.unwrap() > b.id.unwrap() {
-                std::cmp::Ordering::Greater
-            } else {
-                std::cmp::Ordering::Equal
-            }
-        });
+        let feat = Address::from_value(feat, &grammar).map_err(Error::Parse)?;
+        let dup_feats = dup_feats.into_iter()
+            .map(|dup_feat| Address::from_value(dup_feat, &grammar))
+            .collect::<Result<Vec<Address>, String>>()
+            .map_err(Error::Parse)?;
 
         //
         // Since this operation is performed in parallel - duplicates could be potentially
         // processed by multiple threads - resulting in duplicate output. To avoid this
-        // the dup_feat will only be processed if the lowest ID in the match falls within
-        // the min_id/max_id that the given thread is processing
+        // a group is only output by the thread processing its lowest ID, which falls
+        // within the min_id/max_id of that thread
         //
-        if dup_feats[0].id.unwrap() < feat.id.unwrap() || dup_feats[0].id.unwrap() < min_id || dup_feats[0].id.unwrap() > max_id {
-            continue;
+        for result in exact(is_hecate, feat, dup_feats) {
+            if tx.send(result).is_err() {
+                // Output stream has gone away, nothing left to do
+                return Ok(());
+            }
         }
+    }
 
-        if is_hecate {
-            // If it is hecate output - delete all features
-            // but the desired feature
+    Ok(())
+}
 
-            for dup_feat in dup_feats {
-                if dup_feat.id.unwrap() != feat.id.unwrap() {
-                    tx.send(dup_feat).unwrap();
-                }
-            }
+///
+/// Given an address and all addresses within the exact dup distance of it,
+/// return the features that should be output
+///
+/// Only the address with the lowest id in a group of duplicates produces output,
+/// ensuring each group is only output once
+///
+fn exact(is_hecate: bool, feat: Address, dup_feats: Vec<Address>) -> Vec<Address> {
+    //
+    // For now the dup logic is rather simple & strict
+    // - Number must be the same - apt numbers included
+    // - Unit must be the same, so units under one number are not duplicates
+    // - Text synonyms must match
+    let mut dup_feats: Vec<Address> = dup_feats.into_iter().filter(|dup_feat| {
+        dup_feat.number == feat.number
+        && dup_feat.unit == feat.unit
+        && dup_feat.names == feat.names
+    }).collect();
+
+    dup_feats.sort_by(|a, b| {
+        if a.id.unwrap() < b.id.unwrap() {
+            std::cmp::Ordering::Less
+        } else if a.id.unwrap() > b.id.unwrap() {
+            std::cmp::Ordering::Greater
         } else {
-            // If not hecate, only print the desired feature
-
-            tx.send(feat).unwrap();
+            std::cmp::Ordering::Equal
         }
+    });
+
+    if dup_feats.is_empty() || dup_feats[0].id.unwrap() < feat.id.unwrap() {
+        return Vec::new();
+    }
+
+    if is_hecate {
+        // If it is hecate output - delete all features
+        // but the desired feature
+        dup_feats.into_iter().filter(|dup_feat| {
+            dup_feat.id.unwrap() != feat.id.unwrap()
+        }).collect()
+    } else {
+        // If not hecate, only print the desired feature
+        vec![feat]
     }
 }
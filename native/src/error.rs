@@ -0,0 +1,149 @@
+use std::fmt;
+use neon::prelude::*;
+
+///
+/// Crate wide error type. Every native mode surfaces failures as one of these,
+/// which is then thrown into NodeJS as an Error with a stable `code` property
+///
+#[derive(Debug)]
+pub enum Error {
+    /// Failure reading or writing a file or stream
+    Io(std::io::Error),
+
+    /// Failure returned by the database
+    Postgres(postgres::Error),
+
+    /// Failure checking a connection out of the pool
+    Pool(r2d2::Error),
+
+    /// Failure reading or writing a GeoPackage
+    Sqlite(rusqlite::Error),
+
+    /// Input that could not be parsed as GeoJSON/JSON
+    Parse(String),
+
+    /// Input that parsed but is not a valid feature for the given mode
+    Validation(String),
+
+    /// Invalid arguments or connection settings
+    Config(String)
+}
+
+impl Error {
+    ///
+    /// Stable error code exposed to NodeJS via `err.code`
+    ///
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "EIO",
+            Error::Postgres(_) => "EPOSTGRES",
+            Error::Pool(_) => "EPOSTGRES",
+            Error::Sqlite(_) => "ESQLITE",
+            Error::Parse(_) => "EPARSE",
+            Error::Validation(_) => "EVALIDATION",
+            Error::Config(_) => "ECONFIG"
+        }
+    }
+
+    ///
+    /// Throw the error as a JS Error, setting the `code` property
+    ///
+    pub fn throw<'a, T: Value, C: Context<'a>>(self, cx: &mut C) -> JsResult<'a, T> {
+        let err = JsError::error(cx, self.to_string())?;
+        let code = cx.string(self.code());
+        err.set(cx, "code", code)?;
+
+        cx.throw(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "IO Error: {}", err),
+            Error::Postgres(err) => write!(f, "Postgres Error: {}", err),
+            Error::Pool(err) => write!(f, "Connection Pool Error: {}", err),
+            Error::Sqlite(err) => write!(f, "SQLite Error: {}", err),
+            Error::Parse(err) => write!(f, "Parse Error: {}", err),
+            Error::Validation(err) => write!(f, "Validation Error: {}", err),
+            Error::Config(err) => write!(f, "Config Error: {}", err)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        // Errors raised by a stream inside of a Read impl are smuggled
+        // through io::Error, unwrap them so the original code is kept
+        if err.get_ref().map_or(false, |inner| inner.is::<Error>()) {
+            match err.into_inner().unwrap().downcast::<Error>() {
+                Ok(inner) => *inner,
+                Err(inner) => Error::Io(std::io::Error::new(std::io::ErrorKind::Other, inner))
+            }
+        } else {
+            Error::Io(err)
+        }
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::Other, err)
+        }
+    }
+}
+
+impl From<postgres::Error> for Error {
+    fn from(err: postgres::Error) -> Self {
+        Error::Postgres(err)
+    }
+}
+
+impl From<r2d2::Error> for Error {
+    fn from(err: r2d2::Error) -> Self {
+        Error::Pool(err)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
+impl From<geojson::Error> for Error {
+    fn from(err: geojson::Error) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(Error::Validation(String::from("Number property required")).code(), "EVALIDATION");
+        assert_eq!(Error::Config(String::from("Output file required")).code(), "ECONFIG");
+        assert_eq!(Error::from(std::io::Error::new(std::io::ErrorKind::NotFound, "missing")).code(), "EIO");
+    }
+
+    #[test]
+    fn test_error_io_roundtrip() {
+        let err = std::io::Error::from(Error::Parse(String::from("Invalid GeoJSON")));
+
+        let err = Error::from(err);
+        assert_eq!(err.code(), "EPARSE");
+        assert_eq!(err.to_string(), "Parse Error: Invalid GeoJSON");
+    }
+}
@@ -4,17 +4,31 @@
 extern crate serde_json;
 extern crate neon_serde;
 extern crate crossbeam;
+extern crate csv;
+extern crate flate2;
+extern crate bzip2;
+extern crate zstd;
 extern crate num_cpus;
+extern crate osmpbfreader;
 extern crate postgres;
+extern crate r2d2;
+extern crate r2d2_postgres;
+extern crate openssl;
+extern crate sha2;
+extern crate rusqlite;
 extern crate geojson;
 extern crate regex;
 
 // Internal Helper Libraries
+pub mod error;
+pub mod progress;
 pub mod stream;
 pub mod text;
+pub mod crs;
 
 pub mod types;
 pub mod pg;
+pub mod mem;
 
 // Helper to current node fn
 pub mod map;
@@ -25,13 +39,19 @@ pub mod stats;
 pub mod dedupe;
 pub mod classify;
 
+pub use self::error::Error;
+pub use self::progress::Progress;
+
 pub use self::types::Address;
 pub use self::types::Network;
 pub use self::types::Polygon;
+pub use self::types::HouseNumber;
+pub use self::types::Unit;
 
 pub use self::types::hecate;
 pub use self::types::Context;
 pub use self::text::Tokens;
+pub use self::crs::Crs;
 
 pub use self::types::Names;
 pub use self::types::Name;
@@ -40,6 +60,7 @@ pub use self::types::Name;
 register_module!(mut m, {
     m.export_function("pg_init", map::pg_init)?;
     m.export_function("pg_optimize", map::pg_optimize)?;
+    m.export_function("mem_drop", map::mem_drop)?;
 
     m.export_function("import_addr", map::import_addr)?;
     m.export_function("import_net", map::import_net)?;
@@ -49,6 +70,9 @@ register_module!(mut m, {
 
     m.export_function("intersections", map::intersections)?;
 
+    m.export_function("stage_begin", map::stage_begin)?;
+    m.export_function("stage_complete", map::stage_complete)?;
+
     m.export_function("classify", classify::classify)?;
     m.export_function("convert", convert::convert)?;
     m.export_function("stats", stats::stats)?;
@@ -1,18 +1,23 @@
 use std::convert::From;
-use postgres::{Connection, TlsMode};
 use std::collections::HashMap;
 
 use crate::Context as CrateContext;
-use crate::Tokens;
+use crate::{Tokens, Error, Progress};
+use crate::progress;
 
 use neon::prelude::*;
 
 use super::stream::GeoStream;
 use super::stream::AddrStream;
 use super::stream::NetStream;
+use super::stream::{CsvConfig, CsvStream};
+use super::stream::{OsmConfig, OsmKind, OsmStream};
+use super::stream::{ErrorSink, Policy};
 
+use super::mem;
 use super::pg;
 use super::pg::{Table, InputTable};
+use super::pg::stages::file_fingerprint;
 
 pub fn pg_init(mut cx: FunctionContext) -> JsResult<JsBoolean> {
     let db = match cx.argument_opt(0) {
@@ -20,50 +25,161 @@ pub fn pg_init(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         None => String::from("pt_test")
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &db).as_str(), TlsMode::None).unwrap();
+    let resume = match cx.argument_opt(1) {
+        Some(arg) => arg.downcast::<JsBoolean>().or_throw(&mut cx)?.value(),
+        None => false
+    };
 
-    let address = pg::Address::new();
-    let network = pg::Network::new();
+    let backend = backend_arg(&mut cx, 2)?;
 
-    address.create(&conn);
-    network.create(&conn);
+    // Each memory backed run starts from an empty database
+    if backend == mem::Backend::Memory {
+        mem::open_database(&db);
 
-    let networkcluster = pg::NetworkCluster::new(false);
-    let addresscluster = pg::AddressCluster::new(false);
+        return Ok(cx.boolean(true));
+    }
 
-    networkcluster.create(&conn);
-    addresscluster.create(&conn);
+    match init(&db, resume) {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
 
-    let networkcluster = pg::NetworkCluster::new(true);
-    let addresscluster = pg::AddressCluster::new(true);
+///
+/// Read the optional storage backend argument of a map stage, Postgres if not given
+///
+fn backend_arg(cx: &mut FunctionContext, i: i32) -> NeonResult<mem::Backend> {
+    match cx.argument_opt(i) {
+        Some(arg) if !(arg.is_a::<JsUndefined>() || arg.is_a::<JsNull>()) => Ok(neon_serde::from_value(cx, arg)?),
+        _ => Ok(mem::Backend::Postgres)
+    }
+}
 
-    networkcluster.create(&conn);
-    addresscluster.create(&conn);
+///
+/// Release the memory database of a map run once it has finished
+///
+pub fn mem_drop(mut cx: FunctionContext) -> JsResult<JsBoolean> {
+    let db = cx.argument::<JsString>(0)?.value();
 
-    let intersections = pg::Intersections::new();
-    intersections.create(&conn);
+    mem::drop_database(&db);
 
     Ok(cx.boolean(true))
 }
 
+///
+/// Run a memory backed map stage against the named database,
+/// releasing the database if the stage fails
+///
+fn mem_stage(db: &str, run: impl FnOnce(&mut mem::Database) -> Result<(), Error>) -> Result<(), Error> {
+    let res = mem::with_database(db, run);
+
+    if res.is_err() {
+        mem::drop_database(db);
+    }
+
+    res
+}
+
+fn init(db: &str, resume: bool) -> Result<(), Error> {
+    let config = pg::Config::new(db)?;
+    let conn = config.connect()?;
+    config.setup(&conn)?;
+
+    let stages = pg::Stages::new();
+    stages.create(&conn)?;
+
+    if !resume {
+        stages.clear(&conn)?;
+    }
+
+    let tables: [(&str, &dyn Table); 7] = [
+        ("address", &pg::Address::new()),
+        ("network", &pg::Network::new()),
+        ("network_cluster", &pg::NetworkCluster::new(false)),
+        ("address_cluster", &pg::AddressCluster::new(false)),
+        ("network_orphan_cluster", &pg::NetworkCluster::new(true)),
+        ("address_orphan_cluster", &pg::AddressCluster::new(true)),
+        ("intersections", &pg::Intersections::new())
+    ];
+
+    for (name, table) in tables.iter() {
+        // When resuming, existing tables are kept for any stage that is still valid,
+        // each stage recreating its own tables if it is not, and only missing tables are created
+        if !resume || !pg::stages::table_exists(&conn, name)? {
+            table.create(&conn)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn pg_optimize(mut cx: FunctionContext) -> JsResult<JsBoolean> {
     let db = match cx.argument_opt(0) {
         Some(arg) => arg.downcast::<JsString>().or_throw(&mut cx)?.value(),
         None => String::from("pt_test")
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &db).as_str(), TlsMode::None).unwrap();
+    match optimize(&db) {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn optimize(db: &str) -> Result<(), Error> {
+    let conn = pg::Config::new(db)?.connect()?;
 
     let address = pg::Address::new();
     let network = pg::Network::new();
 
-    address.seq_id(&conn);
-    network.seq_id(&conn);
+    address.seq_id(&conn)?;
+    network.seq_id(&conn)?;
 
-    address.index(&conn);
-    network.index(&conn);
+    address.index(&conn)?;
+    network.index(&conn)?;
 
-    Ok(cx.boolean(true))
+    // Ids have been regenerated so any recorded stages are no longer valid
+    let stages = pg::Stages::new();
+    stages.create(&conn)?;
+    stages.invalidate(&conn, "address")?;
+    stages.invalidate(&conn, "network")?;
+
+    Ok(())
+}
+
+///
+/// Run a map stage, skipping it when resuming if it previously
+/// completed with the same inputs and its output is still intact
+///
+fn stage(conn: &postgres::Connection, name: &str, inputs: Option<&str>, resume: bool, run: impl FnOnce() -> Result<(), Error>) -> Result<(), Error> {
+    let stages = pg::Stages::new();
+    stages.create(conn)?;
+
+    if !stages.begin(conn, name, inputs, resume)? {
+        println!("ok - {} stage already complete, skipping", name);
+        return Ok(());
+    }
+
+    run()?;
+
+    stages.complete(conn, name, inputs)
+}
+
+///
+/// Fingerprint the inputs of an import stage, None when reading from stdin
+///
+fn import_inputs(args: &MapArgs) -> Result<Option<String>, Error> {
+    match args.input {
+        Some(ref input) => Ok(Some(format!("{}|{}|{}|{:?}|{}|{}|{}",
+            file_fingerprint(input)?,
+            serde_json::to_string(&args.context)?,
+            args.seq,
+            args.errors,
+            serde_json::to_string(&args.invalid)?,
+            serde_json::to_string(&args.csv)?,
+            serde_json::to_string(&args.osm)?
+        ))),
+        None => Ok(None)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -72,7 +188,19 @@ struct MapArgs {
     context: Option<super::types::InputContext>,
     seq: bool,
     input: Option<String>,
-    errors: Option<String>
+    errors: Option<String>,
+
+    /// How lines of input that cannot be parsed are handled, strict by default
+    invalid: Option<Policy>,
+
+    backend: Option<mem::Backend>,
+    resume: Option<bool>,
+
+    /// Read addresses from CSV rather than GeoJSON
+    csv: Option<CsvConfig>,
+
+    /// Read addresses or networks from an OSM PBF rather than GeoJSON
+    osm: Option<OsmConfig>
 }
 
 impl MapArgs {
@@ -82,7 +210,12 @@ impl MapArgs {
             seq: true,
             context: None,
             input: None,
-            errors: None
+            errors: None,
+            invalid: None,
+            backend: None,
+            resume: None,
+            csv: None,
+            osm: None
         }
     }
 }
@@ -100,22 +233,76 @@ pub fn import_addr(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         }
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &args.db).as_str(), TlsMode::None).unwrap();
+    let callback = progress::callback(&mut cx, 1)?;
+
+    match progress::run(&mut cx, callback, move |progress| addr(args, progress))? {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn addr(args: MapArgs, progress: Progress) -> Result<(), Error> {
+    let inputs = import_inputs(&args)?;
 
     let context = match args.context {
         Some(context) => CrateContext::from(context),
         None => CrateContext::new(String::from(""), None, Tokens::new(HashMap::new()))
     };
 
-    let address = pg::Address::new();
-    address.create(&conn);
-    address.input(&conn, AddrStream::new(GeoStream::new(args.input), context, args.errors));
-    if args.seq {
-        address.seq_id(&conn);
+    let invalid = args.invalid.unwrap_or_default();
+
+    if args.backend == Some(mem::Backend::Memory) {
+        let db = args.db.clone();
+
+        let address = (|| -> Result<mem::Address, Error> {
+            let mut address = mem::Address::new();
+            progress.stage("address");
+            let errors = ErrorSink::new(args.errors)?.policy(invalid);
+            address.input(addr_stream(args.input, args.csv, args.osm, context, errors.clone(), progress.clone())?)?;
+            errors.flush()?;
+            progress.stage("address_index");
+            if args.seq {
+                address.seq_id();
+            }
+            address.index();
+
+            Ok(address)
+        })();
+
+        return mem_stage(&db, |database| {
+            database.address = address?;
+
+            Ok(())
+        });
     }
-    address.index(&conn);
 
-    Ok(cx.boolean(true))
+    let conn = pg::Config::new(&args.db)?.connect()?;
+
+    stage(&conn, "address", inputs.as_ref().map(String::as_str), args.resume.unwrap_or(false), || {
+        let address = pg::Address::new();
+        address.create(&conn)?;
+        progress.stage("address");
+        let errors = ErrorSink::new(args.errors)?.policy(invalid);
+        address.input(&conn, addr_stream(args.input, args.csv, args.osm, context, errors.clone(), progress.clone())?)?;
+        errors.flush()?;
+        progress.stage("address_index");
+        if args.seq {
+            address.seq_id(&conn)?;
+        }
+        address.index(&conn)
+    })
+}
+
+///
+/// Open an address input as either GeoJSON/GeoPackage or, if configured, CSV or OSM PBF
+///
+fn addr_stream(input: Option<String>, csv: Option<CsvConfig>, osm: Option<OsmConfig>, context: CrateContext, errors: ErrorSink, progress: Progress) -> Result<AddrStream, Error> {
+    match (csv, osm) {
+        (Some(_), Some(_)) => Err(Error::Config(String::from("Only one of CSV or OSM input may be configured"))),
+        (Some(csv), None) => AddrStream::labelled(CsvStream::new(input, csv)?, context, errors, progress),
+        (None, Some(osm)) => AddrStream::labelled(OsmStream::new(input, OsmKind::Address, osm)?, context, errors, progress),
+        (None, None) => AddrStream::open(input, context, errors, progress)
+    }
 }
 
 pub fn import_net(mut cx: FunctionContext) -> JsResult<JsBoolean> {
@@ -131,22 +318,74 @@ pub fn import_net(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         }
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &args.db).as_str(), TlsMode::None).unwrap();
+    let callback = progress::callback(&mut cx, 1)?;
+
+    match progress::run(&mut cx, callback, move |progress| net(args, progress))? {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn net(args: MapArgs, progress: Progress) -> Result<(), Error> {
+    let inputs = import_inputs(&args)?;
 
     let context = match args.context {
         Some(context) => CrateContext::from(context),
         None => CrateContext::new(String::from(""), None, Tokens::new(HashMap::new()))
     };
 
-    let network = pg::Network::new();
-    network.create(&conn);
-    network.input(&conn, NetStream::new(GeoStream::new(args.input), context, args.errors));
-    if args.seq {
-        network.seq_id(&conn);
+    let invalid = args.invalid.unwrap_or_default();
+
+    if args.backend == Some(mem::Backend::Memory) {
+        let db = args.db.clone();
+
+        let network = (|| -> Result<mem::Network, Error> {
+            let mut network = mem::Network::new();
+            progress.stage("network");
+            let errors = ErrorSink::new(args.errors)?.policy(invalid);
+            network.input(net_stream(args.input, args.osm, context, errors.clone(), progress.clone())?)?;
+            errors.flush()?;
+            progress.stage("network_index");
+            if args.seq {
+                network.seq_id();
+            }
+            network.index();
+
+            Ok(network)
+        })();
+
+        return mem_stage(&db, |database| {
+            database.network = network?;
+
+            Ok(())
+        });
     }
-    network.index(&conn);
 
-    Ok(cx.boolean(true))
+    let conn = pg::Config::new(&args.db)?.connect()?;
+
+    stage(&conn, "network", inputs.as_ref().map(String::as_str), args.resume.unwrap_or(false), || {
+        let network = pg::Network::new();
+        network.create(&conn)?;
+        progress.stage("network");
+        let errors = ErrorSink::new(args.errors)?.policy(invalid);
+        network.input(&conn, net_stream(args.input, args.osm, context, errors.clone(), progress.clone())?)?;
+        errors.flush()?;
+        progress.stage("network_index");
+        if args.seq {
+            network.seq_id(&conn)?;
+        }
+        network.index(&conn)
+    })
+}
+
+///
+/// Open a network input as either GeoJSON/GeoPackage or, if configured, OSM PBF
+///
+fn net_stream(input: Option<String>, osm: Option<OsmConfig>, context: CrateContext, errors: ErrorSink, progress: Progress) -> Result<NetStream, Error> {
+    match osm {
+        Some(osm) => NetStream::labelled(OsmStream::new(input, OsmKind::Network, osm)?, context, errors, progress),
+        None => NetStream::open(input, context, errors, progress)
+    }
 }
 
 pub fn cluster_addr(mut cx: FunctionContext) -> JsResult<JsBoolean> {
@@ -160,14 +399,46 @@ pub fn cluster_addr(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         None => false
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &db).as_str(), TlsMode::None).unwrap();
+    let resume = match cx.argument_opt(2) {
+        Some(arg) => arg.downcast::<JsBoolean>().or_throw(&mut cx)?.value(),
+        None => false
+    };
+
+    let backend = backend_arg(&mut cx, 3)?;
 
-    let cluster = pg::AddressCluster::new(orphan);
-    cluster.create(&conn);
-    cluster.generate(&conn);
-    cluster.index(&conn);
+    let callback = progress::callback(&mut cx, 4)?;
 
-    Ok(cx.boolean(true))
+    match progress::run(&mut cx, callback, move |progress| addr_cluster(&db, orphan, resume, backend, progress))? {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn addr_cluster(db: &str, orphan: bool, resume: bool, backend: mem::Backend, progress: Progress) -> Result<(), Error> {
+    let name = match orphan {
+        true => "address_orphan_cluster",
+        false => "address_cluster"
+    };
+
+    progress.stage(name);
+
+    if backend == mem::Backend::Memory {
+        return mem_stage(db, |database| {
+            progress.processed(database.cluster_addresses(orphan)? as u64);
+
+            Ok(())
+        });
+    }
+
+    let conn = pg::Config::new(db)?.connect()?;
+
+    stage(&conn, name, Some(""), resume, || {
+        let cluster = pg::AddressCluster::new(orphan);
+        cluster.create(&conn)?;
+        cluster.generate(&conn)?;
+        progress.processed(cluster.count(&conn)? as u64);
+        cluster.index(&conn)
+    })
 }
 
 pub fn cluster_net(mut cx: FunctionContext) -> JsResult<JsBoolean> {
@@ -181,14 +452,46 @@ pub fn cluster_net(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         None => false
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &db).as_str(), TlsMode::None).unwrap();
+    let resume = match cx.argument_opt(2) {
+        Some(arg) => arg.downcast::<JsBoolean>().or_throw(&mut cx)?.value(),
+        None => false
+    };
 
-    let cluster = pg::NetworkCluster::new(orphan);
-    cluster.create(&conn);
-    cluster.generate(&conn);
-    cluster.index(&conn);
+    let backend = backend_arg(&mut cx, 3)?;
 
-    Ok(cx.boolean(true))
+    let callback = progress::callback(&mut cx, 4)?;
+
+    match progress::run(&mut cx, callback, move |progress| net_cluster(&db, orphan, resume, backend, progress))? {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn net_cluster(db: &str, orphan: bool, resume: bool, backend: mem::Backend, progress: Progress) -> Result<(), Error> {
+    let name = match orphan {
+        true => "network_orphan_cluster",
+        false => "network_cluster"
+    };
+
+    progress.stage(name);
+
+    if backend == mem::Backend::Memory {
+        return mem_stage(db, |database| {
+            progress.processed(database.cluster_networks(orphan)? as u64);
+
+            Ok(())
+        });
+    }
+
+    let conn = pg::Config::new(db)?.connect()?;
+
+    stage(&conn, name, Some(""), resume, || {
+        let cluster = pg::NetworkCluster::new(orphan);
+        cluster.create(&conn)?;
+        cluster.generate(&conn)?;
+        progress.processed(cluster.count(&conn)? as u64);
+        cluster.index(&conn)
+    })
 }
 
 pub fn intersections(mut cx: FunctionContext) -> JsResult<JsBoolean> {
@@ -197,12 +500,64 @@ pub fn intersections(mut cx: FunctionContext) -> JsResult<JsBoolean> {
         None => String::from("pt_test")
     };
 
-    let conn = Connection::connect(format!("postgres://postgres@localhost:5432/{}", &db).as_str(), TlsMode::None).unwrap();
+    let resume = match cx.argument_opt(1) {
+        Some(arg) => arg.downcast::<JsBoolean>().or_throw(&mut cx)?.value(),
+        None => false
+    };
 
-    let intersections = pg::Intersections::new();
-    intersections.create(&conn);
-    intersections.generate(&conn);
-    intersections.index(&conn);
+    match intersections_generate(&db, resume) {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
 
-    Ok(cx.boolean(true))
+fn intersections_generate(db: &str, resume: bool) -> Result<(), Error> {
+    let conn = pg::Config::new(db)?.connect()?;
+
+    stage(&conn, "intersections", Some(""), resume, || {
+        let intersections = pg::Intersections::new();
+        intersections.create(&conn)?;
+        intersections.generate(&conn)?;
+        intersections.index(&conn)
+    })
+}
+
+///
+/// Allow stages run from NodeJS, such as matching, to be checkpointed
+///
+/// Returns false if resuming and the stage is still valid and can be skipped
+///
+pub fn stage_begin(mut cx: FunctionContext) -> JsResult<JsBoolean> {
+    let db = cx.argument::<JsString>(0)?.value();
+    let name = cx.argument::<JsString>(1)?.value();
+
+    let resume = match cx.argument_opt(2) {
+        Some(arg) => arg.downcast::<JsBoolean>().or_throw(&mut cx)?.value(),
+        None => false
+    };
+
+    let run = pg::Config::new(&db).and_then(|config| config.connect()).and_then(|conn| {
+        let stages = pg::Stages::new();
+        stages.create(&conn)?;
+        stages.begin(&conn, &name, Some(""), resume)
+    });
+
+    match run {
+        Ok(run) => Ok(cx.boolean(run)),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+pub fn stage_complete(mut cx: FunctionContext) -> JsResult<JsBoolean> {
+    let db = cx.argument::<JsString>(0)?.value();
+    let name = cx.argument::<JsString>(1)?.value();
+
+    let complete = pg::Config::new(&db).and_then(|config| config.connect()).and_then(|conn| {
+        pg::Stages::new().complete(&conn, &name, Some(""))
+    });
+
+    match complete {
+        Ok(_) => Ok(cx.boolean(true)),
+        Err(err) => err.throw(&mut cx)
+    }
 }
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use crate::Error;
+use super::{geom, Clusters};
+use super::rtree::{BBox, RTree};
+
+///
+/// In-memory equivalent of the pg::Address table
+///
+pub struct Address {
+    pub addresses: Vec<crate::Address>,
+    tree: RTree
+}
+
+impl Address {
+    pub fn new() -> Self {
+        Address {
+            addresses: Vec::new(),
+            tree: RTree::new(Vec::new())
+        }
+    }
+
+    pub fn input(&mut self, data: impl Iterator<Item = Result<crate::Address, Error>>) -> Result<(), Error> {
+        for addr in data {
+            self.addresses.push(addr?);
+        }
+
+        Ok(())
+    }
+
+    pub fn seq_id(&mut self) {
+        for (it, addr) in self.addresses.iter_mut().enumerate() {
+            addr.id = Some(it as i64 + 1);
+        }
+    }
+
+    ///
+    /// Build the spatial index, must be called after input
+    /// and before any spatial queries are made
+    ///
+    pub fn index(&mut self) {
+        self.tree = RTree::new(self.addresses.iter().enumerate().map(|(it, addr)| {
+            (it, BBox::point(addr.geom[0], addr.geom[1]))
+        }).collect());
+    }
+
+    pub fn count(&self) -> i64 {
+        self.addresses.len() as i64
+    }
+
+    ///
+    /// Return all addresses within the given distance of a point,
+    /// equivalent to ST_DWithin
+    ///
+    pub fn within(&self, point: &[f64], distance: f64) -> Vec<&crate::Address> {
+        self.tree.search(&BBox::point(point[0], point[1]).buffer(distance)).into_iter().map(|it| {
+            &self.addresses[it]
+        }).filter(|addr| {
+            geom::distance(&addr.geom, point) <= distance
+        }).collect()
+    }
+
+    ///
+    /// Cluster addresses with identical names that are within the given
+    /// distance of each other, equivalent to ST_ClusterWithin grouped by names.
+    /// Addresses for which include returns false are not clustered
+    ///
+    /// Clusters are returned as indexes into the address list
+    ///
+    pub fn cluster(&self, distance: f64, include: impl Fn(usize) -> bool) -> Result<Vec<Vec<usize>>, Error> {
+        let mut keys = Vec::with_capacity(self.addresses.len());
+        for (it, addr) in self.addresses.iter().enumerate() {
+            keys.push(match include(it) {
+                true => Some(serde_json::to_string(&addr.names.names)?),
+                false => None
+            });
+        }
+
+        let mut clusters = Clusters::new(self.addresses.len());
+
+        for (it, addr) in self.addresses.iter().enumerate() {
+            if keys[it].is_none() {
+                continue;
+            }
+
+            for other in self.tree.search(&BBox::point(addr.geom[0], addr.geom[1]).buffer(distance)) {
+                if other > it
+                    && keys[it] == keys[other]
+                    && geom::distance(&addr.geom, &self.addresses[other].geom) <= distance
+                {
+                    clusters.union(it, other);
+                }
+            }
+        }
+
+        Ok(clusters.groups().into_iter().filter(|cluster| {
+            keys[cluster[0]].is_some()
+        }).collect())
+    }
+
+    ///
+    /// Group matched addresses by the network cluster they were matched to,
+    /// equivalent to the address_cluster table grouped by netid
+    ///
+    /// Groups are returned as the index of the network cluster & the
+    /// indexes into the address list, ordered by network cluster
+    ///
+    pub fn matched(&self, netids: &[Option<usize>]) -> Vec<(usize, Vec<usize>)> {
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for (it, netid) in netids.iter().enumerate() {
+            if let Some(netid) = netid {
+                groups.entry(*netid).or_insert_with(Vec::new).push(it);
+            }
+        }
+
+        groups.into_iter().collect()
+    }
+}
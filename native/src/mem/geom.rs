@@ -0,0 +1,220 @@
+//
+// Planar geometry helpers, operating directly on lng/lat degrees
+// to mirror the behavior of the equivalent PostGIS functions on SRID 4326
+//
+
+///
+/// Distance between two points
+///
+pub fn distance(a: &[f64], b: &[f64]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+///
+/// Distance between a point and a line segment
+///
+pub fn segment_distance(pt: &[f64], start: &[f64], end: &[f64]) -> f64 {
+    let dx = end[0] - start[0];
+    let dy = end[1] - start[1];
+
+    let len = dx * dx + dy * dy;
+
+    if len == 0.0 {
+        return distance(pt, start);
+    }
+
+    let t = (((pt[0] - start[0]) * dx + (pt[1] - start[1]) * dy) / len).max(0.0).min(1.0);
+
+    distance(pt, &[start[0] + t * dx, start[1] + t * dy])
+}
+
+fn orientation(a: &[f64], b: &[f64], c: &[f64]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn on_segment(pt: &[f64], start: &[f64], end: &[f64]) -> bool {
+    pt[0] >= start[0].min(end[0]) && pt[0] <= start[0].max(end[0])
+        && pt[1] >= start[1].min(end[1]) && pt[1] <= start[1].max(end[1])
+}
+
+///
+/// Do the two given line segments touch or cross
+///
+pub fn segments_intersect(a1: &[f64], a2: &[f64], b1: &[f64], b2: &[f64]) -> bool {
+    let d1 = orientation(b1, b2, a1);
+    let d2 = orientation(b1, b2, a2);
+    let d3 = orientation(a1, a2, b1);
+    let d4 = orientation(a1, a2, b2);
+
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+
+    (d1 == 0.0 && on_segment(a1, b1, b2))
+        || (d2 == 0.0 && on_segment(a2, b1, b2))
+        || (d3 == 0.0 && on_segment(b1, a1, a2))
+        || (d4 == 0.0 && on_segment(b2, a1, a2))
+}
+
+///
+/// Minimum distance between two linestrings, equivalent to ST_Distance
+///
+pub fn linestring_distance(a: &[Vec<f64>], b: &[Vec<f64>]) -> f64 {
+    let mut min = std::f64::MAX;
+
+    if a.len() == 1 || b.len() == 1 {
+        for a_pt in a {
+            for b_pt in b {
+                min = min.min(distance(a_pt, b_pt));
+            }
+        }
+    }
+
+    for a_seg in a.windows(2) {
+        for b_pt in b {
+            min = min.min(segment_distance(b_pt, &a_seg[0], &a_seg[1]));
+        }
+
+        for b_seg in b.windows(2) {
+            if segments_intersect(&a_seg[0], &a_seg[1], &b_seg[0], &b_seg[1]) {
+                return 0.0;
+            }
+        }
+    }
+
+    for b_seg in b.windows(2) {
+        for a_pt in a {
+            min = min.min(segment_distance(a_pt, &b_seg[0], &b_seg[1]));
+        }
+    }
+
+    min
+}
+
+///
+/// Ray casting point in ring test
+///
+fn ring_contains(ring: &[Vec<f64>], pt: &[f64]) -> bool {
+    let mut inside = false;
+
+    for seg in ring.windows(2) {
+        let (a, b) = (&seg[0], &seg[1]);
+
+        if segment_distance(pt, a, b) == 0.0 {
+            // Points on the boundary intersect the polygon
+            return true;
+        }
+
+        if (a[1] > pt[1]) != (b[1] > pt[1]) && pt[0] < (b[0] - a[0]) * (pt[1] - a[1]) / (b[1] - a[1]) + a[0] {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+///
+/// Does the point intersect the polygon, equivalent to ST_Intersects
+///
+pub fn polygon_contains(polygon: &geojson::PolygonType, pt: &[f64]) -> bool {
+    if polygon.is_empty() || !ring_contains(&polygon[0], pt) {
+        return false;
+    }
+
+    for hole in polygon.iter().skip(1) {
+        if ring_contains(hole, pt) && !hole.windows(2).any(|seg| segment_distance(pt, &seg[0], &seg[1]) == 0.0) {
+            return false;
+        }
+    }
+
+    true
+}
+
+///
+/// Signed area of a closed ring, positive when the ring is counterclockwise
+///
+pub fn signed_area(ring: &[Vec<f64>]) -> f64 {
+    ring.windows(2).map(|seg| seg[0][0] * seg[1][1] - seg[1][0] * seg[0][1]).sum::<f64>() / 2.0
+}
+
+fn ring_area(ring: &[Vec<f64>]) -> f64 {
+    signed_area(ring).abs()
+}
+
+///
+/// Return a point that is guaranteed to lie within the largest polygon
+/// of the multipolygon, similar to ST_PointOnSurface
+///
+/// A horizontal line is cast through the middle of the polygon's bbox
+/// and the midpoint of the widest interior span is used
+///
+pub fn point_on_surface(geom: &[geojson::PolygonType]) -> Option<Vec<f64>> {
+    let polygon = geom.iter().filter(|polygon| !polygon.is_empty()).max_by(|a, b| {
+        ring_area(&a[0]).partial_cmp(&ring_area(&b[0])).unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+
+    let (min_y, max_y) = polygon[0].iter().fold((std::f64::MAX, std::f64::MIN), |(min, max), pt| {
+        (min.min(pt[1]), max.max(pt[1]))
+    });
+
+    let y = (min_y + max_y) / 2.0;
+
+    let mut crossings: Vec<f64> = Vec::new();
+    for ring in polygon {
+        for seg in ring.windows(2) {
+            let (a, b) = (&seg[0], &seg[1]);
+
+            if (a[1] > y) != (b[1] > y) {
+                crossings.push((b[0] - a[0]) * (y - a[1]) / (b[1] - a[1]) + a[0]);
+            }
+        }
+    }
+
+    crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let span = crossings.chunks(2).filter(|span| span.len() == 2).max_by(|a, b| {
+        (a[1] - a[0]).partial_cmp(&(b[1] - b[0])).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    match span {
+        Some(span) => Some(vec![(span[0] + span[1]) / 2.0, y]),
+        None => polygon[0].first().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> geojson::PolygonType {
+        vec![
+            vec![vec![0.0, 0.0], vec![4.0, 0.0], vec![4.0, 4.0], vec![0.0, 4.0], vec![0.0, 0.0]],
+            vec![vec![1.0, 1.0], vec![2.0, 1.0], vec![2.0, 3.0], vec![1.0, 3.0], vec![1.0, 1.0]]
+        ]
+    }
+
+    #[test]
+    fn test_polygon_contains() {
+        assert_eq!(polygon_contains(&square(), &[3.0, 3.0]), true);
+        assert_eq!(polygon_contains(&square(), &[4.0, 2.0]), true);
+        assert_eq!(polygon_contains(&square(), &[1.5, 1.5]), false);
+        assert_eq!(polygon_contains(&square(), &[5.0, 5.0]), false);
+    }
+
+    #[test]
+    fn test_point_on_surface() {
+        let pt = point_on_surface(&vec![square()]).unwrap();
+
+        assert_eq!(polygon_contains(&square(), &pt), true);
+        assert_eq!(pt, vec![3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_linestring_distance() {
+        let a = vec![vec![0.0, 0.0], vec![2.0, 0.0]];
+
+        assert_eq!(linestring_distance(&a, &vec![vec![1.0, 1.0], vec![1.0, 2.0]]), 1.0);
+        assert_eq!(linestring_distance(&a, &vec![vec![1.0, 1.0], vec![1.0, -1.0]]), 0.0);
+        assert_eq!(linestring_distance(&a, &vec![vec![3.0, 0.0]]), 1.0);
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::Error;
+
+pub mod address;
+pub mod network;
+pub mod polygon;
+pub mod geom;
+pub mod rtree;
+
+pub use self::address::Address;
+pub use self::network::Network;
+pub use self::polygon::Polygon;
+
+///
+/// Storage backend used by a mode
+///
+/// The memory backend keeps all features in process and is intended for
+/// small jobs where standing up a Postgres database is not worth the overhead
+///
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Postgres,
+    Memory
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Backend::Postgres
+    }
+}
+
+///
+/// In-memory equivalent of a map database, allowing
+/// separate map stages to operate on the same data
+///
+pub struct Database {
+    pub address: Address,
+    pub network: Network,
+
+    /// Index of the network cluster each address was matched to, equivalent to address.netid
+    pub netids: Vec<Option<usize>>,
+
+    /// Addresses grouped by the index of the network cluster they were matched to
+    pub address_cluster: Vec<(usize, Vec<usize>)>,
+    pub address_orphan_cluster: Vec<Vec<usize>>,
+    pub network_cluster: Vec<Vec<usize>>,
+    pub network_orphan_cluster: Vec<Vec<usize>>
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Database {
+            address: Address::new(),
+            network: Network::new(),
+            netids: Vec::new(),
+            address_cluster: Vec::new(),
+            address_orphan_cluster: Vec::new(),
+            network_cluster: Vec::new(),
+            network_orphan_cluster: Vec::new()
+        }
+    }
+
+    ///
+    /// Match each address to the nearest network cluster within the given distance
+    /// that has a name with the same tokenized form as one of the address names
+    ///
+    /// Only the exact token matches of the linker are considered, as the fuzzy
+    /// matching performed by the map matcher is not available in process
+    ///
+    pub fn link(&self, distance: f64) -> Vec<Option<usize>> {
+        let mut clusters: HashMap<usize, usize> = HashMap::new();
+        let mut names: Vec<HashSet<&str>> = Vec::with_capacity(self.network_cluster.len());
+
+        for (netid, cluster) in self.network_cluster.iter().enumerate() {
+            let mut tokenized = HashSet::new();
+
+            for net in cluster.iter() {
+                clusters.insert(*net, netid);
+
+                for name in self.network.networks[*net].names.names.iter() {
+                    tokenized.insert(name.tokenized.as_str());
+                }
+            }
+
+            names.push(tokenized);
+        }
+
+        self.address.addresses.iter().map(|addr| {
+            let mut nearest: Option<(usize, f64)> = None;
+
+            for (net, dist) in self.network.within(&addr.geom, distance) {
+                let netid = match clusters.get(&net) {
+                    Some(netid) => *netid,
+                    None => continue
+                };
+
+                if !addr.names.names.iter().any(|name| names[netid].contains(name.tokenized.as_str())) {
+                    continue;
+                }
+
+                match nearest {
+                    Some((_, min)) if min <= dist => (),
+                    _ => nearest = Some((netid, dist))
+                };
+            }
+
+            nearest.map(|(netid, _)| netid)
+        }).collect()
+    }
+
+    ///
+    /// Cluster the networks, equivalent to pg::NetworkCluster::generate
+    ///
+    /// Orphan clustering only considers networks that are not part of a network
+    /// cluster matched to an address cluster, so must follow address clustering
+    ///
+    pub fn cluster_networks(&mut self, orphan: bool) -> Result<usize, Error> {
+        match orphan {
+            true => {
+                let mut matched = HashSet::new();
+                for (netid, _) in self.address_cluster.iter() {
+                    matched.extend(self.network_cluster[*netid].iter().cloned());
+                }
+
+                self.network_orphan_cluster = self.network.cluster(0.005, |it| !matched.contains(&it))?;
+
+                Ok(self.network_orphan_cluster.len())
+            },
+            false => {
+                self.network_cluster = self.network.cluster(0.005, |_| true)?;
+
+                Ok(self.network_cluster.len())
+            }
+        }
+    }
+
+    ///
+    /// Cluster the addresses, equivalent to pg::AddressCluster::generate
+    ///
+    /// Addresses are first matched to the network clusters, with matched addresses
+    /// grouped by network cluster & the remaining addresses clustered as orphans
+    ///
+    pub fn cluster_addresses(&mut self, orphan: bool) -> Result<usize, Error> {
+        match orphan {
+            true => {
+                let netids = &self.netids;
+                let clusters = self.address.cluster(0.005, |it| netids.get(it).map_or(true, Option::is_none))?;
+                self.address_orphan_cluster = clusters;
+
+                Ok(self.address_orphan_cluster.len())
+            },
+            false => {
+                self.netids = self.link(0.02);
+                self.address_cluster = self.address.matched(&self.netids);
+
+                Ok(self.address_cluster.len())
+            }
+        }
+    }
+}
+
+lazy_static! {
+    ///
+    /// Memory backed databases, keyed by the db name given to each map stage
+    ///
+    static ref DATABASES: Mutex<HashMap<String, Database>> = Mutex::new(HashMap::new());
+}
+
+fn databases() -> MutexGuard<'static, HashMap<String, Database>> {
+    match DATABASES.lock() {
+        Ok(databases) => databases,
+        Err(poisoned) => poisoned.into_inner()
+    }
+}
+
+///
+/// Run the given function against the named memory database,
+/// creating the database if it does not yet exist
+///
+pub fn with_database<T>(db: &str, run: impl FnOnce(&mut Database) -> T) -> T {
+    run(databases().entry(String::from(db)).or_insert_with(Database::new))
+}
+
+///
+/// Start the named memory database afresh, discarding the data of any previous run
+///
+pub fn open_database(db: &str) {
+    databases().insert(String::from(db), Database::new());
+}
+
+///
+/// Release the named memory database once a map run has finished or failed
+///
+pub fn drop_database(db: &str) {
+    databases().remove(db);
+}
+
+///
+/// Union-Find structure used to build single linkage clusters
+///
+pub struct Clusters {
+    parents: Vec<usize>
+}
+
+impl Clusters {
+    pub fn new(size: usize) -> Self {
+        Clusters {
+            parents: (0..size).collect()
+        }
+    }
+
+    fn find(&mut self, mut it: usize) -> usize {
+        while self.parents[it] != it {
+            self.parents[it] = self.parents[self.parents[it]];
+            it = self.parents[it];
+        }
+
+        it
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+
+        if a < b {
+            self.parents[b] = a;
+        } else if b < a {
+            self.parents[a] = b;
+        }
+    }
+
+    ///
+    /// Return each cluster as a sorted list of members,
+    /// ordered by the lowest member of each cluster
+    ///
+    pub fn groups(mut self) -> Vec<Vec<usize>> {
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for it in 0..self.parents.len() {
+            let root = self.find(it);
+            groups.entry(root).or_insert_with(Vec::new).push(it);
+        }
+
+        let mut groups: Vec<Vec<usize>> = groups.into_iter().map(|(_, group)| group).collect();
+        groups.sort_by_key(|group| group[0]);
+
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::{Context, Tokens};
+
+    fn addr(number: &str, street: &str, lng: f64, lat: f64) -> Result<crate::Address, crate::Error> {
+        let feat: geojson::GeoJson = format!(r#"{{
+            "type": "Feature",
+            "properties": {{ "number": "{}", "street": [{{ "display": "{}", "priority": 0 }}] }},
+            "geometry": {{ "type": "Point", "coordinates": [{}, {}] }}
+        }}"#, number, street, lng, lat).parse().unwrap();
+
+        let context = Context::new(String::from("us"), None, Tokens::new(HashMap::new()));
+
+        Ok(crate::Address::new(feat, &context).unwrap())
+    }
+
+    fn net(street: &str, coords: serde_json::Value) -> Result<crate::Network, crate::Error> {
+        let feat: geojson::GeoJson = serde_json::json!({
+            "type": "Feature",
+            "properties": { "street": [{ "display": street, "priority": 0 }] },
+            "geometry": { "type": "LineString", "coordinates": coords }
+        }).to_string().parse().unwrap();
+
+        let context = Context::new(String::from("us"), None, Tokens::new(HashMap::new()));
+
+        Ok(crate::Network::new(feat, &context).unwrap())
+    }
+
+    #[test]
+    fn test_database_cluster() {
+        let mut database = Database::new();
+
+        database.network.input(vec![
+            net("Main St", serde_json::json!([[0.0, 0.0], [0.01, 0.0]])),
+            net("Side St", serde_json::json!([[0.0, 0.01], [0.01, 0.01]])),
+            net("Main St", serde_json::json!([[1.0, 0.0], [1.01, 0.0]]))
+        ].into_iter()).unwrap();
+        database.network.seq_id();
+        database.network.index();
+
+        database.address.input(vec![
+            addr("1", "Main St", 0.005, 0.0001),
+            addr("2", "Side St", 0.005, 0.0101),
+            addr("3", "Fake St", 0.005, 0.005)
+        ].into_iter()).unwrap();
+        database.address.seq_id();
+        database.address.index();
+
+        assert_eq!(database.cluster_networks(false).unwrap(), 3);
+        assert_eq!(database.network_cluster, vec![vec![0], vec![1], vec![2]]);
+
+        assert_eq!(database.cluster_addresses(false).unwrap(), 2);
+        assert_eq!(database.netids, vec![Some(0), Some(1), None]);
+        assert_eq!(database.address_cluster, vec![(0, vec![0]), (1, vec![1])]);
+
+        assert_eq!(database.cluster_addresses(true).unwrap(), 1);
+        assert_eq!(database.address_orphan_cluster, vec![vec![2]]);
+
+        assert_eq!(database.cluster_networks(true).unwrap(), 1);
+        assert_eq!(database.network_orphan_cluster, vec![vec![2]]);
+    }
+
+    #[test]
+    fn test_database_lifecycle() {
+        with_database("mem_lifecycle", |database| database.netids.push(None));
+        assert_eq!(with_database("mem_lifecycle", |database| database.netids.len()), 1);
+
+        open_database("mem_lifecycle");
+        assert_eq!(with_database("mem_lifecycle", |database| database.netids.len()), 0);
+
+        drop_database("mem_lifecycle");
+        assert!(!databases().contains_key("mem_lifecycle"));
+    }
+
+    #[test]
+    fn test_clusters() {
+        let mut clusters = Clusters::new(5);
+        clusters.union(3, 1);
+        clusters.union(4, 3);
+
+        assert_eq!(clusters.groups(), vec![vec![0], vec![1, 3, 4], vec![2]]);
+    }
+
+    #[test]
+    fn test_address() {
+        let mut address = Address::new();
+        address.input(vec![
+            addr("1", "Main St", 0.0, 0.0),
+            addr("2", "Main St", 0.001, 0.0),
+            addr("3", "Main St", 0.1, 0.0),
+            addr("4", "Side St", 0.0, 0.001)
+        ].into_iter()).unwrap();
+        address.seq_id();
+        address.index();
+
+        assert_eq!(address.count(), 4);
+
+        let mut within: Vec<i64> = address.within(&[0.0, 0.0], 0.0015).iter().map(|addr| addr.id.unwrap()).collect();
+        within.sort();
+        assert_eq!(within, vec![1, 2, 4]);
+
+        assert_eq!(address.cluster(0.005, |_| true).unwrap(), vec![vec![0, 1], vec![2], vec![3]]);
+        assert_eq!(address.cluster(0.005, |it| it != 1).unwrap(), vec![vec![0], vec![2], vec![3]]);
+    }
+}
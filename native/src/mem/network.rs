@@ -0,0 +1,134 @@
+use crate::Error;
+use super::{geom, Clusters};
+use super::rtree::{BBox, RTree};
+
+///
+/// In-memory equivalent of the pg::Network table
+///
+pub struct Network {
+    pub networks: Vec<crate::Network>,
+    tree: RTree
+}
+
+impl Network {
+    pub fn new() -> Self {
+        Network {
+            networks: Vec::new(),
+            tree: RTree::new(Vec::new())
+        }
+    }
+
+    pub fn input(&mut self, data: impl Iterator<Item = Result<crate::Network, Error>>) -> Result<(), Error> {
+        for net in data {
+            self.networks.push(net?);
+        }
+
+        Ok(())
+    }
+
+    pub fn seq_id(&mut self) {
+        for (it, net) in self.networks.iter_mut().enumerate() {
+            net.id = Some(it as i64 + 1);
+        }
+    }
+
+    ///
+    /// Build the spatial index, must be called after input
+    /// and before any spatial queries are made
+    ///
+    pub fn index(&mut self) {
+        let mut items = Vec::with_capacity(self.networks.len());
+
+        for (it, net) in self.networks.iter().enumerate() {
+            if let Some(bbox) = BBox::from_coords(net.geom.iter().flatten()) {
+                items.push((it, bbox));
+            }
+        }
+
+        self.tree = RTree::new(items);
+    }
+
+    pub fn count(&self) -> i64 {
+        self.networks.len() as i64
+    }
+
+    ///
+    /// Minimum distance between two network features
+    ///
+    fn distance(&self, a: usize, b: usize) -> f64 {
+        let mut min = std::f64::MAX;
+
+        for a_ln in self.networks[a].geom.iter() {
+            for b_ln in self.networks[b].geom.iter() {
+                min = min.min(geom::linestring_distance(a_ln, b_ln));
+            }
+        }
+
+        min
+    }
+
+    ///
+    /// Return the index & distance of all networks within the given distance
+    /// of a point, equivalent to ST_DWithin & ST_Distance
+    ///
+    pub fn within(&self, point: &[f64], distance: f64) -> Vec<(usize, f64)> {
+        self.tree.search(&BBox::point(point[0], point[1]).buffer(distance)).into_iter().map(|it| {
+            let mut min = std::f64::MAX;
+
+            for ln in self.networks[it].geom.iter() {
+                match ln.len() {
+                    1 => min = min.min(geom::distance(point, &ln[0])),
+                    _ => for seg in ln.windows(2) {
+                        min = min.min(geom::segment_distance(point, &seg[0], &seg[1]));
+                    }
+                }
+            }
+
+            (it, min)
+        }).filter(|&(_, dist)| dist <= distance).collect()
+    }
+
+    ///
+    /// Cluster networks with the same primary name that are within the given
+    /// distance of each other, equivalent to ST_ClusterWithin grouped by the
+    /// primary name. Unnamed networks & networks for which include returns
+    /// false are not clustered
+    ///
+    /// Clusters are returned as indexes into the network list
+    ///
+    pub fn cluster(&self, distance: f64, include: impl Fn(usize) -> bool) -> Result<Vec<Vec<usize>>, Error> {
+        let mut keys: Vec<Option<String>> = Vec::with_capacity(self.networks.len());
+        for (it, net) in self.networks.iter().enumerate() {
+            keys.push(match net.names.names.first() {
+                Some(name) if !name.tokenized.is_empty() && include(it) => Some(serde_json::to_string(name)?),
+                _ => None
+            });
+        }
+
+        let mut clusters = Clusters::new(self.networks.len());
+
+        for (it, net) in self.networks.iter().enumerate() {
+            if keys[it].is_none() {
+                continue;
+            }
+
+            let bbox = match BBox::from_coords(net.geom.iter().flatten()) {
+                Some(bbox) => bbox.buffer(distance),
+                None => continue
+            };
+
+            for other in self.tree.search(&bbox) {
+                if other > it
+                    && keys[it] == keys[other]
+                    && self.distance(it, other) <= distance
+                {
+                    clusters.union(it, other);
+                }
+            }
+        }
+
+        Ok(clusters.groups().into_iter().filter(|cluster| {
+            keys[cluster[0]].is_some()
+        }).collect())
+    }
+}
@@ -0,0 +1,90 @@
+use crate::Error;
+use super::geom;
+use super::rtree::{BBox, RTree};
+
+///
+/// In-memory equivalent of the pg::Polygon table
+///
+pub struct Polygon {
+    pub name: String,
+    pub polygons: Vec<crate::Polygon>,
+    tree: RTree,
+
+    /// Point on surface of each polygon, equivalent to ST_PointOnSurface
+    surfaces: Vec<Option<Vec<f64>>>,
+    surface_tree: RTree
+}
+
+impl Polygon {
+    pub fn new(name: String) -> Self {
+        Polygon {
+            name: name,
+            polygons: Vec::new(),
+            tree: RTree::new(Vec::new()),
+            surfaces: Vec::new(),
+            surface_tree: RTree::new(Vec::new())
+        }
+    }
+
+    pub fn input(&mut self, data: impl Iterator<Item = Result<crate::Polygon, Error>>) -> Result<(), Error> {
+        for poly in data {
+            self.polygons.push(poly?);
+        }
+
+        Ok(())
+    }
+
+    pub fn seq_id(&mut self) {
+        for (it, poly) in self.polygons.iter_mut().enumerate() {
+            poly.id = Some(it as i64 + 1);
+        }
+    }
+
+    ///
+    /// Build the spatial indexes, must be called after input
+    /// and before any spatial queries are made
+    ///
+    pub fn index(&mut self) {
+        let mut items = Vec::with_capacity(self.polygons.len());
+
+        for (it, poly) in self.polygons.iter().enumerate() {
+            if let Some(bbox) = BBox::from_coords(poly.geom.iter().flatten().flatten()) {
+                items.push((it, bbox));
+            }
+        }
+
+        self.tree = RTree::new(items);
+
+        self.surfaces = self.polygons.iter().map(|poly| geom::point_on_surface(&poly.geom)).collect();
+
+        self.surface_tree = RTree::new(self.surfaces.iter().enumerate().filter_map(|(it, surface)| {
+            surface.as_ref().map(|pt| (it, BBox::point(pt[0], pt[1])))
+        }).collect());
+    }
+
+    pub fn count(&self) -> i64 {
+        self.polygons.len() as i64
+    }
+
+    ///
+    /// Does the point intersect any of the polygons, equivalent to ST_Intersects
+    ///
+    pub fn intersects(&self, point: &[f64]) -> bool {
+        self.tree.search(&BBox::point(point[0], point[1])).into_iter().any(|it| {
+            self.polygons[it].geom.iter().any(|polygon| geom::polygon_contains(polygon, point))
+        })
+    }
+
+    ///
+    /// Is the point within the given distance of the point on surface of any
+    /// of the polygons, equivalent to ST_DWithin(geom, ST_PointOnSurface(poly))
+    ///
+    pub fn surface_within(&self, point: &[f64], distance: f64) -> bool {
+        self.surface_tree.search(&BBox::point(point[0], point[1]).buffer(distance)).into_iter().any(|it| {
+            match self.surfaces[it] {
+                Some(ref surface) => geom::distance(surface, point) <= distance,
+                None => false
+            }
+        })
+    }
+}
@@ -0,0 +1,212 @@
+///
+/// Axis aligned bounding box in lng/lat degrees
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64
+}
+
+impl BBox {
+    pub fn point(x: f64, y: f64) -> Self {
+        BBox {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y
+        }
+    }
+
+    ///
+    /// Bounding box of all the given coordinates, None if there are none
+    ///
+    pub fn from_coords<'a>(coords: impl Iterator<Item = &'a Vec<f64>>) -> Option<Self> {
+        let mut bbox: Option<BBox> = None;
+
+        for coord in coords {
+            let pt = BBox::point(coord[0], coord[1]);
+
+            bbox = match bbox {
+                None => Some(pt),
+                Some(bbox) => Some(bbox.expand(&pt))
+            };
+        }
+
+        bbox
+    }
+
+    ///
+    /// Return a new bbox which has been buffered by the given distance
+    ///
+    pub fn buffer(&self, distance: f64) -> Self {
+        BBox {
+            min_x: self.min_x - distance,
+            min_y: self.min_y - distance,
+            max_x: self.max_x + distance,
+            max_y: self.max_y + distance
+        }
+    }
+
+    pub fn expand(&self, other: &BBox) -> Self {
+        BBox {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y)
+        }
+    }
+
+    pub fn intersects(&self, other: &BBox) -> bool {
+        self.min_x <= other.max_x
+            && self.max_x >= other.min_x
+            && self.min_y <= other.max_y
+            && self.max_y >= other.min_y
+    }
+
+    fn center(&self) -> (f64, f64) {
+        ((self.min_x + self.max_x) / 2.0, (self.min_y + self.max_y) / 2.0)
+    }
+}
+
+const NODE_SIZE: usize = 16;
+
+struct Node {
+    bbox: BBox,
+
+    /// At the leaf level these are item ids, otherwise
+    /// they are indexes into the level below
+    children: Vec<(usize, BBox)>
+}
+
+///
+/// Static R-Tree, bulk loaded using the Sort-Tile-Recursive algorithm
+///
+pub struct RTree {
+    levels: Vec<Vec<Node>>
+}
+
+impl RTree {
+    pub fn new(items: Vec<(usize, BBox)>) -> Self {
+        let mut levels: Vec<Vec<Node>> = Vec::new();
+
+        if items.is_empty() {
+            return RTree {
+                levels: levels
+            };
+        }
+
+        levels.push(RTree::pack(items));
+
+        while levels[levels.len() - 1].len() > 1 {
+            let entries = levels[levels.len() - 1].iter().enumerate().map(|(it, node)| {
+                (it, node.bbox)
+            }).collect();
+
+            levels.push(RTree::pack(entries));
+        }
+
+        RTree {
+            levels: levels
+        }
+    }
+
+    fn pack(mut entries: Vec<(usize, BBox)>) -> Vec<Node> {
+        let nodes = (entries.len() + NODE_SIZE - 1) / NODE_SIZE;
+        let slices = (nodes as f64).sqrt().ceil() as usize;
+
+        entries.sort_by(|a, b| {
+            a.1.center().0.partial_cmp(&b.1.center().0).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut packed = Vec::with_capacity(nodes);
+
+        for slice in entries.chunks_mut(slices * NODE_SIZE) {
+            slice.sort_by(|a, b| {
+                a.1.center().1.partial_cmp(&b.1.center().1).unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            for group in slice.chunks(NODE_SIZE) {
+                let mut bbox = group[0].1;
+                for entry in group.iter().skip(1) {
+                    bbox = bbox.expand(&entry.1);
+                }
+
+                packed.push(Node {
+                    bbox: bbox,
+                    children: group.to_vec()
+                });
+            }
+        }
+
+        packed
+    }
+
+    ///
+    /// Return the ids of all items whose bbox intersects the query bbox
+    ///
+    pub fn search(&self, query: &BBox) -> Vec<usize> {
+        let mut results = Vec::new();
+
+        if self.levels.is_empty() {
+            return results;
+        }
+
+        let top = self.levels.len() - 1;
+        let mut stack: Vec<(usize, usize)> = (0..self.levels[top].len()).map(|it| (top, it)).collect();
+
+        while let Some((level, it)) = stack.pop() {
+            let node = &self.levels[level][it];
+
+            if !node.bbox.intersects(query) {
+                continue;
+            }
+
+            for child in node.children.iter() {
+                if !child.1.intersects(query) {
+                    continue;
+                }
+
+                if level == 0 {
+                    results.push(child.0);
+                } else {
+                    stack.push((level - 1, child.0));
+                }
+            }
+        }
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtree_search() {
+        let mut items = Vec::new();
+        for x in 0..100 {
+            for y in 0..100 {
+                items.push((x * 100 + y, BBox::point(x as f64, y as f64)));
+            }
+        }
+
+        let tree = RTree::new(items);
+
+        let mut results = tree.search(&BBox::point(10.0, 10.0).buffer(1.0));
+        results.sort();
+
+        assert_eq!(results, vec![909, 910, 911, 1009, 1010, 1011, 1109, 1110, 1111]);
+
+        assert_eq!(tree.search(&BBox::point(-10.0, -10.0)), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_rtree_empty() {
+        let tree = RTree::new(Vec::new());
+
+        assert_eq!(tree.search(&BBox::point(0.0, 0.0)), Vec::<usize>::new());
+    }
+}
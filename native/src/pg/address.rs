@@ -1,6 +1,7 @@
 use postgres::{Connection};
 use std::io::Read;
-use super::{Table, InputTable};
+use super::{Table, InputTable, CopyReader};
+use crate::Error;
 
 pub struct Address ();
 
@@ -11,14 +12,14 @@ impl Address {
 }
 
 impl Table for Address {
-    fn create(&self, conn: &Connection) {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
              CREATE EXTENSION IF NOT EXISTS POSTGIS
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             DROP TABLE IF EXISTS address;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE UNLOGGED TABLE address (
@@ -27,60 +28,68 @@ impl Table for Address {
                 netid BIGINT,
                 names JSONB,
                 number TEXT,
+                unit TEXT,
+                postcode TEXT,
+                locality TEXT,
                 source TEXT,
                 output BOOLEAN,
                 props JSONB,
                 geom GEOMETRY(POINT, 4326)
             )
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 
-    fn count(&self, conn: &Connection) -> i64 {
-        match conn.query(r#"
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let res = conn.query(r#"
             SELECT count(*) FROM address
-        "#, &[]) {
-            Ok(res) => {
-                let cnt: i64 = res.get(0).get(0);
-                cnt
-            },
-            _ => 0
-        }
+        "#, &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
     }
 
-    fn index(&self, conn: &Connection) {
+    fn index(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
             ALTER TABLE address
                 ALTER COLUMN geom
                 TYPE GEOMETRY(POINTZ, 4326)
                 USING ST_SetSRID(ST_MakePoint(ST_X(geom), ST_Y(geom), COALESCE(id::FLOAT, 0)), 4326);
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE INDEX address_idx ON address (id);
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE INDEX address_gix ON address USING GIST (geom);
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CLUSTER address USING address_idx;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             ANALYZE address;
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 }
 
 impl InputTable for Address {
-    fn input(&self, conn: &Connection, mut data: impl Read) {
+    fn input(&self, conn: &Connection, data: impl Read) -> Result<(), Error> {
         let stmt = conn.prepare(format!(r#"
             COPY address (
                 id,
                 version,
                 names,
                 number,
+                unit,
+                postcode,
+                locality,
                 source,
                 output,
                 props,
@@ -88,28 +97,31 @@ impl InputTable for Address {
             )
             FROM STDIN
             WITH (
-                FORMAT CSV,
-                NULL '',
-                DELIMITER E'\t',
-                QUOTE E'\b'
+                FORMAT BINARY
             )
-        "#).as_str()).unwrap();
+        "#).as_str())?;
+
+        let mut data = CopyReader::new(data);
+        let res = stmt.copy_in(&[], &mut data);
+        data.finish(res)?;
 
-        stmt.copy_in(&[], &mut data).unwrap();
+        Ok(())
     }
 
-    fn seq_id(&self, conn: &Connection) {
+    fn seq_id(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
             DROP SEQUENCE IF EXISTS address_seq;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE SEQUENCE address_seq;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             UPDATE address
                 SET id = nextval('address_seq');
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use postgres::{Connection};
 use super::Table;
+use crate::Error;
 
 pub struct AddressCluster {
     orphan: bool
@@ -12,10 +13,17 @@ impl AddressCluster {
         }
     }
 
+    fn table(&self) -> String {
+        match self.orphan {
+            true => String::from("address_orphan_cluster"),
+            false => String::from("address_cluster")
+        }
+    }
+
     ///
     /// Cluster address points
     ///
-    pub fn generate(&self, conn: &postgres::Connection) {
+    pub fn generate(&self, conn: &postgres::Connection) -> Result<(), Error> {
         if self.orphan {
             conn.execute(r#"
                 INSERT INTO address_orphan_cluster (names, geom)
@@ -30,7 +38,7 @@ impl AddressCluster {
                         WHERE netid IS NULL
                         GROUP BY names
                     ) addr;
-            "#, &[]).unwrap();
+            "#, &[])?;
         } else {
             conn.execute(r#"
                 INSERT INTO address_cluster (names, geom, netid)
@@ -53,41 +61,79 @@ impl AddressCluster {
                     ) a
                     GROUP BY
                         netid;
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             conn.execute(r#"
                 UPDATE network_cluster n
                     SET address = a.id
                     FROM address_cluster a
                     WHERE n.id = a.netid;
-            "#, &[]).unwrap();
+            "#, &[])?;
         }
+
+        self.aggregate(conn)
+    }
+
+    ///
+    /// Populate the distinct postcodes & localities of each cluster from the
+    /// addresses that make it up, via the id encoded Z coordinates of the geometry
+    ///
+    fn aggregate(&self, conn: &postgres::Connection) -> Result<(), Error> {
+        conn.execute(format!(r#"
+            UPDATE {table}
+                SET
+                    postcode = final.postcode,
+                    locality = final.locality
+                FROM (
+                    SELECT
+                        ac.id,
+                        array_agg(DISTINCT a.postcode ORDER BY a.postcode) FILTER (WHERE a.postcode IS NOT NULL) AS postcode,
+                        array_agg(DISTINCT a.locality ORDER BY a.locality) FILTER (WHERE a.locality IS NOT NULL) AS locality
+                    FROM
+                        (
+                            SELECT
+                                id,
+                                ST_Z((ST_Dump(geom)).geom)::BIGINT AS z
+                            FROM
+                                {table}
+                        ) ac,
+                        address a
+                    WHERE a.id = ac.z
+                    GROUP BY ac.id
+                ) final
+                WHERE
+                    final.id = {table}.id;
+        "#, table = self.table()).as_str(), &[])?;
+
+        Ok(())
     }
 }
 
 impl Table for AddressCluster {
-    fn create(&self, conn: &Connection) {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
              CREATE EXTENSION IF NOT EXISTS POSTGIS
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         if self.orphan {
             conn.execute(r#"
                 DROP TABLE IF EXISTS address_orphan_cluster;
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             conn.execute(r#"
                 CREATE UNLOGGED TABLE address_orphan_cluster (
                     ID SERIAL,
                     names JSONB,
                     geom GEOMETRY(MULTIPOINTZ, 4326),
+                    postcode TEXT[],
+                    locality TEXT[],
                     props JSONB
                 )
-            "#, &[]).unwrap();
+            "#, &[])?;
         } else {
             conn.execute(r#"
                 DROP TABLE IF EXISTS address_cluster;
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             conn.execute(r#"
                 CREATE UNLOGGED TABLE address_cluster (
@@ -95,50 +141,48 @@ impl Table for AddressCluster {
                     netid BIGINT,
                     names JSONB,
                     geom GEOMETRY(MULTIPOINTZ, 4326),
+                    postcode TEXT[],
+                    locality TEXT[],
                     props JSONB
                 )
-            "#, &[]).unwrap();
+            "#, &[])?;
 
         }
+
+        Ok(())
     }
 
-    fn count(&self, conn: &Connection) -> i64 {
-        let table = match self.orphan {
-            true => String::from("address_orphan_cluster"),
-            false => String::from("address_cluster")
-        };
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let table = self.table();
 
-        match conn.query(format!("
+        let res = conn.query(format!("
             SELECT count(*) FROM {}
-        ", table).as_str(), &[]) {
-            Ok(res) => {
-                let cnt: i64 = res.get(0).get(0);
-                cnt
-            },
-            _ => 0
-        }
+        ", table).as_str(), &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
     }
 
-    fn index(&self, conn: &Connection) {
-        let table = match self.orphan {
-            true => String::from("address_orphan_cluster"),
-            false => String::from("address_cluster")
-        };
+    fn index(&self, conn: &Connection) -> Result<(), Error> {
+        let table = self.table();
 
         conn.execute(format!("
             CREATE INDEX IF NOT EXISTS {table}_idx ON {table} (id);
-        ", table = &table).as_str(), &[]).unwrap();
+        ", table = &table).as_str(), &[])?;
 
         conn.execute(format!("
             CREATE INDEX IF NOT EXISTS {table}_gix ON {table} USING GIST (geom);
-        ", table = table).as_str(), &[]).unwrap();
+        ", table = table).as_str(), &[])?;
 
         conn.execute(format!("
             CLUSTER {table} USING {table}_gix;
-        ", table = table).as_str(), &[]).unwrap();
+        ", table = table).as_str(), &[])?;
 
         conn.execute(format!("
             ANALYZE {table};
-        ", table = table).as_str(), &[]).unwrap();
+        ", table = table).as_str(), &[])?;
+
+        Ok(())
     }
 }
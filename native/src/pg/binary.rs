@@ -0,0 +1,127 @@
+use postgis::ewkb::EwkbWrite;
+use serde::Serialize;
+use crate::Error;
+
+///
+/// Signature, flags & header extension length that begin every binary COPY
+///
+pub const HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0\0\0\0\0\0\0\0\0";
+
+///
+/// A field count of -1 marks the end of a binary COPY
+///
+pub const TRAILER: &[u8] = &[0xff, 0xff];
+
+///
+/// Builds a single tuple of a binary COPY
+///
+/// Unlike the text format values are length prefixed, so no escaping
+/// of tabs, newlines or quotes is required
+///
+pub struct Row {
+    buf: Vec<u8>
+}
+
+impl Row {
+    pub fn new(fields: i16) -> Self {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&fields.to_be_bytes());
+
+        Row {
+            buf: buf
+        }
+    }
+
+    fn field(&mut self, value: &[u8]) {
+        self.buf.extend_from_slice(&(value.len() as i32).to_be_bytes());
+        self.buf.extend_from_slice(value);
+    }
+
+    pub fn null(&mut self) {
+        self.buf.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+
+    pub fn int8(&mut self, value: Option<i64>) {
+        match value {
+            None => self.null(),
+            Some(value) => self.field(&value.to_be_bytes())
+        }
+    }
+
+    pub fn boolean(&mut self, value: bool) {
+        self.field(&[value as u8]);
+    }
+
+    pub fn text(&mut self, value: &str) {
+        self.field(value.as_bytes());
+    }
+
+    pub fn jsonb(&mut self, value: &impl Serialize) -> Result<(), Error> {
+        // JSONB is sent as a version byte followed by the JSON text
+        let mut json = vec![1];
+        serde_json::to_writer(&mut json, value)?;
+
+        self.field(&json);
+
+        Ok(())
+    }
+
+    pub fn geometry(&mut self, value: &impl EwkbWrite) -> Result<(), Error> {
+        let mut ewkb = Vec::new();
+
+        if let Err(err) = value.write_ewkb(&mut ewkb) {
+            return Err(Error::Validation(format!("Failed to encode geometry: {}", err)));
+        }
+
+        self.field(&ewkb);
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use postgis::ewkb::AsEwkbPoint;
+
+    #[test]
+    fn test_row() {
+        let mut row = Row::new(5);
+        row.int8(Some(1));
+        row.null();
+        row.boolean(true);
+        row.text("Main\t\"St\"\n");
+        row.jsonb(&json!({ "a": 1 })).unwrap();
+
+        assert_eq!(row.finish(), vec![
+            0, 5,
+            0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 1,
+            255, 255, 255, 255,
+            0, 0, 0, 1, 1,
+            0, 0, 0, 11, b'M', b'a', b'i', b'n', b'\t', b'"', b'S', b't', b'"', b'\n',
+            0, 0, 0, 8, 1, b'{', b'"', b'a', b'"', b':', b'1', b'}'
+        ]);
+    }
+
+    #[test]
+    fn test_row_geometry() {
+        let point = postgis::ewkb::Point::new(1.0, 2.0, Some(4326));
+
+        let mut row = Row::new(1);
+        row.geometry(&point.as_ewkb()).unwrap();
+
+        let row = row.finish();
+
+        assert_eq!(&row[0..6], &[0, 1, 0, 0, 0, 25]);
+        assert_eq!(row[6..].to_vec(), {
+            let mut ewkb = Vec::new();
+            point.as_ewkb().write_ewkb(&mut ewkb).unwrap();
+            ewkb
+        });
+    }
+}
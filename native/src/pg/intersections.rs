@@ -1,5 +1,6 @@
 use postgres::{Connection};
 use super::Table;
+use crate::Error;
 
 pub struct Intersections ();
 
@@ -11,7 +12,7 @@ impl Intersections {
     ///
     /// Create intersections from network data
     ///
-    pub fn generate(&self, conn: &postgres::Connection) {
+    pub fn generate(&self, conn: &postgres::Connection) -> Result<(), Error> {
         conn.execute(r#"
             INSERT INTO intersections (a_id, b_id, a_street, b_street, geom) (
                 SELECT
@@ -27,19 +28,21 @@ impl Intersections {
                     a.id != b.id
                     AND ST_Intersects(a.geom, b.geom)
             )
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 }
 
 impl Table for Intersections {
-    fn create(&self, conn: &Connection) {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
              CREATE EXTENSION IF NOT EXISTS POSTGIS
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             DROP TABLE IF EXISTS intersections;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE UNLOGGED TABLE intersections (
@@ -50,28 +53,30 @@ impl Table for Intersections {
                 b_street JSONB,
                 geom GEOMETRY(POINT, 4326)
             )
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 
-    fn count(&self, conn: &Connection) -> i64 {
-        match conn.query("
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let res = conn.query("
             SELECT count(*) FROM intersections
-        ", &[]) {
-            Ok(res) => {
-                let cnt: i64 = res.get(0).get(0);
-                cnt
-            },
-            _ => 0
-        }
+        ", &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
     }
 
-    fn index(&self, conn: &Connection) {
+    fn index(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute("
             CREATE INDEX IF NOT EXISTS intersections_idx ON intersections (id);
-        ", &[]).unwrap();
+        ", &[])?;
 
         conn.execute("
             CREATE INDEX IF NOT EXISTS intersections_gix ON intersections USING GIST (geom);
-        ", &[]).unwrap();
+        ", &[])?;
+
+        Ok(())
     }
 }
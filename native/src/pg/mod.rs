@@ -1,15 +1,21 @@
 use std::iter::Iterator;
+use std::collections::VecDeque;
 use postgres::{Connection};
+use postgres::types::ToSql;
+use serde::de::DeserializeOwned;
 use std::io::Read;
-use std::mem;
 use serde_json::Value;
+use crate::Error;
 
 pub mod address;
+pub mod binary;
 pub mod addresscluster;
 pub mod network;
 pub mod networkcluster;
 pub mod polygon;
 pub mod intersections;
+pub mod pool;
+pub mod stages;
 
 pub use self::address::Address;
 pub use self::addresscluster::AddressCluster;
@@ -19,11 +25,13 @@ pub use self::networkcluster::NetworkCluster;
 
 pub use self::polygon::Polygon;
 pub use self::intersections::Intersections;
+pub use self::pool::{Config, Pool, PooledConnection};
+pub use self::stages::Stages;
 
 pub trait Table {
-    fn create(&self, conn: &Connection);
-    fn count(&self, conn: &Connection) -> i64;
-    fn index(&self, conn: &Connection);
+    fn create(&self, conn: &Connection) -> Result<(), Error>;
+    fn count(&self, conn: &Connection) -> Result<i64, Error>;
+    fn index(&self, conn: &Connection) -> Result<(), Error>;
 }
 
 ///
@@ -31,83 +39,159 @@ pub trait Table {
 /// will implement the InputTable Property
 ///
 pub trait InputTable {
-    fn input(&self, conn: &Connection, data: impl Read);
-    fn seq_id(&self, conn: &Connection);
+    fn input(&self, conn: &Connection, data: impl Read) -> Result<(), Error>;
+    fn seq_id(&self, conn: &Connection) -> Result<(), Error>;
 }
 
 ///
-/// Relatively limited cursor wrapper that will allow a cursor to be
-/// created that returns a single Serde_Json::Value field
+/// Wraps the Read impl handed to COPY, holding onto any error raised
+/// by the underlying stream so that it is returned with its original
+/// code instead of as a generic COPY failure
 ///
-pub struct Cursor {
+pub struct CopyReader<R: Read> {
+    inner: R,
+    error: Option<Error>
+}
+
+impl<R: Read> CopyReader<R> {
+    pub fn new(inner: R) -> Self {
+        CopyReader {
+            inner: inner,
+            error: None
+        }
+    }
+
+    pub fn finish(self, res: Result<u64, postgres::Error>) -> Result<u64, Error> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(res?)
+        }
+    }
+}
+
+impl<R: Read> Read for CopyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.inner.read(buf) {
+            Ok(len) => Ok(len),
+            Err(err) => {
+                let err = Error::from(err);
+                let msg = err.to_string();
+                self.error = Some(err);
+
+                Err(std::io::Error::new(std::io::ErrorKind::Other, msg))
+            }
+        }
+    }
+}
+
+///
+/// Server side cursor which owns its connection, binds its parameters and
+/// deserializes each row into T via serde. The query must return a single
+/// JSON/JSONB column, ie: `SELECT row_to_json(t) FROM (...) t`
+///
+/// The cursor is declared inside of a transaction that is committed when the
+/// Cursor is dropped, returning the connection to the pool in a clean state
+///
+pub struct Cursor<T: DeserializeOwned> {
     pub fetch: i64,
-    pub query: String,
-    trans: postgres::transaction::Transaction<'static>,
-    #[allow(dead_code)]
-    conn: Box<postgres::Connection>,
-    cache: Vec<Value>
+    conn: PooledConnection,
+    cache: VecDeque<T>,
+    done: bool
 }
 
-impl Cursor {
-    pub fn new(conn: Connection, query: String) -> Result<Self, String> {
+impl<T: DeserializeOwned> Cursor<T> {
+    pub fn new(conn: PooledConnection, query: &str, params: &[&dyn ToSql]) -> Result<Self, Error> {
         let fetch = 1000;
 
-        let pg_conn = Box::new(conn);
+        conn.batch_execute("BEGIN")?;
 
-        let trans: postgres::transaction::Transaction = unsafe {
-            mem::transmute(match pg_conn.transaction() {
-                Ok(trans) => trans,
-                Err(err) => {
-                    return Err(err.to_string());
-                }
-            })
+        // The cursor is built as soon as the transaction is open, so that if the query
+        // cannot be declared Drop still ends the transaction before the connection is
+        // returned to the pool
+        let cursor = Cursor {
+            fetch: fetch,
+            conn: conn,
+            cache: VecDeque::with_capacity(fetch as usize),
+            done: false
         };
 
-        match trans.execute(format!(r#"
-            DECLARE next_cursor CURSOR FOR {}
-        "#, &query).as_str(), &[]) {
-            Err(err) => {
-                return Err(err.to_string());
-            },
-            _ => ()
-        };
+        cursor.conn.execute(format!(r#"
+            DECLARE next_cursor NO SCROLL CURSOR FOR {}
+        "#, query).as_str(), params)?;
 
-        Ok(Cursor {
-            fetch: fetch,
-            conn: pg_conn,
-            trans: trans,
-            query: query,
-            cache: Vec::with_capacity(fetch as usize)
-        })
+        Ok(cursor)
+    }
+
+    fn fill(&mut self) -> Result<(), Error> {
+        let rows = self.conn.query(format!(r#"
+            FETCH {} FROM next_cursor
+        "#, &self.fetch).as_str(), &[])?;
+
+        // Cursor is finished
+        if rows.is_empty() {
+            self.done = true;
+        }
+
+        for row in rows.iter() {
+            let json: Value = match row.get_opt(0) {
+                Some(Ok(json)) => json,
+                Some(Err(err)) => { return Err(Error::Postgres(err)); },
+                None => { return Err(Error::Parse(String::from("Cursor query must return a single JSON column"))); }
+            };
+
+            self.cache.push_back(serde_json::from_value(json)?);
+        }
+
+        Ok(())
     }
 }
 
-impl Iterator for Cursor {
-    type Item = Value;
+impl<T: DeserializeOwned> Iterator for Cursor<T> {
+    type Item = Result<T, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.cache.is_empty() {
-            return self.cache.pop()
+        if self.cache.is_empty() && !self.done {
+            if let Err(err) = self.fill() {
+                // Don't keep fetching from a cursor that is in a failed transaction
+                self.done = true;
+                self.cache.clear();
+
+                return Some(Err(err));
+            }
         }
 
-        let rows = match self.trans.query(format!(r#"
-            FETCH {} FROM next_cursor
-        "#, &self.fetch).as_str(), &[]) {
-            Ok(rows) => rows,
-            Err(err) => panic!("Fetch Error: {}", err.to_string())
-        };
-
-        // Cursor is finished
-        if rows.is_empty() {
-            return None;
-        } else {
-            self.cache = rows.iter().map(|row| {
-                let json: Value = row.get(0);
-                json
-            }).collect();
-
-            return self.cache.pop();
+        match self.cache.pop_front() {
+            Some(row) => Some(Ok(row)),
+            None => None
         }
     }
 }
 
+impl<T: DeserializeOwned> Drop for Cursor<T> {
+    fn drop(&mut self) {
+        // Committing closes the cursor, if the transaction failed this is a ROLLBACK
+        self.conn.batch_execute("COMMIT").ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cursor_failed_query() {
+        // A single connection, so that the failed cursor's connection is the one reused
+        let mut config = Config::new("postgres").unwrap();
+        config.size = 1;
+        let pool = config.pool().unwrap();
+
+        let cursor: Result<Cursor<Value>, Error> = Cursor::new(pool.get().unwrap(), "SELECT * FROM pt2itp_missing_table", &[]);
+        assert!(cursor.is_err());
+
+        let cursor: Cursor<Value> = Cursor::new(pool.get().unwrap(), "SELECT JSON_Build_Object('id', id) FROM generate_series(1, 2) id", &[]).unwrap();
+        let rows: Vec<Value> = cursor.collect::<Result<Vec<Value>, Error>>().unwrap();
+
+        assert_eq!(rows, vec![json!({ "id": 1 }), json!({ "id": 2 })]);
+    }
+}
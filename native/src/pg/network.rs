@@ -1,6 +1,7 @@
 use postgres::{Connection};
 use std::io::Read;
-use super::{Table, InputTable};
+use super::{Table, InputTable, CopyReader};
+use crate::Error;
 
 pub struct Network ();
 
@@ -11,99 +12,108 @@ impl Network {
 }
 
 impl Table for Network {
-    fn create(&self, conn: &Connection) {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
              CREATE EXTENSION IF NOT EXISTS POSTGIS
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             DROP TABLE IF EXISTS network;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE UNLOGGED TABLE network (
                 id BIGINT,
                 names JSONB,
                 source TEXT,
+                postcode TEXT,
+                locality TEXT,
                 props JSONB,
                 geom GEOMETRY(MultiLineString, 4326)
             )
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 
-    fn count(&self, conn: &Connection) -> i64 {
-        match conn.query(r#"
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let res = conn.query(r#"
             SELECT count(*) FROM network
-        "#, &[]) {
-            Ok(res) => {
-                let cnt: i64 = res.get(0).get(0);
-                cnt
-            },
-            _ => 0
-        }
+        "#, &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
     }
 
 
-    fn index(&self, conn: &Connection) {
+    fn index(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
             ALTER TABLE network
                 ALTER COLUMN geom
                 TYPE GEOMETRY(MULTILINESTRINGZ, 4326)
                 USING ST_GEomFromEWKT(Regexp_Replace(ST_AsEWKT(geom)::TEXT, '(?<=\d)(?=[,)])', ' '||id, 'g'))
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE INDEX network_idx ON network (id);
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE INDEX network_gix ON network USING GIST (geom);
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CLUSTER network USING network_idx;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             ANALYZE network;
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 }
 
 impl InputTable for Network {
-    fn input(&self, conn: &Connection, mut data: impl Read) {
+    fn input(&self, conn: &Connection, data: impl Read) -> Result<(), Error> {
         let stmt = conn.prepare(format!(r#"
             COPY network (
                 names,
                 source,
+                postcode,
+                locality,
                 props,
                 geom
             )
             FROM STDIN
             WITH (
-                FORMAT CSV,
-                NULL '',
-                DELIMITER E'\t',
-                QUOTE E'\b'
+                FORMAT BINARY
             )
-        "#).as_str()).unwrap();
+        "#).as_str())?;
+
+        let mut data = CopyReader::new(data);
+        let res = stmt.copy_in(&[], &mut data);
+        data.finish(res)?;
 
-        stmt.copy_in(&[], &mut data).unwrap();
+        Ok(())
     }
 
-    fn seq_id(&self, conn: &Connection) {
+    fn seq_id(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
             DROP SEQUENCE IF EXISTS network_seq;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             CREATE SEQUENCE network_seq;
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(r#"
             UPDATE network
                 SET id = nextval('network_seq');
-        "#, &[]).unwrap();
+        "#, &[])?;
+
+        Ok(())
     }
 
 }
@@ -1,5 +1,6 @@
 use postgres::{Connection};
 use super::Table;
+use crate::Error;
 
 pub struct NetworkCluster {
     orphan: bool
@@ -12,14 +13,47 @@ impl NetworkCluster {
         }
     }
 
+    fn table(&self) -> String {
+        match self.orphan {
+            true => String::from("network_orphan_cluster"),
+            false => String::from("network_cluster")
+        }
+    }
+
     ///
     /// Cluster network linestrings
     ///
-    pub fn generate(&self, conn: &postgres::Connection) {
+    /// Orphan clustering should be run after addresses have been clustered
+    /// as it only considers network features which were not matched to an
+    /// address cluster
+    ///
+    pub fn generate(&self, conn: &postgres::Connection) -> Result<(), Error> {
         if self.orphan {
             conn.execute(r#"
-                // TODO
-            "#, &[]).unwrap();
+                INSERT INTO network_orphan_cluster (geom)
+                    SELECT
+                        ST_Multi(ST_CollectionExtract(orphan.geom, 2)) AS geom
+                    FROM (
+                        SELECT
+                            unnest(ST_ClusterWithin(n.geom, 0.005)) AS geom
+                        FROM
+                            network n
+                        WHERE
+                            n.names->0->>'tokenized' != ''
+                            AND NOT EXISTS (
+                                SELECT
+                                    1
+                                FROM
+                                    network_cluster nc
+                                WHERE
+                                    nc.address IS NOT NULL
+                                    AND nc.source_ids @> ARRAY[n.id]
+                            )
+                        GROUP BY
+                            n.names->0
+                    ) orphan
+                    WHERE orphan.geom IS NOT NULL;
+            "#, &[])?;
         } else {
             conn.execute(r#"
                 INSERT INTO network_cluster(geom)
@@ -45,115 +79,154 @@ impl NetworkCluster {
                     ) final
                     WHERE geom IS NOT NULL
                     GROUP BY geom;
-            "#, &[]).unwrap();
+            "#, &[])?;
+        }
 
-            conn.execute(r#"
-                -- extracts distinct Z coordinates from a multilinestring
-                CREATE OR REPLACE FUNCTION get_source_ids(geometry(MultiLineStringZ))
-                RETURNS BIGINT[]
-                AS
-                $$
-                DECLARE
-                    mls ALIAS FOR $1;
-                    ls geometry(LineStringZ);
-                    retVal BIGINT[];
-                    j BIGINT;
-                BEGIN
-                    j := 1;
-                    FOR ls IN SELECT (ST_Dump(mls)).geom LOOP
-                        FOR i IN 1..ST_NPoints(ls) LOOP
-                            retVal[j] := ST_Z(ST_PointN(ls, i));
-                            j := j + 1;
-                        END LOOP;
+        self.aggregate(conn)
+    }
+
+    ///
+    /// Populate the source_ids, names, postcodes & localities of each cluster from
+    /// the network features that make it up and flatten the id encoded Z coordinates of the geometry
+    ///
+    fn aggregate(&self, conn: &postgres::Connection) -> Result<(), Error> {
+        conn.execute(r#"
+            -- extracts distinct Z coordinates from a multilinestring
+            CREATE OR REPLACE FUNCTION get_source_ids(geometry(MultiLineStringZ))
+            RETURNS BIGINT[]
+            AS
+            $$
+            DECLARE
+                mls ALIAS FOR $1;
+                ls geometry(LineStringZ);
+                retVal BIGINT[];
+                j BIGINT;
+            BEGIN
+                j := 1;
+                FOR ls IN SELECT (ST_Dump(mls)).geom LOOP
+                    FOR i IN 1..ST_NPoints(ls) LOOP
+                        retVal[j] := ST_Z(ST_PointN(ls, i));
+                        j := j + 1;
                     END LOOP;
-                    retVal := ARRAY(SELECT DISTINCT UNNEST(retVal) ORDER BY 1);
-                RETURN retVal;
-                END;
-                $$
-                LANGUAGE plpgsql
-                   STABLE
-                RETURNS NULL ON NULL INPUT;
-            "#, &[]).unwrap();
+                END LOOP;
+                retVal := ARRAY(SELECT DISTINCT UNNEST(retVal) ORDER BY 1);
+            RETURN retVal;
+            END;
+            $$
+            LANGUAGE plpgsql
+               STABLE
+            RETURNS NULL ON NULL INPUT;
+        "#, &[])?;
 
-            conn.execute(r#"
-                UPDATE network_cluster
-                    SET source_ids = get_source_ids(geom);
-            "#, &[]).unwrap();
+        conn.execute(format!(r#"
+            UPDATE {table}
+                SET source_ids = get_source_ids(geom);
+        "#, table = self.table()).as_str(), &[])?;
 
-            conn.execute(r#"
-            UPDATE network_cluster
-                SET names = final.names
+        conn.execute(format!(r#"
+        UPDATE {table}
+            SET names = final.names
+            FROM (
+                SELECT
+                    joined.id,
+                    json_agg(joined.names) AS names
+                FROM (
+                    SELECT DISTINCT
+                        nc.id,
+                        jsonb_array_elements(n.names) AS names
+                    FROM
+                        (
+                            SELECT
+                                id,
+                                unnest(source_ids) AS sources
+                            FROM
+                                {table}
+                        ) nc,
+                        network n
+                    WHERE n.id = sources
+                ) joined
+                GROUP BY joined.id
+            ) final
+            WHERE
+                final.id = {table}.id;
+        "#, table = self.table()).as_str(), &[])?;
+
+        conn.execute(format!(r#"
+            UPDATE {table}
+                SET
+                    postcode = final.postcode,
+                    locality = final.locality
                 FROM (
                     SELECT
-                        joined.id,
-                        json_agg(joined.names) AS names
-                    FROM (
-                        SELECT DISTINCT
-                            nc.id,
-                            jsonb_array_elements(n.names) AS names
-                        FROM
-                            (
-                                SELECT
-                                    id,
-                                    unnest(source_ids) AS sources
-                                FROM
-                                    network_cluster
-                            ) nc,
-                            network n
-                        WHERE n.id = sources
-                    ) joined
-                    GROUP BY joined.id
+                        nc.id,
+                        array_agg(DISTINCT n.postcode ORDER BY n.postcode) FILTER (WHERE n.postcode IS NOT NULL) AS postcode,
+                        array_agg(DISTINCT n.locality ORDER BY n.locality) FILTER (WHERE n.locality IS NOT NULL) AS locality
+                    FROM
+                        (
+                            SELECT
+                                id,
+                                unnest(source_ids) AS sources
+                            FROM
+                                {table}
+                        ) nc,
+                        network n
+                    WHERE n.id = nc.sources
+                    GROUP BY nc.id
                 ) final
                 WHERE
-                    final.id = network_cluster.id;
-            "#, &[]).unwrap();
+                    final.id = {table}.id;
+        "#, table = self.table()).as_str(), &[])?;
 
-            conn.execute(r#"
-                ALTER TABLE network_cluster
-                    ADD COLUMN geom_flat geometry(geometry, 4326);
-            "#, &[]).unwrap();
+        conn.execute(format!(r#"
+            ALTER TABLE {table}
+                ADD COLUMN geom_flat geometry(geometry, 4326);
+        "#, table = self.table()).as_str(), &[])?;
 
-            conn.execute(r#"
-                UPDATE network_cluster
-                    SET geom_flat = ST_SetSRID(ST_Force2D(geom), 4326);
-            "#, &[]).unwrap();
+        conn.execute(format!(r#"
+            UPDATE {table}
+                SET geom_flat = ST_SetSRID(ST_Force2D(geom), 4326);
+        "#, table = self.table()).as_str(), &[])?;
 
-            conn.execute(r#"
-                ALTER TABLE network_cluster
-                    DROP COLUMN geom;
-            "#, &[]).unwrap();
+        conn.execute(format!(r#"
+            ALTER TABLE {table}
+                DROP COLUMN geom;
+        "#, table = self.table()).as_str(), &[])?;
 
-            conn.execute(r#"
-                ALTER TABLE network_cluster
-                    RENAME geom_flat TO geom;
-            "#, &[]).unwrap();
-        }
+        conn.execute(format!(r#"
+            ALTER TABLE {table}
+                RENAME geom_flat TO geom;
+        "#, table = self.table()).as_str(), &[])?;
+
+        Ok(())
     }
 }
 
 impl Table for NetworkCluster {
-    fn create(&self, conn: &Connection) {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
              CREATE EXTENSION IF NOT EXISTS POSTGIS
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         if self.orphan {
             conn.execute(r#"
                 DROP TABLE IF EXISTS network_orphan_cluster;
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             conn.execute(r#"
                 CREATE UNLOGGED TABLE network_orphan_cluster (
-                    ID SERIAL,
+                    id SERIAL,
                     names JSONB,
-                    geom GEOMETRY(MULTILINESTRINGZ, 4326),
+                    geom GEOMETRY(GEOMETRYZ, 4326),
+                    source_ids BIGINT[],
+                    postcode TEXT[],
+                    locality TEXT[],
                     props JSONB
                 )
-            "#, &[]).unwrap();
+            "#, &[])?;
         } else {
             conn.execute(r#"
                 DROP TABLE IF EXISTS network_cluster;
-            "#, &[]).unwrap();
+            "#, &[])?;
 
             conn.execute(r#"
                 CREATE UNLOGGED TABLE network_cluster (
@@ -161,55 +234,49 @@ impl Table for NetworkCluster {
                     names JSONB,
                     geom GEOMETRY(GEOMETRYZ, 4326),
                     address BIGINT,
-                    source_ids BIGINT[]
+                    source_ids BIGINT[],
+                    postcode TEXT[],
+                    locality TEXT[]
                 )
-            "#, &[]).unwrap();
+            "#, &[])?;
         }
-    }
 
-    fn count(&self, conn: &Connection) -> i64 {
-        let table = match self.orphan {
-            true => String::from("network_orphan_cluster"),
-            false => String::from("network_cluster")
-        };
+        Ok(())
+    }
 
-        match conn.query(format!("
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let res = conn.query(format!("
             SELECT count(*) FROM {}
-        ", table).as_str(), &[]) {
-            Ok(res) => {
-                let cnt: i64 = res.get(0).get(0);
-                cnt
-            },
-            _ => 0
-        }
+        ", self.table()).as_str(), &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
     }
 
-    fn index(&self, conn: &Connection) {
-        let table = match self.orphan {
-            true => String::from("network_orphan_cluster"),
-            false => String::from("network_cluster")
-        };
+    fn index(&self, conn: &Connection) -> Result<(), Error> {
+        let table = self.table();
 
         conn.execute(format!("
             CREATE INDEX IF NOT EXISTS {table}_idx ON {table} (id);
-        ", table = &table).as_str(), &[]).unwrap();
+        ", table = &table).as_str(), &[])?;
 
         conn.execute(format!("
             CREATE INDEX IF NOT EXISTS {table}_gix ON {table} USING GIST (geom);
-        ", table = table).as_str(), &[]).unwrap();
+        ", table = table).as_str(), &[])?;
 
-        if !self.orphan {
-            conn.execute(format!("
-                CREATE INDEX network_cluster_source_ids_idx ON network_cluster USING GIN (source_ids);
-            ").as_str(), &[]).unwrap();
-        }
+        conn.execute(format!("
+            CREATE INDEX IF NOT EXISTS {table}_source_ids_idx ON {table} USING GIN (source_ids);
+        ", table = table).as_str(), &[])?;
 
         conn.execute(format!("
             CLUSTER {table} USING {table}_gix;
-        ", table = table).as_str(), &[]).unwrap();
+        ", table = table).as_str(), &[])?;
 
         conn.execute(format!("
             ANALYZE {table};
-        ", table = table).as_str(), &[]).unwrap();
+        ", table = table).as_str(), &[])?;
+
+        Ok(())
     }
 }
@@ -1,6 +1,7 @@
 use postgres::{Connection};
 use std::io::Read;
-use super::{Table, InputTable};
+use super::{Table, InputTable, CopyReader};
+use crate::Error;
 
 ///
 /// Polygon table are special in that they don't make assumptions about the underlying
@@ -19,14 +20,14 @@ impl Polygon {
 }
 
 impl Table for Polygon {
-    fn create(&self, conn: &Connection) {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(r#"
              CREATE EXTENSION IF NOT EXISTS POSTGIS
-        "#, &[]).unwrap();
+        "#, &[])?;
 
         conn.execute(format!(r#"
             DROP TABLE IF EXISTS {};
-        "#, &self.name).as_str(), &[]).unwrap();
+        "#, &self.name).as_str(), &[])?;
 
         conn.execute(format!(r#"
             CREATE UNLOGGED TABLE {} (
@@ -34,34 +35,36 @@ impl Table for Polygon {
                 props JSONB,
                 geom GEOMETRY(MultiPolygon, 4326)
             )
-        "#, &self.name).as_str(), &[]).unwrap();
+        "#, &self.name).as_str(), &[])?;
+
+        Ok(())
     }
 
-    fn count(&self, conn: &Connection) -> i64 {
-        match conn.query(format!(r#"
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let res = conn.query(format!(r#"
             SELECT count(*) FROM {}
-        "#, &self.name).as_str(), &[]) {
-            Ok(res) => {
-                let cnt: i64 = res.get(0).get(0);
-                cnt
-            },
-            _ => 0
-        }
+        "#, &self.name).as_str(), &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
     }
 
-    fn index(&self, conn: &Connection) {
+    fn index(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(format!(r#"
             CREATE INDEX {name}_idx ON {name} (id);
-        "#, name = &self.name).as_str(), &[]).unwrap();
+        "#, name = &self.name).as_str(), &[])?;
 
         conn.execute(format!(r#"
             CREATE INDEX {name}_gix ON {name} USING GIST (geom);
-        "#, name = &self.name).as_str(), &[]).unwrap();
+        "#, name = &self.name).as_str(), &[])?;
+
+        Ok(())
     }
 }
 
 impl InputTable for Polygon {
-    fn input(&self, conn: &Connection, mut data: impl Read) {
+    fn input(&self, conn: &Connection, data: impl Read) -> Result<(), Error> {
         let stmt = conn.prepare(format!(r#"
             COPY {} (
                 props,
@@ -69,33 +72,36 @@ impl InputTable for Polygon {
             )
             FROM STDIN
             WITH (
-                FORMAT CSV,
-                NULL '',
-                DELIMITER E'\t',
-                QUOTE E'\b'
+                FORMAT BINARY
             )
-        "#, &self.name).as_str()).unwrap();
+        "#, &self.name).as_str())?;
 
-        stmt.copy_in(&[], &mut data).unwrap();
+        let mut data = CopyReader::new(data);
+        let res = stmt.copy_in(&[], &mut data);
+        data.finish(res)?;
 
         conn.execute(format!(r#"
             UPDATE {name}
                 SET geom = ST_CollectionExtract(ST_MakeValid(geom), 3)
-        "#, name = &self.name).as_str(), &[]).unwrap();
+        "#, name = &self.name).as_str(), &[])?;
+
+        Ok(())
     }
 
-    fn seq_id(&self, conn: &Connection) {
+    fn seq_id(&self, conn: &Connection) -> Result<(), Error> {
         conn.execute(format!(r#"
             DROP SEQUENCE IF EXISTS {}_seq;
-        "#, &self.name).as_str(), &[]).unwrap();
+        "#, &self.name).as_str(), &[])?;
 
         conn.execute(format!(r#"
             CREATE SEQUENCE {}_seq;
-        "#, &self.name).as_str(), &[]).unwrap();
+        "#, &self.name).as_str(), &[])?;
 
         conn.execute(format!(r#"
             UPDATE {name}
                 SET id = nextval('{name}_seq');
-        "#, name = &self.name).as_str(), &[]).unwrap();
+        "#, name = &self.name).as_str(), &[])?;
+
+        Ok(())
     }
 }
@@ -0,0 +1,282 @@
+use std::time::UNIX_EPOCH;
+use sha2::{Digest, Sha256};
+use postgres::{Connection};
+use super::Table;
+use crate::Error;
+
+///
+/// A single step of the map pipeline, named after the table it produces
+///
+pub struct Stage {
+    pub name: &'static str,
+
+    /// Table populated by the stage, used to verify the stage output still exists
+    pub table: &'static str,
+
+    /// Condition selecting the rows of the table produced by the stage, for
+    /// stages such as match that update a table populated by an earlier stage
+    pub rows: Option<&'static str>,
+
+    /// Stages whose output this stage reads
+    pub deps: &'static [&'static str]
+}
+
+pub const STAGES: &[Stage] = &[
+    Stage { name: "address", table: "address", rows: None, deps: &[] },
+    Stage { name: "network", table: "network", rows: None, deps: &[] },
+    Stage { name: "network_cluster", table: "network_cluster", rows: None, deps: &["network"] },
+    Stage { name: "intersections", table: "intersections", rows: None, deps: &["network_cluster"] },
+    Stage { name: "match", table: "address", rows: Some("netid IS NOT NULL"), deps: &["address", "network_cluster"] },
+    Stage { name: "address_cluster", table: "address_cluster", rows: None, deps: &["match"] },
+    Stage { name: "address_orphan_cluster", table: "address_orphan_cluster", rows: None, deps: &["match"] },
+    Stage { name: "network_orphan_cluster", table: "network_orphan_cluster", rows: None, deps: &["address_cluster"] }
+];
+
+///
+/// Return a fingerprint of an input file, based on its path, size & modification time
+///
+pub fn file_fingerprint(path: &str) -> Result<String, Error> {
+    let meta = std::fs::metadata(path)?;
+
+    let modified = match meta.modified()?.duration_since(UNIX_EPOCH) {
+        Ok(modified) => modified.as_nanos(),
+        Err(_) => 0
+    };
+
+    Ok(format!("{}:{}:{}", path, meta.len(), modified))
+}
+
+///
+/// Records the map stages that have completed, allowing an interrupted
+/// run to be resumed from the first stage that is no longer valid
+///
+pub struct Stages ();
+
+impl Stages {
+    pub fn new() -> Self {
+        Stages()
+    }
+
+    pub fn get(name: &str) -> Result<&'static Stage, Error> {
+        match STAGES.iter().find(|stage| stage.name == name) {
+            Some(stage) => Ok(stage),
+            None => Err(Error::Config(format!("Unknown map stage: {}", name)))
+        }
+    }
+
+    ///
+    /// Fingerprint a stage by combining its own inputs with the fingerprint and
+    /// completion time of each stage it depends on. Rerunning a dependency will
+    /// therefore invalidate every stage after it.
+    ///
+    /// Returns None if the stage cannot be fingerprinted, either as it has no
+    /// stable inputs (ie: stdin) or a dependency has not been recorded
+    ///
+    fn fingerprint(&self, conn: &Connection, stage: &Stage, inputs: Option<&str>) -> Result<Option<String>, Error> {
+        let inputs = match inputs {
+            Some(inputs) => inputs,
+            None => { return Ok(None); }
+        };
+
+        // Fingerprints are persisted between runs so must be hashed with a stable algorithm,
+        // each part is NUL terminated so that adjacent parts cannot run into each other
+        let mut hasher = Sha256::default();
+        for part in [stage.name, inputs].iter() {
+            hasher.input(part.as_bytes());
+            hasher.input(&[0]);
+        }
+
+        for dep in stage.deps.iter() {
+            let rows = conn.query("
+                SELECT
+                    fingerprint,
+                    EXTRACT(EPOCH FROM completed)::TEXT
+                FROM
+                    stages
+                WHERE
+                    name = $1
+            ", &[dep])?;
+
+            if rows.is_empty() {
+                return Ok(None);
+            }
+
+            let fingerprint: String = rows.get(0).get(0);
+            let completed: String = rows.get(0).get(1);
+
+            for part in [fingerprint, completed].iter() {
+                hasher.input(part.as_bytes());
+                hasher.input(&[0]);
+            }
+        }
+
+        Ok(Some(format!("{:x}", hasher.result())))
+    }
+
+    ///
+    /// A completed stage is valid if its fingerprint is unchanged
+    /// and its table still holds the number of rows it was completed with,
+    /// ie: the number of matched addresses for the match stage
+    ///
+    fn valid(&self, conn: &Connection, stage: &Stage, fingerprint: &str) -> Result<bool, Error> {
+        let rows = conn.query("
+            SELECT
+                fingerprint,
+                count
+            FROM
+                stages
+            WHERE
+                name = $1
+        ", &[&stage.name])?;
+
+        if rows.is_empty() {
+            return Ok(false);
+        }
+
+        let recorded: String = rows.get(0).get(0);
+        let count: i64 = rows.get(0).get(1);
+
+        if recorded != fingerprint {
+            return Ok(false);
+        }
+
+        match table_count(conn, stage)? {
+            Some(current) => Ok(current == count),
+            None => Ok(false)
+        }
+    }
+
+    ///
+    /// Called before a stage runs. Returns false if resuming and the
+    /// stage is still valid, otherwise clears any previous record of the
+    /// stage so that a failure part way through leaves it invalid
+    ///
+    pub fn begin(&self, conn: &Connection, name: &str, inputs: Option<&str>, resume: bool) -> Result<bool, Error> {
+        let stage = Stages::get(name)?;
+
+        if resume {
+            if let Some(fingerprint) = self.fingerprint(conn, stage, inputs)? {
+                if self.valid(conn, stage, &fingerprint)? {
+                    return Ok(false);
+                }
+            }
+        }
+
+        self.invalidate(conn, name)?;
+
+        Ok(true)
+    }
+
+    ///
+    /// Record a stage as completed
+    ///
+    pub fn complete(&self, conn: &Connection, name: &str, inputs: Option<&str>) -> Result<(), Error> {
+        let stage = Stages::get(name)?;
+
+        let fingerprint = match self.fingerprint(conn, stage, inputs)? {
+            Some(fingerprint) => fingerprint,
+            None => { return Ok(()); }
+        };
+
+        let count = match table_count(conn, stage)? {
+            Some(count) => count,
+            None => { return Err(Error::Validation(format!("{} stage completed without creating {}", stage.name, stage.table))); }
+        };
+
+        conn.execute("
+            INSERT INTO stages (name, fingerprint, count, completed)
+                VALUES ($1, $2, $3, clock_timestamp())
+                ON CONFLICT (name) DO UPDATE
+                    SET
+                        fingerprint = EXCLUDED.fingerprint,
+                        count = EXCLUDED.count,
+                        completed = EXCLUDED.completed
+        ", &[&stage.name, &fingerprint, &count])?;
+
+        Ok(())
+    }
+
+    pub fn invalidate(&self, conn: &Connection, name: &str) -> Result<(), Error> {
+        conn.execute("
+            DELETE FROM stages WHERE name = $1
+        ", &[&name])?;
+
+        Ok(())
+    }
+
+    pub fn clear(&self, conn: &Connection) -> Result<(), Error> {
+        conn.execute("
+            DELETE FROM stages
+        ", &[])?;
+
+        Ok(())
+    }
+}
+
+///
+/// Does the given table exist in the current schema
+///
+pub fn table_exists(conn: &Connection, table: &str) -> Result<bool, Error> {
+    let rows = conn.query("
+        SELECT
+            count(*)
+        FROM
+            pg_tables
+        WHERE
+            schemaname = current_schema()
+            AND tablename = $1
+    ", &[&table])?;
+
+    let exists: i64 = rows.get(0).get(0);
+
+    Ok(exists > 0)
+}
+
+///
+/// Number of rows produced by a stage, None if its table does not exist
+///
+fn table_count(conn: &Connection, stage: &Stage) -> Result<Option<i64>, Error> {
+    if !table_exists(conn, stage.table)? {
+        return Ok(None);
+    }
+
+    let rows = conn.query(format!("
+        SELECT count(*) FROM {} WHERE {}
+    ", stage.table, stage.rows.unwrap_or("TRUE")).as_str(), &[])?;
+
+    let cnt: i64 = rows.get(0).get(0);
+
+    Ok(Some(cnt))
+}
+
+impl Table for Stages {
+    fn create(&self, conn: &Connection) -> Result<(), Error> {
+        // Unlike the other tables this table must survive between
+        // runs so is never dropped & is not UNLOGGED
+        conn.execute(r#"
+            CREATE TABLE IF NOT EXISTS stages (
+                name TEXT PRIMARY KEY,
+                fingerprint TEXT NOT NULL,
+                count BIGINT NOT NULL,
+                completed TIMESTAMPTZ NOT NULL
+            )
+        "#, &[])?;
+
+        Ok(())
+    }
+
+    fn count(&self, conn: &Connection) -> Result<i64, Error> {
+        let res = conn.query("
+            SELECT count(*) FROM stages
+        ", &[])?;
+
+        let cnt: i64 = res.get(0).get(0);
+
+        Ok(cnt)
+    }
+
+    fn index(&self, _conn: &Connection) -> Result<(), Error> {
+        // The primary key is the only index required
+        Ok(())
+    }
+}
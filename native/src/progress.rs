@@ -0,0 +1,315 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{unbounded, Sender};
+use neon::prelude::*;
+use neon::result::Throw;
+
+use crate::Error;
+
+/// Minimum time between events emitted part way through a stage
+const INTERVAL: Duration = Duration::from_secs(1);
+
+///
+/// A snapshot of the progress of the current stage of a native mode
+///
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    pub stage: String,
+
+    /// Number of features processed by the stage so far
+    pub processed: u64,
+
+    /// Number of invalid features skipped by the stage so far
+    pub errors: u64,
+
+    /// Seconds since the stage began
+    pub elapsed: f64,
+
+    /// Features processed per second
+    pub throughput: f64,
+
+    /// Set on the final event of each stage
+    pub complete: bool
+}
+
+struct State {
+    stage: Option<String>,
+    processed: u64,
+    errors: u64,
+    start: Instant,
+    last: Instant
+}
+
+///
+/// Handle used by native modes to report their progress
+///
+/// Events are emitted when a stage begins, at most once per second while
+/// it runs & once it completes. Progress::none() ignores all reports so
+/// that modes can report unconditionally
+///
+#[derive(Clone)]
+pub struct Progress {
+    tx: Option<Sender<Event>>,
+    state: Arc<Mutex<State>>
+}
+
+impl Progress {
+    pub fn new(tx: Sender<Event>) -> Self {
+        Progress {
+            tx: Some(tx),
+            state: Arc::new(Mutex::new(State {
+                stage: None,
+                processed: 0,
+                errors: 0,
+                start: Instant::now(),
+                last: Instant::now()
+            }))
+        }
+    }
+
+    pub fn none() -> Self {
+        let mut progress = Progress::new(unbounded().0);
+        progress.tx = None;
+        progress
+    }
+
+    fn lock(&self) -> MutexGuard<State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner()
+        }
+    }
+
+    ///
+    /// Begin a new stage, completing the previous stage if there was one
+    ///
+    pub fn stage(&self, name: &str) {
+        if self.tx.is_none() {
+            return;
+        }
+
+        let mut state = self.lock();
+
+        if state.stage.is_some() {
+            self.emit(&mut state, true);
+        }
+
+        state.stage = Some(String::from(name));
+        state.processed = 0;
+        state.errors = 0;
+        state.start = Instant::now();
+
+        self.emit(&mut state, false);
+    }
+
+    ///
+    /// Record a single feature as processed
+    ///
+    pub fn feature(&self) {
+        if self.tx.is_none() {
+            return;
+        }
+
+        let mut state = self.lock();
+        state.processed += 1;
+
+        if state.last.elapsed() >= INTERVAL {
+            self.emit(&mut state, false);
+        }
+    }
+
+    ///
+    /// Record a single feature as invalid
+    ///
+    pub fn error(&self) {
+        if self.tx.is_none() {
+            return;
+        }
+
+        let mut state = self.lock();
+        state.errors += 1;
+
+        if state.last.elapsed() >= INTERVAL {
+            self.emit(&mut state, false);
+        }
+    }
+
+    ///
+    /// Set the number of features processed, for stages performed
+    /// in bulk where features can't be counted as they are processed
+    ///
+    pub fn processed(&self, processed: u64) {
+        if self.tx.is_none() {
+            return;
+        }
+
+        self.lock().processed = processed;
+    }
+
+    ///
+    /// Complete the current stage
+    ///
+    pub fn complete(&self) {
+        if self.tx.is_none() {
+            return;
+        }
+
+        let mut state = self.lock();
+
+        if state.stage.is_some() {
+            self.emit(&mut state, true);
+            state.stage = None;
+        }
+    }
+
+    fn emit(&self, state: &mut State, complete: bool) {
+        let tx = match self.tx {
+            Some(ref tx) => tx,
+            None => { return; }
+        };
+
+        let elapsed = state.start.elapsed();
+        let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0;
+
+        let event = Event {
+            stage: state.stage.clone().unwrap_or_default(),
+            processed: state.processed,
+            errors: state.errors,
+            elapsed: elapsed,
+            throughput: match elapsed > 0.0 {
+                true => state.processed as f64 / elapsed,
+                false => 0.0
+            },
+            complete: complete
+        };
+
+        state.last = Instant::now();
+
+        // The receiver only goes away if the caller is no longer interested
+        let _ = tx.send(event);
+    }
+}
+
+///
+/// Return the optional progress callback at the given argument index
+///
+pub fn callback<'a>(cx: &mut FunctionContext<'a>, i: i32) -> NeonResult<Option<Handle<'a, JsFunction>>> {
+    match cx.argument_opt(i) {
+        None => Ok(None),
+        Some(arg) => {
+            if arg.is_a::<JsUndefined>() || arg.is_a::<JsNull>() {
+                Ok(None)
+            } else {
+                Ok(Some(arg.downcast::<JsFunction>().or_throw(cx)?))
+            }
+        }
+    }
+}
+
+///
+/// Run a native mode, passing each progress event it emits to the optional JS callback
+///
+/// When a callback is given the mode is run on a separate thread, leaving the main
+/// thread free to call the callback as events arrive. If the callback throws, the
+/// mode is allowed to finish before the exception is rethrown.
+///
+pub fn run<'a, T, F>(cx: &mut FunctionContext<'a>, callback: Option<Handle<'a, JsFunction>>, run: F) -> NeonResult<Result<T, Error>>
+where
+    T: Send + 'static,
+    F: FnOnce(Progress) -> Result<T, Error> + Send + 'static
+{
+    let callback = match callback {
+        Some(callback) => callback,
+        None => { return Ok(run(Progress::none())); }
+    };
+
+    let (tx, rx) = unbounded();
+    let progress = Progress::new(tx);
+
+    let worker = thread::Builder::new().name(String::from("Progress Worker")).spawn(move || {
+        let res = run(progress.clone());
+
+        if res.is_ok() {
+            progress.complete();
+        }
+
+        res
+    });
+
+    let worker = match worker {
+        Ok(worker) => worker,
+        Err(err) => { return Ok(Err(Error::from(err))); }
+    };
+
+    let mut thrown: Option<Throw> = None;
+
+    for event in rx.iter() {
+        if thrown.is_some() {
+            continue;
+        }
+
+        let called = neon_serde::to_value(cx, &event).map_err(Throw::from).and_then(|event| {
+            let this = cx.null();
+            callback.call(cx, this, vec![event])
+        });
+
+        if let Err(throw) = called {
+            thrown = Some(throw);
+        }
+    }
+
+    let res = match worker.join() {
+        Ok(res) => res,
+        Err(_) => Err(Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "Progress Worker thread panicked")))
+    };
+
+    match thrown {
+        Some(throw) => Err(throw),
+        None => Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let (tx, rx) = unbounded();
+        let progress = Progress::new(tx);
+
+        progress.stage("address");
+        progress.feature();
+        progress.feature();
+        progress.error();
+        progress.stage("index");
+        progress.processed(5);
+        progress.complete();
+
+        drop(progress);
+
+        let events: Vec<(String, u64, u64, bool)> = rx.iter().map(|event| {
+            (event.stage, event.processed, event.errors, event.complete)
+        }).collect();
+
+        assert_eq!(events, vec![
+            (String::from("address"), 0, 0, false),
+            (String::from("address"), 2, 1, true),
+            (String::from("index"), 0, 0, false),
+            (String::from("index"), 5, 0, true)
+        ]);
+    }
+
+    #[test]
+    fn test_progress_none() {
+        let progress = Progress::none();
+
+        progress.stage("address");
+        progress.feature();
+        progress.complete();
+
+        assert_eq!(progress.lock().processed, 0);
+    }
+}
@@ -1,5 +1,6 @@
 use neon::prelude::*;
 use super::stream::GeoStream;
+use crate::Error;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct StatsArgs {
@@ -52,12 +53,19 @@ pub fn stats(mut cx: FunctionContext) -> JsResult<JsValue> {
         }
     };
 
-    let stream = GeoStream::new(args.input);
+    match run(args) {
+        Ok(stats) => Ok(neon_serde::to_value(&mut cx, &stats)?),
+        Err(err) => err.throw(&mut cx)
+    }
+}
+
+fn run(args: StatsArgs) -> Result<Stats, Error> {
+    let stream = GeoStream::new(args.input)?;
 
     let mut stats = Stats::new();
 
     for geo in stream {
-        match geo {
+        match geo? {
             geojson::GeoJson::Feature(feat) => {
                 stats.feats = stats.feats + 1;
 
@@ -109,7 +117,7 @@ pub fn stats(mut cx: FunctionContext) -> JsResult<JsValue> {
         };
     }
 
-    Ok(neon_serde::to_value(&mut cx, &stats)?)
+    Ok(stats)
 }
 
 fn count_addresses(feat: &geojson::Feature) -> i64 {
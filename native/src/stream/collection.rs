@@ -0,0 +1,401 @@
+use std::io::{BufRead, BufReader, Read};
+use std::iter::Iterator;
+
+use crate::Error;
+
+///
+/// Determine if the start of an input is a GeoJSON FeatureCollection
+///
+/// Only the given bytes are examined, so the top level "type" or
+/// "features" member must appear within them to be detected
+///
+/// Line delimited FeatureCollections, where the first line is a complete
+/// value followed by further lines, are not a single FeatureCollection
+///
+pub fn detect(start: &[u8]) -> bool {
+    let mut scanner = Scanner::new(start);
+
+    match scanner.features(true) {
+        Ok(true) => !delimited(start),
+        _ => false
+    }
+}
+
+///
+/// Determine if the first line of the input is a complete JSON value with more input following it
+///
+fn delimited(start: &[u8]) -> bool {
+    let end = match start.iter().position(|byte| *byte == b'\n') {
+        Some(end) => end,
+        None => { return false; }
+    };
+
+    let mut scanner = Scanner::new(&start[..end]);
+
+    let complete = scanner.whitespace().is_ok()
+        && scanner.value(None).is_ok()
+        && scanner.whitespace().is_ok()
+        && scanner.peek().ok() == Some(None);
+
+    complete && start[end..].iter().any(|byte| !byte.is_ascii_whitespace())
+}
+
+enum State {
+    Start,
+    Features,
+    End
+}
+
+///
+/// Streams each feature of a FeatureCollection as a string, along with the line
+/// it begins on, without holding more than a single feature of the collection in memory
+///
+pub struct Collection {
+    scanner: Scanner<BufReader<Box<dyn Read + Send>>>,
+    state: State
+}
+
+impl Collection {
+    pub fn new(input: BufReader<Box<dyn Read + Send>>) -> Self {
+        Collection {
+            scanner: Scanner::new(input),
+            state: State::Start
+        }
+    }
+
+    fn feature(&mut self) -> Result<Option<(u64, String)>, Error> {
+        loop {
+            match self.state {
+                State::End => { return Ok(None); },
+                State::Start => {
+                    self.state = match self.scanner.features(false)? {
+                        true => State::Features,
+                        false => State::End
+                    };
+                },
+                State::Features => {
+                    self.scanner.whitespace()?;
+
+                    match self.scanner.peek()? {
+                        Some(b']') => {
+                            self.scanner.consume();
+                            self.scanner.finish()?;
+                            self.state = State::End;
+                        },
+                        Some(b',') => {
+                            self.scanner.consume();
+                        },
+                        _ => {
+                            let line = self.scanner.line;
+
+                            let mut feature = Vec::new();
+                            self.scanner.value(Some(&mut feature))?;
+
+                            return match String::from_utf8(feature) {
+                                Ok(feature) => Ok(Some((line, feature))),
+                                Err(err) => Err(Error::Parse(format!("Invalid FeatureCollection: {}", err)))
+                            };
+                        }
+                    };
+                }
+            };
+        }
+    }
+}
+
+impl Iterator for Collection {
+    type Item = Result<(u64, String), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.feature() {
+            Ok(feature) => feature.map(Ok),
+            Err(err) => {
+                self.state = State::End;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+///
+/// Minimal JSON scanner, able to step over the members of the top level
+/// object & capture the raw text of individual values
+///
+struct Scanner<R: BufRead> {
+    input: R,
+
+    /// Line number of the next byte of input
+    line: u64
+}
+
+impl<R: BufRead> Scanner<R> {
+    fn new(input: R) -> Self {
+        Scanner {
+            input: input,
+            line: 1
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        let buf = self.input.fill_buf()?;
+
+        Ok(buf.first().cloned())
+    }
+
+    fn consume(&mut self) {
+        if let Ok(buf) = self.input.fill_buf() {
+            if buf.first() == Some(&b'\n') {
+                self.line += 1;
+            }
+        }
+
+        self.input.consume(1);
+    }
+
+    fn next(&mut self) -> Result<u8, Error> {
+        match self.peek()? {
+            Some(byte) => {
+                self.consume();
+                Ok(byte)
+            },
+            None => Err(Error::Parse(String::from("Invalid FeatureCollection: Unexpected end of input")))
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), Error> {
+        let byte = self.next()?;
+
+        if byte != expected {
+            return Err(Error::Parse(format!("Invalid FeatureCollection: Expected '{}' but found '{}'", expected as char, byte as char)));
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Skip whitespace, including Ascii Record Separators
+    ///
+    fn whitespace(&mut self) -> Result<(), Error> {
+        while let Some(byte) = self.peek()? {
+            match byte {
+                b' ' | b'\t' | b'\n' | b'\r' | 0x1E => self.consume(),
+                _ => break
+            };
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Step over a single JSON value, optionally capturing its raw text
+    ///
+    fn value(&mut self, mut out: Option<&mut Vec<u8>>) -> Result<(), Error> {
+        let mut depth: usize = 0;
+        let mut started = false;
+        let mut string = false;
+        let mut escape = false;
+
+        loop {
+            let byte = match self.peek()? {
+                Some(byte) => byte,
+                None if started && depth == 0 && !string => { return Ok(()); },
+                None => { return Err(Error::Parse(String::from("Invalid FeatureCollection: Unexpected end of input"))); }
+            };
+
+            if started && depth == 0 && !string {
+                match byte {
+                    b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r' => { return Ok(()); },
+                    _ => ()
+                };
+            }
+
+            self.consume();
+            started = true;
+
+            if let Some(ref mut out) = out {
+                out.push(byte);
+            }
+
+            if string {
+                if escape {
+                    escape = false;
+                } else if byte == b'\\' {
+                    escape = true;
+                } else if byte == b'"' {
+                    string = false;
+
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+
+                continue;
+            }
+
+            match byte {
+                b'"' => string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    if depth == 0 {
+                        return Err(Error::Parse(format!("Invalid FeatureCollection: Unexpected '{}'", byte as char)));
+                    }
+
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                },
+                _ => ()
+            };
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let mut raw = Vec::new();
+        self.value(Some(&mut raw))?;
+
+        match serde_json::from_slice::<String>(&raw) {
+            Ok(string) => Ok(string),
+            Err(_) => Err(Error::Parse(String::from("Invalid FeatureCollection: Expected a string")))
+        }
+    }
+
+    ///
+    /// Step through the members of the top level object until the "features" array,
+    /// returning true once positioned at the first element of the array, or false
+    /// if the object has no features
+    ///
+    /// When detecting, a top level "type" member is sufficient to determine the result
+    ///
+    fn features(&mut self, detect: bool) -> Result<bool, Error> {
+        self.whitespace()?;
+
+        if self.peek()? != Some(b'{') {
+            return Ok(false);
+        }
+
+        self.consume();
+
+        loop {
+            self.whitespace()?;
+
+            if self.peek()? == Some(b'}') {
+                self.consume();
+                return Ok(false);
+            }
+
+            let key = self.string()?;
+
+            self.whitespace()?;
+            self.expect(b':')?;
+            self.whitespace()?;
+
+            if key == "features" && self.peek()? == Some(b'[') {
+                self.consume();
+                return Ok(true);
+            } else if key == "type" && detect {
+                return Ok(self.string()? == "FeatureCollection");
+            }
+
+            self.value(None)?;
+
+            self.whitespace()?;
+            match self.next()? {
+                b',' => (),
+                b'}' => { return Ok(false); },
+                byte => { return Err(Error::Parse(format!("Invalid FeatureCollection: Unexpected '{}'", byte as char))); }
+            };
+        }
+    }
+
+    ///
+    /// Step over the members of the top level object following the "features" array
+    ///
+    fn finish(&mut self) -> Result<(), Error> {
+        loop {
+            self.whitespace()?;
+
+            match self.next()? {
+                b'}' => { break; },
+                b',' => {
+                    self.whitespace()?;
+                    self.value(None)?;
+                    self.whitespace()?;
+                    self.expect(b':')?;
+                    self.whitespace()?;
+                    self.value(None)?;
+                },
+                byte => { return Err(Error::Parse(format!("Invalid FeatureCollection: Unexpected '{}'", byte as char))); }
+            };
+        }
+
+        self.whitespace()?;
+
+        if self.peek()?.is_some() {
+            return Err(Error::Parse(String::from("Invalid FeatureCollection: Unexpected content following the FeatureCollection")));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection(input: &'static str) -> Collection {
+        let reader: Box<dyn Read + Send> = Box::new(input.as_bytes());
+        Collection::new(BufReader::new(reader))
+    }
+
+    #[test]
+    fn test_detect() {
+        assert!(detect(br#"{ "type": "FeatureCollection", "features": [] }"#));
+        assert!(detect(b"{\n  \"name\": \"addresses\",\n  \"crs\": { \"type\": \"name\" },\n  \"type\": \"FeatureCollection\""));
+        assert!(detect(br#"{ "features": [{ "type": "Feature""#));
+        assert!(!detect(br#"{ "type": "Feature", "properties": {}, "geometry": null }"#));
+        assert!(!detect(br#"{ "properties": { "type": "FeatureCollection" }, "type": "Feature" }"#));
+        assert!(!detect(b"not json"));
+        assert!(!detect(b""));
+
+        assert!(detect(b"{ \"type\": \"FeatureCollection\", \"features\": [] }\n"));
+        assert!(!detect(b"{ \"type\": \"FeatureCollection\", \"features\": [] }\n{ \"type\": \"FeatureCollection\", \"features\": [] }\n"));
+    }
+
+    #[test]
+    fn test_collection() {
+        let features: Vec<(u64, String)> = collection(r#"{
+            "type": "FeatureCollection",
+            "name": "test",
+            "features": [
+                { "type": "Feature", "properties": { "street": "Main St ]}\"" }, "geometry": null },
+                {"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[1,2]}}
+            ],
+            "bbox": [1, 2, 1, 2]
+        }"#).map(|feat| feat.unwrap()).collect();
+
+        assert_eq!(features, vec![
+            (5, String::from(r#"{ "type": "Feature", "properties": { "street": "Main St ]}\"" }, "geometry": null }"#)),
+            (6, String::from(r#"{"type":"Feature","properties":{},"geometry":{"type":"Point","coordinates":[1,2]}}"#))
+        ]);
+    }
+
+    #[test]
+    fn test_collection_empty() {
+        assert_eq!(collection(r#"{ "type": "FeatureCollection", "features": [] }"#).count(), 0);
+        assert_eq!(collection(r#"{ "type": "FeatureCollection" }"#).count(), 0);
+    }
+
+    #[test]
+    fn test_collection_invalid() {
+        let mut features = collection(r#"{ "type": "FeatureCollection", "features": [ { "type": "Feature" "#);
+
+        assert!(features.next().unwrap().is_err());
+        assert!(features.next().is_none());
+
+        let mut features = collection(r#"{ "type": "FeatureCollection", "features": [] } {}"#);
+
+        assert!(features.next().unwrap().is_err());
+    }
+}
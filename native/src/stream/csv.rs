@@ -0,0 +1,272 @@
+use std::io::Read;
+use std::iter::Iterator;
+
+use crate::{Error, stream::Labelled, stream::Origin, stream::geo::GeoStream};
+
+///
+/// Column names & delimiter of a CSV address input, defaulting
+/// to the columns used by OpenAddresses
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CsvConfig {
+    pub delimiter: char,
+    pub lon: String,
+    pub lat: String,
+    pub number: String,
+    pub street: String,
+    pub unit: String,
+    pub city: String,
+    pub postcode: String,
+    pub id: String
+}
+
+impl Default for CsvConfig {
+    fn default() -> Self {
+        CsvConfig {
+            delimiter: ',',
+            lon: String::from("LON"),
+            lat: String::from("LAT"),
+            number: String::from("NUMBER"),
+            street: String::from("STREET"),
+            unit: String::from("UNIT"),
+            city: String::from("CITY"),
+            postcode: String::from("POSTCODE"),
+            id: String::from("ID")
+        }
+    }
+}
+
+///
+/// Index of each configured column within a row,
+/// None for optional columns not present in the input
+///
+struct Columns {
+    lon: usize,
+    lat: usize,
+    number: usize,
+    street: usize,
+    unit: Option<usize>,
+    city: Option<usize>,
+    postcode: Option<usize>,
+    id: Option<usize>
+}
+
+///
+/// Reads addresses from a CSV file with a header row, converting each row
+/// to a GeoJSON feature so it is validated exactly as GeoJSON input is
+///
+/// Each item is labelled with the line number of the row along with either
+/// the feature or a description of why the row is invalid
+///
+pub struct CsvStream {
+    records: ::csv::StringRecordsIntoIter<Box<dyn Read + Send>>,
+    columns: Columns,
+    delimiter: char
+}
+
+impl CsvStream {
+    pub fn new(input: Option<String>, config: CsvConfig) -> Result<Self, Error> {
+        if !config.delimiter.is_ascii() {
+            return Err(Error::Config(format!("CSV delimiter must be a single ASCII character: {}", config.delimiter)));
+        }
+
+        let mut reader = ::csv::ReaderBuilder::new()
+            .delimiter(config.delimiter as u8)
+            .flexible(true)
+            .from_reader(GeoStream::reader(input)?);
+
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(err) => { return Err(CsvStream::error(err)); }
+        };
+
+        let find = |name: &String| headers.iter().position(|header| {
+            header.trim().eq_ignore_ascii_case(name.trim())
+        });
+
+        let required = |name: &String| match find(name) {
+            Some(column) => Ok(column),
+            None => Err(Error::Config(format!("CSV input is missing the {} column", name)))
+        };
+
+        let columns = Columns {
+            lon: required(&config.lon)?,
+            lat: required(&config.lat)?,
+            number: required(&config.number)?,
+            street: required(&config.street)?,
+            unit: find(&config.unit),
+            city: find(&config.city),
+            postcode: find(&config.postcode),
+            id: find(&config.id)
+        };
+
+        Ok(CsvStream {
+            records: reader.into_records(),
+            columns: columns,
+            delimiter: config.delimiter
+        })
+    }
+
+    fn error(err: ::csv::Error) -> Error {
+        match err.into_kind() {
+            ::csv::ErrorKind::Io(err) => Error::Io(err),
+            kind => Error::Parse(format!("Invalid CSV: {:?}", kind))
+        }
+    }
+
+    ///
+    /// Convert a single row to a GeoJSON feature
+    ///
+    fn feature(&self, record: &::csv::StringRecord) -> Result<geojson::GeoJson, String> {
+        let get = |column: usize| record.get(column).map(str::trim).filter(|value| !value.is_empty());
+
+        let coord = |column: usize| match get(column).map(str::parse::<f64>) {
+            Some(Ok(coord)) if coord.is_finite() => Ok(coord),
+            _ => Err(String::from("Invalid coordinates"))
+        };
+
+        let geom = vec![coord(self.columns.lon)?, coord(self.columns.lat)?];
+
+        let mut props = serde_json::Map::new();
+
+        match get(self.columns.number) {
+            Some(number) => props.insert(String::from("number"), serde_json::Value::from(number)),
+            None => { return Err(String::from("Missing number")); }
+        };
+
+        match get(self.columns.street) {
+            Some(street) => props.insert(String::from("street"), serde_json::Value::from(street)),
+            None => { return Err(String::from("Missing street")); }
+        };
+
+        for (name, column) in vec![
+            ("unit", self.columns.unit),
+            ("city", self.columns.city),
+            ("postcode", self.columns.postcode)
+        ] {
+            if let Some(value) = column.and_then(get) {
+                props.insert(String::from(name), serde_json::Value::from(value));
+            }
+        }
+
+        // Only numeric ids are used as feature ids, others are retained as a property
+        let mut id = None;
+        if let Some(value) = self.columns.id.and_then(get) {
+            match value.parse::<i64>() {
+                Ok(numeric) => { id = Some(geojson::feature::Id::Number(serde_json::Number::from(numeric))); },
+                Err(_) => { props.insert(String::from("id"), serde_json::Value::from(value)); }
+            };
+        }
+
+        Ok(geojson::GeoJson::Feature(geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::Point(geom))),
+            id: id,
+            properties: Some(props),
+            foreign_members: None
+        }))
+    }
+}
+
+impl Iterator for CsvStream {
+    type Item = Result<Labelled, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map(|pos| pos.line()).unwrap_or(0);
+
+                return match err.into_kind() {
+                    ::csv::ErrorKind::Io(err) => Some(Err(Error::Io(err))),
+                    kind => Some(Ok((Origin::line(line), Err(format!("Invalid CSV row: {:?}", kind)))))
+                };
+            }
+        };
+
+        let line = record.position().map(|pos| pos.line()).unwrap_or(0);
+
+        let feature = self.feature(&record).map_err(|err| {
+            format!("{}: {}", err, record.iter().collect::<Vec<&str>>().join(&self.delimiter.to_string()))
+        });
+
+        Some(Ok((Origin::line(line), feature)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn stream(name: &str, csv: &str, config: CsvConfig) -> Result<CsvStream, Error> {
+        let path = std::env::temp_dir().join(name);
+        std::fs::File::create(&path).unwrap().write_all(csv.as_bytes()).unwrap();
+
+        let stream = CsvStream::new(Some(path.to_str().unwrap().to_string()), config);
+
+        std::fs::remove_file(path).unwrap();
+
+        stream
+    }
+
+    #[test]
+    fn test_csv() {
+        let rows: Vec<Labelled> = stream("pt2itp-csv.csv", "LON,LAT,NUMBER,STREET,UNIT,CITY,POSTCODE,ID\n\
+            -77.0,38.9,10,Main St,4B,Washington,20001,1\n\
+            -77.0,NaN?,11,Main St,,,,\n\
+            -77.0,38.9,,Main St,,,,\n\
+            -77.0,38.9,12,Main St,,,,abc\n", CsvConfig::default()).unwrap().map(|row| row.unwrap()).collect();
+
+        assert_eq!(rows.len(), 4);
+
+        assert_eq!(rows[0].0, Origin::line(2));
+        assert_eq!(rows[0].1.as_ref().unwrap().to_string(), geojson::GeoJson::Feature(geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(geojson::Value::Point(vec![-77.0, 38.9]))),
+            id: Some(geojson::feature::Id::Number(serde_json::Number::from(1))),
+            properties: Some(serde_json::json!({
+                "number": "10",
+                "street": "Main St",
+                "unit": "4B",
+                "city": "Washington",
+                "postcode": "20001"
+            }).as_object().unwrap().clone()),
+            foreign_members: None
+        }).to_string());
+
+        assert_eq!(rows[1].0, Origin::line(3));
+        assert_eq!(rows[1].1.as_ref().unwrap_err(), "Invalid coordinates: -77.0,NaN?,11,Main St,,,,");
+
+        assert_eq!(rows[2].0, Origin::line(4));
+        assert_eq!(rows[2].1.as_ref().unwrap_err(), "Missing number: -77.0,38.9,,Main St,,,,");
+
+        match rows[3].1 {
+            Ok(geojson::GeoJson::Feature(ref feat)) => {
+                assert_eq!(feat.id, None);
+                assert_eq!(feat.properties.as_ref().unwrap()["id"], serde_json::Value::from("abc"));
+            },
+            _ => panic!("Expected feature")
+        };
+    }
+
+    #[test]
+    fn test_csv_config() {
+        let mut config = CsvConfig::default();
+        config.delimiter = ';';
+        config.number = String::from("housenumber");
+        config.street = String::from("street_name");
+
+        let rows: Vec<Labelled> = stream("pt2itp-csv-config.csv", "lon;lat;housenumber;street_name\n\
+            -77.0;38.9;10;Main St\n", config).unwrap().map(|row| row.unwrap()).collect();
+
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].1.is_ok());
+
+        match stream("pt2itp-csv-missing.csv", "lon,lat,number\n", CsvConfig::default()) {
+            Err(Error::Config(err)) => assert_eq!(err, "CSV input is missing the STREET column"),
+            _ => panic!("Expected missing column error")
+        };
+    }
+}
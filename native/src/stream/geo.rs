@@ -1,88 +1,284 @@
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read};
 use std::convert::From;
 use std::iter::Iterator;
 
+use crate::Error;
+use super::collection::{self, Collection};
+
+/// Bytes of input examined to determine if it is a FeatureCollection
+const DETECT_SIZE: usize = 64 * 1024;
+
 pub struct GeoStream {
-    input: Input
+    input: Input,
+
+    /// Line number of the most recently read line or feature
+    line: u64
 }
 
 pub enum Input {
-    File(std::io::Lines<BufReader<File>>),
-    StdIn(std::io::Lines<std::io::StdinLock<'static>>),
+    /// Line delimited GeoJSON features
+    Lines(std::io::Lines<BufReader<Box<dyn Read + Send>>>),
+
+    /// Features of a single GeoJSON FeatureCollection
+    Collection(Collection)
+}
+
+///
+/// Compression formats that are transparently decompressed,
+/// detected by the magic bytes at the start of the input
+///
+#[derive(Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Zstd
+}
+
+impl Compression {
+    pub fn detect(magic: &[u8]) -> Self {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if magic.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
 }
 
 impl GeoStream {
-    pub fn new(input: Option<String>) -> Self {
-        let stream = match input {
-            Some(inpath) => match File::open(inpath) {
-                Ok(file) => GeoStream {
-                    input: Input::File(BufReader::new(file).lines())
-                },
-                Err(err) => { panic!("Unable to open input file: {}", err); }
-            },
-            None => {
-                GeoStream {
-                    input: Input::StdIn(Box::leak(Box::new(io::stdin())).lock().lines())
-                }
-            }
+    pub fn new(input: Option<String>) -> Result<Self, Error> {
+        GeoStream::with_collection(input, None)
+    }
+
+    ///
+    /// Open an input as a FeatureCollection if `collection` is true or as line delimited
+    /// features if it is false, rather than detecting which it is when None
+    ///
+    pub fn with_collection(input: Option<String>, collection: Option<bool>) -> Result<Self, Error> {
+        let reader = GeoStream::reader(input)?;
+
+        let mut reader = BufReader::with_capacity(DETECT_SIZE, reader);
+
+        let collection = match collection {
+            Some(collection) => collection,
+            None => collection::detect(reader.fill_buf()?)
+        };
+
+        let input = match collection {
+            true => Input::Collection(Collection::new(reader)),
+            false => Input::Lines(reader.lines())
         };
 
-        stream
+        Ok(GeoStream {
+            input: input,
+            line: 0
+        })
     }
 
-    fn line(input: &mut Input) -> Option<String> {
-        match input {
-            Input::File(ref mut file) => match file.next() {
-                None => None,
-                Some(file) => match file {
-                    Ok(line) => Some(line),
-                    Err(err) => panic!("{}", err)
+    ///
+    /// Open the given file, or stdin if no file is given,
+    /// transparently decompressing it if it is compressed
+    ///
+    pub fn reader(input: Option<String>) -> Result<Box<dyn Read + Send>, Error> {
+        let reader: Box<dyn Read + Send> = match input {
+            Some(inpath) => match File::open(&inpath).and_then(GeoStream::decompress) {
+                Ok(reader) => reader,
+                Err(err) => {
+                    return Err(Error::Io(io::Error::new(err.kind(), format!("Unable to open input file {}: {}", inpath, err))));
                 }
             },
-            Input::StdIn(ref mut stdin) => match stdin.next() {
-                None => None,
-                Some(stdin) => match stdin {
-                    Ok(line) => Some(line),
-                    Err(err) => panic!("{}", err)
-                }
+            None => GeoStream::decompress(io::stdin())?
+        };
+
+        Ok(reader)
+    }
+
+    ///
+    /// Wrap the input in a decoder if it is compressed
+    ///
+    fn decompress(input: impl Read + Send + 'static) -> Result<Box<dyn Read + Send>, io::Error> {
+        let mut input = BufReader::new(input);
+
+        let compression = Compression::detect(input.fill_buf()?);
+
+        Ok(match compression {
+            Compression::None => Box::new(input),
+            // Multi member decoders are used as parallel compressors
+            // such as pigz & pbzip2 output concatenated streams
+            Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(input)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(input)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(input)?)
+        })
+    }
+
+    fn line(&mut self) -> Option<Result<String, Error>> {
+        let line = match self.input {
+            Input::Lines(ref mut lines) => {
+                self.line += 1;
+                lines.next()
+            },
+            Input::Collection(ref mut collection) => match collection.next() {
+                Some(Ok((line, feature))) => {
+                    self.line = line;
+                    return Some(Ok(feature));
+                },
+                Some(Err(err)) => { return Some(Err(err)); },
+                None => { return None; }
             }
+        };
+
+        match line {
+            None => None,
+            Some(Ok(line)) => Some(Ok(line)),
+            Some(Err(err)) => Some(Err(Error::Io(err)))
         }
     }
-}
-
-impl Iterator for GeoStream {
-    type Item = geojson::GeoJson;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    ///
+    /// Return the next non-empty line of the input, or feature of a FeatureCollection, with any
+    /// Ascii Record Separators at the beginning or end removed
+    ///
+    pub fn next_line(&mut self) -> Option<Result<String, Error>> {
         let mut line = Some(String::from(""));
 
         while line.is_some() && line.as_ref().unwrap().trim().len() == 0 {
-            line = match GeoStream::line(&mut self.input) {
+            line = match self.line() {
                 None => None,
-                Some(line) => Some(line)
+                Some(Ok(line)) => Some(line),
+                Some(Err(err)) => { return Some(Err(err)); }
             };
         }
 
         match line {
-            None => {
-                return None;
-            },
+            None => None,
             Some(mut line) => {
-                //Remove Ascii Record Separators at beginning or end of line
                 if line.ends_with("\u{001E}") {
                     line.pop();
                 } else if line.starts_with("\u{001E}") {
                     line.replace_range(0..1, "");
                 }
 
-                match line.parse::<geojson::GeoJson>() {
-                    Ok(geojson) => Some(geojson),
-                    Err(err) => {
-                        panic!("Invalid GeoJSON ({:?}): {}", err, line);
-                    }
-                }
+                Some(Ok(line))
             }
         }
     }
+
+    ///
+    /// Consume the stream, returning an iterator of its non-empty lines
+    /// along with their line number
+    ///
+    pub fn lines(mut self) -> impl Iterator<Item = Result<(u64, String), Error>> + Send {
+        std::iter::from_fn(move || {
+            let line = self.next_line()?;
+
+            Some(line.map(|line| (self.line, line)))
+        })
+    }
+
+    ///
+    /// Parse a single line of input as GeoJSON
+    ///
+    pub fn parse(line: String) -> Result<geojson::GeoJson, Error> {
+        match line.parse::<geojson::GeoJson>() {
+            Ok(geojson) => Ok(geojson),
+            Err(err) => Err(Error::Parse(format!("Invalid GeoJSON ({}): {}", err, line)))
+        }
+    }
+}
+
+impl Iterator for GeoStream {
+    type Item = Result<geojson::GeoJson, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_line() {
+            None => None,
+            Some(Ok(line)) => Some(GeoStream::parse(line)),
+            Some(Err(err)) => Some(Err(err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const FEATURES: &str = "{ \"type\": \"Feature\", \"properties\": { \"it\": 1 }, \"geometry\": null }\n{ \"type\": \"Feature\", \"properties\": { \"it\": 2 }, \"geometry\": null }\n";
+
+    fn read(name: &str, compressed: Vec<u8>) -> Vec<i64> {
+        let path = std::env::temp_dir().join(name);
+        File::create(&path).unwrap().write_all(&compressed).unwrap();
+
+        let its = GeoStream::new(Some(path.to_str().unwrap().to_string())).unwrap().map(|feat| {
+            match feat.unwrap() {
+                geojson::GeoJson::Feature(feat) => feat.properties.unwrap()["it"].as_i64().unwrap(),
+                _ => panic!("Not a GeoJSON Feature")
+            }
+        }).collect();
+
+        std::fs::remove_file(path).unwrap();
+
+        its
+    }
+
+    #[test]
+    fn test_compression_detect() {
+        assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+        assert_eq!(Compression::detect(b"BZh91AY"), Compression::Bzip2);
+        assert_eq!(Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]), Compression::Zstd);
+        assert_eq!(Compression::detect(b"{ \"type\""), Compression::None);
+        assert_eq!(Compression::detect(&[]), Compression::None);
+    }
+
+    #[test]
+    fn test_geostream_plain() {
+        assert_eq!(read("pt2itp-geo-plain.geojson", FEATURES.as_bytes().to_vec()), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_geostream_lines() {
+        let path = std::env::temp_dir().join("pt2itp-geo-lines.geojson");
+        File::create(&path).unwrap().write_all(format!("\n{}\n", FEATURES.replace("\n", "\n\n")).as_bytes()).unwrap();
+
+        let lines: Vec<u64> = GeoStream::new(Some(path.to_str().unwrap().to_string())).unwrap().lines().map(|line| line.unwrap().0).collect();
+
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(lines, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_geostream_collection() {
+        let collection = format!("{{ \"type\": \"FeatureCollection\", \"features\": [\n{}\n] }}", FEATURES.trim().replace("\n", ",\n"));
+
+        assert_eq!(read("pt2itp-geo-collection.geojson", collection.into_bytes()), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_geostream_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(FEATURES.as_bytes()).unwrap();
+
+        assert_eq!(read("pt2itp-geo-gzip.geojson.gz", encoder.finish().unwrap()), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_geostream_bzip2() {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(FEATURES.as_bytes()).unwrap();
+
+        assert_eq!(read("pt2itp-geo-bzip2.geojson.bz2", encoder.finish().unwrap()), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_geostream_zstd() {
+        let compressed = zstd::stream::encode_all(FEATURES.as_bytes(), 0).unwrap();
+
+        assert_eq!(read("pt2itp-geo-zstd.geojson.zst", compressed), vec![1, 2]);
+    }
 }
@@ -0,0 +1,715 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::iter::Iterator;
+use std::path::Path;
+use std::thread;
+
+use crossbeam::channel::{bounded, Receiver, Sender};
+use rusqlite::{Connection, NO_PARAMS, types::Value, types::ValueRef};
+
+use crate::{Crs, Error, stream::Labelled, stream::Origin, stream::wkb};
+
+/// GeoPackage geometry types read as addresses
+pub const POINTS: &[&str] = &["POINT"];
+
+/// GeoPackage geometry types read as networks
+pub const LINES: &[&str] = &["LINESTRING", "MULTILINESTRING"];
+
+/// GeoPackage geometry types read as polygons
+pub const POLYGONS: &[&str] = &["POLYGON", "MULTIPOLYGON"];
+
+/// SRS ids that are accepted as WGS84 lng/lat
+const SRS: &[i64] = &[4326, 0, -1];
+
+///
+/// Determine if an input or output path is a GeoPackage by its extension
+///
+pub fn is_gpkg(path: &str) -> bool {
+    Path::new(path).extension().map_or(false, |ext| ext.eq_ignore_ascii_case("gpkg"))
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+///
+/// A features table of a GeoPackage
+///
+struct Layer {
+    table: String,
+    geometry: String,
+    pk: Option<String>,
+
+    /// Organization & code of the layer's SRS, ie: EPSG & 26918, if it is not lng/lat
+    srs: Option<(String, i64)>
+}
+
+impl Layer {
+    ///
+    /// Find the first features table of a GeoPackage with one of the given geometry types
+    ///
+    fn find(conn: &Connection, kinds: &[&str]) -> Result<Self, Error> {
+        let mut stmt = conn.prepare("
+            SELECT
+                c.table_name,
+                g.column_name,
+                g.geometry_type_name,
+                g.srs_id
+            FROM
+                gpkg_contents c
+                    JOIN gpkg_geometry_columns g
+                        ON c.table_name = g.table_name
+            WHERE
+                c.data_type = 'features'
+            ORDER BY
+                c.rowid
+        ")?;
+
+        let layers = stmt.query_map(NO_PARAMS, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)?))
+        })?.collect::<Result<Vec<_>, _>>()?;
+
+        let (table, geometry, _, srs) = match layers.into_iter().find(|(_, _, kind, _)| {
+            kind.eq_ignore_ascii_case("GEOMETRY") || kinds.iter().any(|expected| kind.eq_ignore_ascii_case(expected))
+        }) {
+            Some(layer) => layer,
+            None => { return Err(Error::Config(format!("GeoPackage has no {} layer", kinds.join(" or ")))); }
+        };
+
+        let srs = match SRS.contains(&srs) {
+            true => None,
+            false => match conn.query_row("
+                SELECT
+                    organization,
+                    organization_coordsys_id
+                FROM
+                    gpkg_spatial_ref_sys
+                WHERE
+                    srs_id = ?1
+            ", &[&srs as &dyn rusqlite::ToSql], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))) {
+                Ok(srs) => Some(srs),
+                Err(_) => { return Err(Error::Config(format!("GeoPackage layer {} has an undefined srs_id {}", table, srs))); }
+            }
+        };
+
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", quote(&table)))?;
+
+        let pk = stmt.query_map(NO_PARAMS, |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)?))
+        })?.collect::<Result<Vec<_>, _>>()?.into_iter().find(|(_, pk)| *pk == 1).map(|(name, _)| name);
+
+        Ok(Layer {
+            table: table,
+            geometry: geometry,
+            pk: pk,
+            srs: srs
+        })
+    }
+}
+
+///
+/// Reads the features of a GeoPackage layer, converting each to a GeoJSON
+/// feature so it is validated exactly as GeoJSON input is
+///
+/// Columns other than the primary key & geometry become properties, with text
+/// holding a JSON array or object, such as a list of names, parsed as JSON.
+/// An integer id column is used as the feature id. Reading is performed on its
+/// own thread with features streamed back as they are read.
+///
+pub struct GpkgStream {
+    features: Receiver<Result<Labelled, Error>>,
+    reader: Option<thread::JoinHandle<()>>,
+    srs: Option<(String, i64)>
+}
+
+impl GpkgStream {
+    pub fn new(input: Option<String>, kinds: &[&str]) -> Result<Self, Error> {
+        let input = match input {
+            Some(input) => input,
+            None => { return Err(Error::Config(String::from("GeoPackage input must be a file"))); }
+        };
+
+        if !Path::new(&input).is_file() {
+            return Err(Error::Io(io::Error::new(io::ErrorKind::NotFound, format!("Unable to open input file {}", input))));
+        }
+
+        let conn = Connection::open(&input)?;
+        let layer = Layer::find(&conn, kinds)?;
+        let srs = layer.srs.clone();
+
+        let (tx, rx) = bounded(10000);
+
+        let reader = thread::Builder::new().name(String::from("GeoPackage Reader")).spawn(move || {
+            if let Err(err) = read(&conn, &layer, &tx) {
+                let _ = tx.send(Err(err));
+            }
+        })?;
+
+        Ok(GpkgStream {
+            features: rx,
+            reader: Some(reader),
+            srs: srs
+        })
+    }
+
+    ///
+    /// The CRS the features of the layer are in, the given CRS if it is projected,
+    /// otherwise the EPSG CRS of the layer so that a projected GeoPackage is
+    /// reprojected without its CRS having to be given
+    ///
+    pub fn crs(&self, given: &Crs) -> Result<Crs, Error> {
+        match self.srs {
+            Some((ref organization, code)) if given.is_geographic() => match organization.eq_ignore_ascii_case("EPSG") {
+                true => Crs::new(&format!("EPSG:{}", code)).map_err(Error::Config),
+                false => Err(Error::Config(format!("GeoPackage layer is in {}:{}, the input CRS must be given", organization, code)))
+            },
+            _ => Ok(given.clone())
+        }
+    }
+}
+
+impl Iterator for GpkgStream {
+    type Item = Result<Labelled, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.features.recv() {
+            Ok(feature) => Some(feature),
+            Err(_) => match self.reader.take().map(|reader| reader.join()) {
+                Some(Err(_)) => Some(Err(Error::Io(io::Error::new(io::ErrorKind::Other, "GeoPackage Reader thread panicked")))),
+                _ => None
+            }
+        }
+    }
+}
+
+fn read(conn: &Connection, layer: &Layer, tx: &Sender<Result<Labelled, Error>>) -> Result<(), Error> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", quote(&layer.table)))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+    let mut rows = stmt.query(NO_PARAMS)?;
+    let mut count: u64 = 0;
+
+    while let Some(row) = rows.next()? {
+        count += 1;
+
+        let mut fid = None;
+        let mut id = None;
+        let mut geometry = Ok(None);
+        let mut props = serde_json::Map::new();
+
+        for (i, column) in columns.iter().enumerate() {
+            let value = row.get_raw(i);
+
+            if Some(column) == layer.pk.as_ref() {
+                fid = value.as_i64().ok();
+            } else if column == &layer.geometry {
+                geometry = match value {
+                    ValueRef::Null => Ok(None),
+                    ValueRef::Blob(blob) => decode(blob),
+                    _ => Err(String::from("Invalid GeoPackage geometry"))
+                };
+            } else {
+                match value {
+                    ValueRef::Null | ValueRef::Blob(_) => (),
+                    ValueRef::Integer(int) if column == "id" => { id = Some(int); },
+                    ValueRef::Integer(int) => { props.insert(column.clone(), serde_json::Value::from(int)); },
+                    ValueRef::Real(real) => {
+                        if let Some(real) = serde_json::Number::from_f64(real) {
+                            props.insert(column.clone(), serde_json::Value::Number(real));
+                        }
+                    },
+                    ValueRef::Text(_) => {
+                        let text = value.as_str().map_err(|err| Error::Parse(format!("Invalid GeoPackage: {}", err)))?;
+                        props.insert(column.clone(), text_value(text));
+                    }
+                };
+            }
+        }
+
+        let origin = Origin::id(fid.unwrap_or(count as i64));
+
+        let feature = geometry.map(|geometry| geojson::GeoJson::Feature(geojson::Feature {
+            bbox: None,
+            geometry: geometry.map(geojson::Geometry::new),
+            id: id.map(|id| geojson::feature::Id::Number(serde_json::Number::from(id))),
+            properties: Some(props),
+            foreign_members: None
+        }));
+
+        if tx.send(Ok((origin, feature))).is_err() {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Text columns holding a JSON array or object are parsed, others kept as strings
+///
+fn text_value(text: &str) -> serde_json::Value {
+    let trimmed = text.trim_start();
+
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        if let Ok(json) = serde_json::from_str(text) {
+            return json;
+        }
+    }
+
+    serde_json::Value::from(text)
+}
+
+///
+/// Decode a GeoPackage geometry blob, a short header followed by WKB
+///
+fn decode(blob: &[u8]) -> Result<Option<geojson::Value>, String> {
+    if blob.len() < 8 || &blob[0..2] != b"GP" {
+        return Err(String::from("Invalid GeoPackage geometry"));
+    }
+
+    let flags = blob[3];
+
+    if flags & 0b0010_0000 != 0 {
+        return Err(String::from("Extended GeoPackage geometries are not supported"));
+    } else if flags & 0b0001_0000 != 0 {
+        return Ok(None);
+    }
+
+    let envelope = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        _ => { return Err(String::from("Invalid GeoPackage geometry envelope")); }
+    };
+
+    if blob.len() < 8 + envelope {
+        return Err(String::from("Invalid GeoPackage geometry"));
+    }
+
+    wkb::read(&blob[8 + envelope..])
+}
+
+///
+/// Encode a geometry as a GeoPackage geometry blob, without an envelope
+///
+fn encode(geom: &geojson::Value) -> Vec<u8> {
+    let mut blob = vec![b'G', b'P', 0, 0b0000_0001];
+    blob.extend_from_slice(&4326i32.to_le_bytes());
+    blob.extend_from_slice(&wkb::write(geom));
+
+    blob
+}
+
+const SCHEMA: &str = r#"
+    PRAGMA application_id = 1196444487;
+    PRAGMA user_version = 10200;
+
+    CREATE TABLE gpkg_spatial_ref_sys (
+        srs_name TEXT NOT NULL,
+        srs_id INTEGER NOT NULL PRIMARY KEY,
+        organization TEXT NOT NULL,
+        organization_coordsys_id INTEGER NOT NULL,
+        definition TEXT NOT NULL,
+        description TEXT
+    );
+
+    INSERT INTO gpkg_spatial_ref_sys VALUES
+        ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+        ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system'),
+        ('WGS 84 geodetic', 4326, 'EPSG', 4326, 'GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]', 'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');
+
+    CREATE TABLE gpkg_contents (
+        table_name TEXT NOT NULL PRIMARY KEY,
+        data_type TEXT NOT NULL,
+        identifier TEXT UNIQUE,
+        description TEXT DEFAULT '',
+        last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+        min_x DOUBLE,
+        min_y DOUBLE,
+        max_x DOUBLE,
+        max_y DOUBLE,
+        srs_id INTEGER,
+        CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+    );
+
+    CREATE TABLE gpkg_geometry_columns (
+        table_name TEXT NOT NULL,
+        column_name TEXT NOT NULL,
+        geometry_type_name TEXT NOT NULL,
+        srs_id INTEGER NOT NULL,
+        z TINYINT NOT NULL,
+        m TINYINT NOT NULL,
+        CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+        CONSTRAINT uk_gc_table_name UNIQUE (table_name),
+        CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+        CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+    );
+"#;
+
+///
+/// Writes line delimited GeoJSON features to a single layer of a new GeoPackage,
+/// named after the output file
+///
+/// Properties are written as columns, added as they are first seen, with arrays &
+/// objects such as names written as JSON text. The layer geometry type is taken
+/// from the first feature. Features are written in a transaction which is
+/// committed on flush.
+///
+pub struct GpkgWriter {
+    conn: Connection,
+    layer: String,
+    columns: HashSet<String>,
+    created: bool,
+    transaction: bool,
+    bounds: Option<[f64; 4]>,
+    buffer: Vec<u8>
+}
+
+impl GpkgWriter {
+    pub fn new(output: &str) -> Result<Self, Error> {
+        let path = Path::new(output);
+
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        let layer = match path.file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => String::from("features")
+        };
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(GpkgWriter {
+            conn: conn,
+            layer: layer,
+            columns: HashSet::new(),
+            created: false,
+            transaction: false,
+            bounds: None,
+            buffer: Vec::new()
+        })
+    }
+
+    fn create(&mut self, kind: &str) -> Result<(), Error> {
+        self.conn.execute_batch(&format!("
+            CREATE TABLE {} (
+                fid INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                geom {}
+            );
+        ", quote(&self.layer), kind))?;
+
+        self.conn.execute("
+            INSERT INTO gpkg_contents (table_name, data_type, identifier, srs_id) VALUES (?1, 'features', ?1, 4326)
+        ", &[&self.layer as &dyn rusqlite::ToSql])?;
+
+        self.conn.execute("
+            INSERT INTO gpkg_geometry_columns VALUES (?1, 'geom', ?2, 4326, 0, 0)
+        ", &[&self.layer as &dyn rusqlite::ToSql, &kind])?;
+
+        self.created = true;
+
+        Ok(())
+    }
+
+    ///
+    /// Column for a property, prefixed if it would clash with the primary key or geometry
+    ///
+    fn column(&mut self, key: &str, value: &serde_json::Value) -> Result<String, Error> {
+        let column = match key {
+            "fid" | "geom" => format!("property_{}", key),
+            key => String::from(key)
+        };
+
+        if !self.columns.contains(&column) {
+            let kind = match value {
+                serde_json::Value::Bool(_) => "BOOLEAN",
+                serde_json::Value::Number(num) if num.is_i64() => "INTEGER",
+                serde_json::Value::Number(_) => "REAL",
+                _ => "TEXT"
+            };
+
+            self.conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", quote(&self.layer), quote(&column), kind))?;
+            self.columns.insert(column.clone());
+        }
+
+        Ok(column)
+    }
+
+    pub fn feature(&mut self, feat: geojson::Feature) -> Result<(), Error> {
+        if !self.created {
+            let kind = match feat.geometry {
+                Some(ref geom) => match geom.value {
+                    geojson::Value::Point(_) => "POINT",
+                    geojson::Value::MultiPoint(_) => "MULTIPOINT",
+                    geojson::Value::LineString(_) => "LINESTRING",
+                    geojson::Value::MultiLineString(_) => "MULTILINESTRING",
+                    geojson::Value::Polygon(_) => "POLYGON",
+                    geojson::Value::MultiPolygon(_) => "MULTIPOLYGON",
+                    geojson::Value::GeometryCollection(_) => "GEOMETRYCOLLECTION"
+                },
+                None => "GEOMETRY"
+            };
+
+            self.create(kind)?;
+        }
+
+        if !self.transaction {
+            self.conn.execute_batch("BEGIN;")?;
+            self.transaction = true;
+        }
+
+        let mut columns = vec![String::from("geom")];
+        let mut values = vec![match feat.geometry {
+            Some(ref geom) => {
+                self.extend(&geom.value);
+                Value::Blob(encode(&geom.value))
+            },
+            None => Value::Null
+        }];
+
+        let mut props = feat.properties.unwrap_or_default();
+
+        if let Some(id) = feat.id {
+            props.insert(String::from("id"), match id {
+                geojson::feature::Id::Number(id) => serde_json::Value::Number(id),
+                geojson::feature::Id::String(id) => serde_json::Value::String(id)
+            });
+        }
+
+        for (key, value) in props {
+            if value.is_null() {
+                continue;
+            }
+
+            columns.push(self.column(&key, &value)?);
+
+            values.push(match value {
+                serde_json::Value::Bool(value) => Value::Integer(value as i64),
+                serde_json::Value::Number(num) => match num.as_i64() {
+                    Some(num) => Value::Integer(num),
+                    None => Value::Real(num.as_f64().unwrap_or(std::f64::NAN))
+                },
+                serde_json::Value::String(value) => Value::Text(value),
+                value => Value::Text(value.to_string())
+            });
+        }
+
+        let sql = format!("INSERT INTO {} ({}) VALUES ({})",
+            quote(&self.layer),
+            columns.iter().map(|column| quote(column)).collect::<Vec<String>>().join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+
+        self.conn.prepare_cached(&sql)?.execute(&values)?;
+
+        Ok(())
+    }
+
+    fn extend(&mut self, geom: &geojson::Value) {
+        let mut coords: Vec<&Vec<f64>> = Vec::new();
+
+        match geom {
+            geojson::Value::Point(pt) => coords.push(pt),
+            geojson::Value::MultiPoint(mpt) | geojson::Value::LineString(mpt) => coords.extend(mpt.iter()),
+            geojson::Value::MultiLineString(mln) | geojson::Value::Polygon(mln) => coords.extend(mln.iter().flatten()),
+            geojson::Value::MultiPolygon(mpy) => coords.extend(mpy.iter().flatten().flatten()),
+            geojson::Value::GeometryCollection(geoms) => {
+                for geom in geoms {
+                    self.extend(&geom.value);
+                }
+            }
+        };
+
+        for coord in coords {
+            if coord.len() < 2 || !coord[0].is_finite() || !coord[1].is_finite() {
+                continue;
+            }
+
+            self.bounds = Some(match self.bounds {
+                None => [coord[0], coord[1], coord[0], coord[1]],
+                Some(bounds) => [bounds[0].min(coord[0]), bounds[1].min(coord[1]), bounds[2].max(coord[0]), bounds[3].max(coord[1])]
+            });
+        }
+    }
+
+    ///
+    /// Parse & write a single line of GeoJSON
+    ///
+    fn line(&mut self, line: &[u8]) -> Result<(), Error> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        match line.parse::<geojson::GeoJson>()? {
+            geojson::GeoJson::Feature(feat) => self.feature(feat),
+            _ => Err(Error::Parse(String::from("GeoPackage output must be GeoJSON Features")))
+        }
+    }
+
+    ///
+    /// Commit features written so far, creating an empty layer if nothing has been written
+    ///
+    pub fn commit(&mut self) -> Result<(), Error> {
+        if !self.created {
+            self.create("GEOMETRY")?;
+        }
+
+        if let Some(bounds) = self.bounds {
+            self.conn.execute("
+                UPDATE gpkg_contents
+                    SET
+                        min_x = ?2,
+                        min_y = ?3,
+                        max_x = ?4,
+                        max_y = ?5,
+                        last_change = strftime('%Y-%m-%dT%H:%M:%fZ','now')
+                    WHERE
+                        table_name = ?1
+            ", &[&self.layer as &dyn rusqlite::ToSql, &bounds[0], &bounds[1], &bounds[2], &bounds[3]])?;
+        }
+
+        if self.transaction {
+            self.conn.execute_batch("COMMIT;")?;
+            self.transaction = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for GpkgWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+
+            self.line(&line)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let line: Vec<u8> = self.buffer.drain(..).collect();
+
+        self.line(&line)?;
+        self.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gpkg() {
+        assert!(is_gpkg("/tmp/addresses.gpkg"));
+        assert!(is_gpkg("/tmp/addresses.GPKG"));
+        assert!(!is_gpkg("/tmp/addresses.geojson"));
+        assert!(!is_gpkg("/tmp/gpkg"));
+    }
+
+    #[test]
+    fn test_gpkg_roundtrip() {
+        let path = std::env::temp_dir().join("pt2itp-gpkg-roundtrip.gpkg");
+        let path = path.to_str().unwrap().to_string();
+
+        let mut writer = GpkgWriter::new(&path).unwrap();
+        writer.write_all(br#"{"type":"Feature","id":1,"properties":{"number":"10","street":[{"display":"Main St","priority":0}],"fid":3},"geometry":{"type":"Point","coordinates":[-77.0,38.9]}}"#).unwrap();
+        writer.write_all(b"\n").unwrap();
+        writer.write_all(br#"{"type":"Feature","properties":{"number":"12"},"geometry":null}"#).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        let conn = Connection::open(&path).unwrap();
+        let bounds: (f64, f64) = conn.query_row("SELECT min_x, max_y FROM gpkg_contents WHERE table_name = 'pt2itp-gpkg-roundtrip'", NO_PARAMS, |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        }).unwrap();
+        assert_eq!(bounds, (-77.0, 38.9));
+        drop(conn);
+
+        let features: Vec<Labelled> = GpkgStream::new(Some(path.clone()), POINTS).unwrap().map(|feat| feat.unwrap()).collect();
+
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].0, Origin::id(1));
+        assert_eq!(features[0].1.as_ref().unwrap().to_string(), r#"{"geometry":{"coordinates":[-77.0,38.9],"type":"Point"},"id":1,"properties":{"number":"10","property_fid":3,"street":[{"display":"Main St","priority":0}]},"type":"Feature"}"#);
+        assert_eq!(features[1].1.as_ref().unwrap().to_string(), r#"{"geometry":null,"properties":{"number":"12"},"type":"Feature"}"#);
+
+        let stream = GpkgStream::new(Some(path.clone()), POINTS).unwrap();
+        assert_eq!(stream.crs(&Crs::default()).unwrap(), Crs::default());
+        assert_eq!(stream.crs(&Crs::new("EPSG:32618").unwrap()).unwrap(), Crs::new("EPSG:32618").unwrap());
+        assert_eq!(stream.count(), 2);
+
+        match GpkgStream::new(Some(path.clone()), LINES) {
+            Err(Error::Config(err)) => assert_eq!(err, "GeoPackage has no LINESTRING or MULTILINESTRING layer"),
+            _ => panic!("Expected missing layer error")
+        };
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_gpkg_srs() {
+        let path = std::env::temp_dir().join("pt2itp-gpkg-srs.gpkg");
+        let path = path.to_str().unwrap().to_string();
+
+        let mut writer = GpkgWriter::new(&path).unwrap();
+        writer.write_all(br#"{"type":"Feature","properties":{"number":"10"},"geometry":{"type":"Point","coordinates":[500000.0,0.0]}}"#).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch("
+            INSERT INTO gpkg_spatial_ref_sys VALUES ('WGS 84 / UTM zone 31N', 32631, 'EPSG', 32631, 'undefined', NULL);
+            INSERT INTO gpkg_spatial_ref_sys VALUES ('Local grid', 100000, 'LOCAL', 1, 'undefined', NULL);
+            UPDATE gpkg_geometry_columns SET srs_id = 32631;
+        ").unwrap();
+
+        let stream = GpkgStream::new(Some(path.clone()), POINTS).unwrap();
+        assert_eq!(stream.crs(&Crs::default()).unwrap(), Crs::new("EPSG:32631").unwrap());
+        assert_eq!(stream.crs(&Crs::new("EPSG:32618").unwrap()).unwrap(), Crs::new("EPSG:32618").unwrap());
+        assert_eq!(stream.count(), 1);
+
+        conn.execute_batch("UPDATE gpkg_geometry_columns SET srs_id = 100000;").unwrap();
+
+        let stream = GpkgStream::new(Some(path.clone()), POINTS).unwrap();
+        assert_eq!(stream.crs(&Crs::default()).unwrap_err().to_string(), "Config Error: GeoPackage layer is in LOCAL:1, the input CRS must be given");
+        assert_eq!(stream.crs(&Crs::new("EPSG:32618").unwrap()).unwrap(), Crs::new("EPSG:32618").unwrap());
+        assert_eq!(stream.count(), 1);
+
+        conn.execute_batch("UPDATE gpkg_geometry_columns SET srs_id = 3857;").unwrap();
+
+        match GpkgStream::new(Some(path.clone()), POINTS) {
+            Err(Error::Config(err)) => assert_eq!(err, "GeoPackage layer pt2itp-gpkg-srs has an undefined srs_id 3857"),
+            _ => panic!("Expected undefined srs error")
+        };
+
+        drop(conn);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(&encode(&geojson::Value::Point(vec![1.0, 2.0]))).unwrap(), Some(geojson::Value::Point(vec![1.0, 2.0])));
+
+        // Header with an XY envelope
+        let mut blob = vec![b'G', b'P', 0, 0b0000_0011, 0, 0, 0, 0];
+        blob.extend_from_slice(&[0; 32]);
+        blob.extend_from_slice(&wkb::write(&geojson::Value::Point(vec![1.0, 2.0])));
+        assert_eq!(decode(&blob).unwrap(), Some(geojson::Value::Point(vec![1.0, 2.0])));
+
+        // Empty geometry flag
+        assert_eq!(decode(&[b'G', b'P', 0, 0b0001_0001, 0, 0, 0, 0]).unwrap(), None);
+
+        assert!(decode(b"not a geometry").is_err());
+    }
+}
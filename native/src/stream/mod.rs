@@ -1,9 +1,26 @@
 pub mod addr;
+pub mod collection;
+pub mod csv;
 pub mod geo;
+pub mod gpkg;
 pub mod net;
+pub mod osm;
+pub mod parallel;
 pub mod poly;
+pub mod rejection;
+pub mod wkb;
 
 pub use self::poly::PolyStream;
 pub use self::geo::GeoStream;
 pub use self::addr::AddrStream;
 pub use self::net::NetStream;
+pub use self::csv::{CsvConfig, CsvStream};
+pub use self::osm::{OsmConfig, OsmKind, OsmStream};
+pub use self::gpkg::{GpkgStream, GpkgWriter};
+pub use self::rejection::{ErrorSink, Origin, Policy, Rejection};
+
+///
+/// A feature read from a CSV, OSM PBF or GeoPackage input, labelled with
+/// its origin in the input so that invalid features can be traced
+///
+pub type Labelled = (Origin, Result<geojson::GeoJson, String>);
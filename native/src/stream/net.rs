@@ -1,28 +1,77 @@
 use std::convert::From;
 use std::iter::Iterator;
-use std::io::{Write, BufWriter};
-use std::fs::File;
 
-use crate::{stream::geo::GeoStream, Network, Context};
+use crate::{Error, Progress, pg::binary, stream::Labelled, stream::rejection::{self, ErrorSink, Origin, Rejection}, stream::geo::GeoStream, stream::gpkg::{self, GpkgStream}, stream::parallel::Parallel, Network, Context};
 
 pub struct NetStream {
-    context: Context,
-    input: GeoStream,
+    input: Parallel<(Origin, Network)>,
     buffer: Option<Vec<u8>>, //Used by Read impl for storing partial features
-    errors: Option<BufWriter<File>>
+    header: bool, //Used by Read impl to track if the COPY header has been written
+    trailer: bool, //Used by Read impl to track if the COPY trailer has been written
+    errors: ErrorSink,
+    progress: Progress
 }
 
 impl NetStream {
-    pub fn new(input: GeoStream, context: Context, errors: Option<String>) -> Self {
-        NetStream {
-            context: context,
+    pub fn new(input: GeoStream, context: Context, errors: ErrorSink, progress: Progress) -> Result<Self, Error> {
+        // Parsing & tokenization are performed across a pool of worker
+        // threads, with features output in the same order as the input
+        let keep = errors.is_enabled();
+        let policy = errors.get_policy();
+
+        let input = Parallel::new(input.lines(), move |(line, raw)| {
+            let origin = Origin::line(line);
+
+            Ok(rejection::build(&origin, policy.parse(raw)?, keep, |feat| Network::new(context.crs.feature(feat)?, &context)).map(|net| (origin, net)))
+        })?;
+
+        NetStream::build(input, errors, progress)
+    }
+
+    ///
+    /// Stream networks from a GeoPackage if the input has a .gpkg extension, otherwise GeoJSON
+    ///
+    pub fn open(input: Option<String>, context: Context, errors: ErrorSink, progress: Progress) -> Result<Self, Error> {
+        match input.as_ref().map_or(false, |input| gpkg::is_gpkg(input)) {
+            true => {
+                let input = GpkgStream::new(input, gpkg::LINES)?;
+
+                let mut context = context;
+                context.crs = input.crs(&context.crs)?;
+
+                NetStream::labelled(input, context, errors, progress)
+            },
+            false => NetStream::new(GeoStream::new(input)?, context, errors, progress)
+        }
+    }
+
+    ///
+    /// Stream networks from an OSM PBF or GeoPackage input, rejecting
+    /// invalid features with their origin in the input
+    ///
+    pub fn labelled<S>(input: S, context: Context, errors: ErrorSink, progress: Progress) -> Result<Self, Error>
+    where
+        S: Iterator<Item = Result<Labelled, Error>> + Send + 'static
+    {
+        let keep = errors.is_enabled();
+        let policy = errors.get_policy();
+
+        let input = Parallel::new(input, move |(origin, feat)| {
+            Ok(rejection::build(&origin, policy.check(feat)?, keep, |feat| Network::new(context.crs.feature(feat)?, &context)).map(|net| (origin, net)))
+        })?;
+
+        NetStream::build(input, errors, progress)
+    }
+
+    fn build(input: Parallel<(Origin, Network)>, errors: ErrorSink, progress: Progress) -> Result<Self, Error> {
+        Ok(NetStream {
             input: input,
             buffer: None,
-            errors: match errors {
-                None => None,
-                Some(path) => Some(BufWriter::new(File::create(path).unwrap()))
-            }
-        }
+            header: false,
+            trailer: false,
+            errors: errors.reset(),
+            progress: progress
+        })
     }
 }
 
@@ -36,12 +85,22 @@ impl std::io::Read for NetStream {
             if self.buffer.is_some() {
                 write = self.buffer.take().unwrap();
             } else {
-                let feat = match self.next() {
-                    Some(feat) => feat.to_tsv(),
-                    None => String::from("")
+                let mut bytes = if !self.header {
+                    self.header = true;
+                    binary::HEADER.to_vec()
+                } else {
+                    match self.next() {
+                        Some(Ok(feat)) => feat.to_copy()?,
+                        Some(Err(err)) => { return Err(err.into()); },
+                        None => if !self.trailer {
+                            self.trailer = true;
+                            binary::TRAILER.to_vec()
+                        } else {
+                            Vec::new()
+                        }
+                    }
                 };
 
-                let mut bytes = feat.into_bytes();
                 if bytes.len() == 0 {
                     end = true;
                 } else {
@@ -67,45 +126,43 @@ impl std::io::Read for NetStream {
 }
 
 impl Iterator for NetStream {
-    type Item = Network;
+    type Item = Result<Network, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut next: Result<Network, String> = Err(String::from(""));
-   
-        while next.is_err() {
-            next = match self.input.next() {
-                Some(potential) => match Network::new(potential, &self.context) {
-                    Ok(potential) => Ok(potential),
-                    Err(err) => match self.errors {
-                        None => Err(err),
-                        Some(ref mut file) => {
-                            file.write(format!("{}\n", err).as_bytes()).unwrap();
-
-                            Err(err)
-                        }
-                    }
-                },
+        loop {
+            let built = match self.input.next() {
+                Some(Ok(built)) => built,
+                Some(Err(err)) => { return Some(Err(err)); },
                 None => { return None; }
             };
-        }
-        while next.is_err() {
-            next = match self.input.next() {
-                Some(potential) => match Network::new(potential, &self.context) {
-                    Ok(potential) => Ok(potential),
-                    Err(err) => match self.errors {
-                        None => Err(err),
-                        Some(ref mut file) => {
-                            file.write(format!("{}\n", err).as_bytes()).unwrap();
-
-                            Err(err)
+
+            match built {
+                Ok((origin, mut feat)) => {
+                    self.progress.feature();
+
+                    // Repairs made to the geometry are reported without rejecting the network
+                    for repair in feat.repairs.drain(..) {
+                        let mut repaired = Rejection::new(&origin, "REPAIRED", repair);
+
+                        if origin.id.is_none() {
+                            repaired.id = feat.id.map(serde_json::Value::from);
+                        }
+
+                        if let Err(err) = self.errors.write(&repaired) {
+                            return Some(Err(err));
                         }
                     }
+
+                    return Some(Ok(feat));
                 },
-                None => { return None; }
+                Err(err) => {
+                    self.progress.error();
+
+                    if let Err(err) = self.errors.write(&err) {
+                        return Some(Err(err));
+                    }
+                }
             };
         }
-
-        Some(next.unwrap())
-
     }
 }
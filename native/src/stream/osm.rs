@@ -0,0 +1,392 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::iter::Iterator;
+use std::thread;
+
+use crossbeam::channel::{bounded, Receiver, Sender};
+use osmpbfreader::{OsmObj, OsmPbfReader, Tags};
+
+use crate::{Error, stream::Labelled, stream::Origin};
+
+///
+/// Options for reading an OpenStreetMap PBF extract
+///
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct OsmConfig {
+    /// Values of the highway tag imported as part of the street network
+    pub highways: Vec<String>
+}
+
+impl Default for OsmConfig {
+    fn default() -> Self {
+        OsmConfig {
+            highways: vec![
+                "motorway", "motorway_link",
+                "trunk", "trunk_link",
+                "primary", "primary_link",
+                "secondary", "secondary_link",
+                "tertiary", "tertiary_link",
+                "unclassified",
+                "residential",
+                "living_street",
+                "pedestrian",
+                "service",
+                "road"
+            ].into_iter().map(String::from).collect()
+        }
+    }
+}
+
+///
+/// Type of feature to read from a PBF
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OsmKind {
+    /// Nodes & ways with addr:housenumber & addr:street tags
+    Address,
+
+    /// Ways with a highway tag & name, alt_name or ref tag
+    Network
+}
+
+///
+/// Reads addresses or street network features from an OpenStreetMap PBF extract,
+/// converting each to a GeoJSON feature so it is validated exactly as GeoJSON input is
+///
+/// The extract is read twice, first to find matching nodes & ways and then to find
+/// the locations of the nodes those ways reference. Reading is performed on its own
+/// thread with features streamed back as they are built.
+///
+pub struct OsmStream {
+    features: Receiver<Result<Labelled, Error>>,
+    reader: Option<thread::JoinHandle<()>>
+}
+
+impl OsmStream {
+    pub fn new(input: Option<String>, kind: OsmKind, config: OsmConfig) -> Result<Self, Error> {
+        let input = match input {
+            Some(input) => input,
+            None => { return Err(Error::Config(String::from("OSM PBF input must be a file"))); }
+        };
+
+        let file = match File::open(&input) {
+            Ok(file) => file,
+            Err(err) => {
+                return Err(Error::Io(std::io::Error::new(err.kind(), format!("Unable to open input file {}: {}", input, err))));
+            }
+        };
+
+        let (tx, rx) = bounded(10000);
+
+        let reader = thread::Builder::new().name(String::from("OSM Reader")).spawn(move || {
+            if let Err(err) = read(OsmPbfReader::new(file), kind, &config, &tx) {
+                let _ = tx.send(Err(err));
+            }
+        })?;
+
+        Ok(OsmStream {
+            features: rx,
+            reader: Some(reader)
+        })
+    }
+}
+
+impl Iterator for OsmStream {
+    type Item = Result<Labelled, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.features.recv() {
+            Ok(feature) => Some(feature),
+            Err(_) => match self.reader.take().map(|reader| reader.join()) {
+                Some(Err(_)) => Some(Err(Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "OSM Reader thread panicked")))),
+                _ => None
+            }
+        }
+    }
+}
+
+fn osm_error(err: osmpbfreader::Error) -> Error {
+    match err {
+        osmpbfreader::Error::Io(err) => Error::Io(err),
+        err => Error::Parse(format!("Invalid OSM PBF: {}", err))
+    }
+}
+
+///
+/// A way which matched on the first pass & is built
+/// once the locations of its nodes are known
+///
+struct Way {
+    id: i64,
+    nodes: Vec<i64>,
+    tags: Tags
+}
+
+fn read(mut pbf: OsmPbfReader<File>, kind: OsmKind, config: &OsmConfig, tx: &Sender<Result<Labelled, Error>>) -> Result<(), Error> {
+    let mut ways: Vec<Way> = Vec::new();
+    let mut needed: HashSet<i64> = HashSet::new();
+
+    for obj in pbf.iter() {
+        match obj.map_err(osm_error)? {
+            OsmObj::Node(node) => {
+                if kind == OsmKind::Address && is_address(&node.tags) {
+                    let feature = address(&node.tags, vec![node.lon(), node.lat()]);
+
+                    if tx.send(Ok((Origin::id(format!("node/{}", node.id.0)), feature))).is_err() {
+                        return Ok(());
+                    }
+                }
+            },
+            OsmObj::Way(way) => {
+                let matched = match kind {
+                    OsmKind::Address => is_address(&way.tags),
+                    OsmKind::Network => is_network(&way.tags, config)
+                };
+
+                if matched {
+                    let nodes: Vec<i64> = way.nodes.iter().map(|node| node.0).collect();
+                    needed.extend(nodes.iter());
+
+                    ways.push(Way {
+                        id: way.id.0,
+                        nodes: nodes,
+                        tags: way.tags
+                    });
+                }
+            },
+            OsmObj::Relation(_) => ()
+        };
+    }
+
+    if ways.is_empty() {
+        return Ok(());
+    }
+
+    pbf.rewind().map_err(osm_error)?;
+
+    let mut locations: HashMap<i64, (f64, f64)> = HashMap::with_capacity(needed.len());
+
+    for obj in pbf.iter() {
+        if let OsmObj::Node(node) = obj.map_err(osm_error)? {
+            if needed.contains(&node.id.0) {
+                locations.insert(node.id.0, (node.lon(), node.lat()));
+            }
+        }
+    }
+
+    for way in ways {
+        let coords: Vec<Vec<f64>> = way.nodes.iter().filter_map(|node| {
+            locations.get(node).map(|&(lon, lat)| vec![lon, lat])
+        }).collect();
+
+        let feature = if coords.len() < 2 {
+            Err(String::from("Way is missing node locations"))
+        } else {
+            match kind {
+                OsmKind::Address => address(&way.tags, centroid(&coords)),
+                OsmKind::Network => network(&way.tags, coords)
+            }
+        };
+
+        if tx.send(Ok((Origin::id(format!("way/{}", way.id)), feature))).is_err() {
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+fn tag(tags: &Tags, key: &str) -> Option<String> {
+    tags.get(key).map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+fn is_address(tags: &Tags) -> bool {
+    tag(tags, "addr:housenumber").is_some() && tag(tags, "addr:street").is_some()
+}
+
+fn is_network(tags: &Tags, config: &OsmConfig) -> bool {
+    let highway = match tag(tags, "highway") {
+        Some(highway) => highway,
+        None => { return false; }
+    };
+
+    config.highways.contains(&highway)
+        && (tag(tags, "name").is_some() || tag(tags, "alt_name").is_some() || tag(tags, "ref").is_some())
+}
+
+fn feature(geometry: geojson::Value, props: serde_json::Map<String, serde_json::Value>) -> Result<geojson::GeoJson, String> {
+    Ok(geojson::GeoJson::Feature(geojson::Feature {
+        bbox: None,
+        geometry: Some(geojson::Geometry::new(geometry)),
+        id: None,
+        properties: Some(props),
+        foreign_members: None
+    }))
+}
+
+fn address(tags: &Tags, point: Vec<f64>) -> Result<geojson::GeoJson, String> {
+    let mut props = serde_json::Map::new();
+
+    for (key, prop) in vec![
+        ("addr:housenumber", "number"),
+        ("addr:street", "street"),
+        ("addr:unit", "unit"),
+        ("addr:city", "city"),
+        ("addr:postcode", "postcode")
+    ] {
+        if let Some(value) = tag(tags, key) {
+            props.insert(String::from(prop), serde_json::Value::from(value));
+        }
+    }
+
+    feature(geojson::Value::Point(point), props)
+}
+
+fn network(tags: &Tags, coords: Vec<Vec<f64>>) -> Result<geojson::GeoJson, String> {
+    let mut props = serde_json::Map::new();
+
+    props.insert(String::from("street"), serde_json::Value::from(names(tags)));
+
+    if let Some(highway) = tag(tags, "highway") {
+        props.insert(String::from("highway"), serde_json::Value::from(highway));
+    }
+
+    feature(geojson::Value::LineString(coords), props)
+}
+
+///
+/// Map the name, alt_name & ref tags of a way to street names in order of priority.
+/// Multiple values of a single tag are separated by semicolons
+///
+fn names(tags: &Tags) -> Vec<serde_json::Value> {
+    let mut names: Vec<(String, i64)> = Vec::new();
+
+    for (key, priority) in vec![("name", 0), ("alt_name", -1), ("ref", -2)] {
+        if let Some(value) = tag(tags, key) {
+            for name in value.split(';').map(str::trim).filter(|name| !name.is_empty()) {
+                names.push((String::from(name), priority));
+            }
+        }
+    }
+
+    // A network must have a single name with a higher priority than all others
+    if names.len() > 1 && names[0].1 == names[1].1 {
+        names[0].1 += 1;
+    }
+
+    names.into_iter().map(|(display, priority)| {
+        serde_json::json!({
+            "display": display,
+            "priority": priority
+        })
+    }).collect()
+}
+
+///
+/// Centroid of a way, area weighted if the way is a closed ring
+/// with an area, otherwise the mean of its vertices
+///
+fn centroid(coords: &[Vec<f64>]) -> Vec<f64> {
+    let closed = coords.len() > 3 && coords[0] == coords[coords.len() - 1];
+
+    if closed {
+        let mut area = 0.0;
+        let mut x = 0.0;
+        let mut y = 0.0;
+
+        for pair in coords.windows(2) {
+            let cross = pair[0][0] * pair[1][1] - pair[1][0] * pair[0][1];
+
+            area += cross;
+            x += (pair[0][0] + pair[1][0]) * cross;
+            y += (pair[0][1] + pair[1][1]) * cross;
+        }
+
+        if area.abs() > std::f64::EPSILON {
+            return vec![x / (3.0 * area), y / (3.0 * area)];
+        }
+    }
+
+    let len = coords.len() as f64;
+
+    vec![
+        coords.iter().map(|coord| coord[0]).sum::<f64>() / len,
+        coords.iter().map(|coord| coord[1]).sum::<f64>() / len
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: Vec<(&str, &str)>) -> Tags {
+        let mut tags = Tags::new();
+
+        for (key, value) in pairs {
+            tags.insert(key.into(), value.into());
+        }
+
+        tags
+    }
+
+    #[test]
+    fn test_centroid() {
+        assert_eq!(centroid(&vec![
+            vec![0.0, 0.0],
+            vec![2.0, 0.0],
+            vec![2.0, 2.0],
+            vec![0.0, 2.0],
+            vec![0.0, 0.0]
+        ]), vec![1.0, 1.0]);
+
+        assert_eq!(centroid(&vec![
+            vec![0.0, 0.0],
+            vec![2.0, 4.0]
+        ]), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(names(&tags(vec![("name", "Main Street"), ("alt_name", "Old Road;Mill Lane"), ("ref", "US 1")])), vec![
+            serde_json::json!({ "display": "Main Street", "priority": 0 }),
+            serde_json::json!({ "display": "Old Road", "priority": -1 }),
+            serde_json::json!({ "display": "Mill Lane", "priority": -1 }),
+            serde_json::json!({ "display": "US 1", "priority": -2 })
+        ]);
+
+        assert_eq!(names(&tags(vec![("ref", "US 1;VA 7")])), vec![
+            serde_json::json!({ "display": "US 1", "priority": -1 }),
+            serde_json::json!({ "display": "VA 7", "priority": -2 })
+        ]);
+    }
+
+    #[test]
+    fn test_is_network() {
+        let config = OsmConfig::default();
+
+        assert!(is_network(&tags(vec![("highway", "residential"), ("name", "Main Street")]), &config));
+        assert!(is_network(&tags(vec![("highway", "primary"), ("ref", "US 1")]), &config));
+        assert!(!is_network(&tags(vec![("highway", "footway"), ("name", "Main Street")]), &config));
+        assert!(!is_network(&tags(vec![("highway", "residential")]), &config));
+
+        let config = OsmConfig {
+            highways: vec![String::from("footway")]
+        };
+
+        assert!(is_network(&tags(vec![("highway", "footway"), ("name", "Main Street")]), &config));
+        assert!(!is_network(&tags(vec![("highway", "residential"), ("name", "Main Street")]), &config));
+    }
+
+    #[test]
+    fn test_address() {
+        let feat = address(&tags(vec![
+            ("addr:housenumber", "10"),
+            ("addr:street", "Main Street"),
+            ("addr:postcode", "20001"),
+            ("building", "yes")
+        ]), vec![1.0, 2.0]).unwrap();
+
+        assert_eq!(feat.to_string(), r#"{"geometry":{"coordinates":[1.0,2.0],"type":"Point"},"properties":{"number":"10","postcode":"20001","street":"Main Street"},"type":"Feature"}"#);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::iter::Iterator;
+use std::sync::Arc;
+use std::thread;
+
+use crossbeam::channel::{bounded, Receiver};
+
+use crate::{Error, stream::rejection::Rejection};
+
+/// Number of lines handed to a worker at a time
+const BATCH_SIZE: usize = 1000;
+
+/// Result of building a single input item, the inner error being an invalid
+/// feature which should be rejected & skipped, the outer a fatal stream error
+pub type Built<T> = Result<Result<T, Rejection>, Error>;
+
+///
+/// Parse & build features from an input stream across a pool of worker threads
+///
+/// A reader thread splits the input into batches of items (ie: lines), worker
+/// threads parse each item and build the feature, and the iterator reassembles
+/// the results so that they are yielded in the same order as the input
+///
+pub struct Parallel<T: Send + 'static> {
+    results: Receiver<(usize, Vec<Built<T>>)>,
+
+    /// Batches which have arrived before the batch that is next in order
+    pending: HashMap<usize, Vec<Built<T>>>,
+
+    /// Sequence number of the next batch to be output
+    next: usize,
+
+    current: std::vec::IntoIter<Built<T>>,
+
+    workers: Vec<thread::JoinHandle<()>>
+}
+
+impl<T: Send + 'static> Parallel<T> {
+    pub fn new<I, S, F>(input: S, build: F) -> Result<Self, Error>
+    where
+        I: Send + 'static,
+        S: Iterator<Item = Result<I, Error>> + Send + 'static,
+        F: Fn(I) -> Built<T> + Send + Sync + 'static
+    {
+        let cpus = num_cpus::get();
+        let build = Arc::new(build);
+
+        let (line_tx, line_rx) = bounded::<(usize, Vec<Result<I, Error>>)>(cpus * 2);
+        let (result_tx, result_rx) = bounded::<(usize, Vec<Built<T>>)>(cpus * 2);
+
+        let mut workers = Vec::with_capacity(cpus + 1);
+
+        workers.push(thread::Builder::new().name(String::from("Stream Reader")).spawn(move || {
+            let mut seq = 0;
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+            for line in input {
+                let failed = line.is_err();
+
+                batch.push(line);
+
+                if batch.len() == BATCH_SIZE || failed {
+                    if line_tx.send((seq, batch)).is_err() {
+                        // Output has gone away, nothing left to do
+                        return;
+                    }
+
+                    seq += 1;
+                    batch = Vec::with_capacity(BATCH_SIZE);
+                }
+
+                // An IO error ends the stream
+                if failed {
+                    return;
+                }
+            }
+
+            if !batch.is_empty() {
+                // If the output has gone away there is nothing left to do
+                let _ = line_tx.send((seq, batch));
+            }
+        })?);
+
+        for cpu in 0..cpus {
+            let line_rx = line_rx.clone();
+            let result_tx = result_tx.clone();
+            let build = build.clone();
+
+            workers.push(thread::Builder::new().name(format!("Stream Worker #{}", cpu)).spawn(move || {
+                for (seq, lines) in line_rx.iter() {
+                    let built: Vec<Built<T>> = lines.into_iter().map(|line| {
+                        build(line?)
+                    }).collect();
+
+                    if result_tx.send((seq, built)).is_err() {
+                        return;
+                    }
+                }
+            })?);
+        }
+
+        Ok(Parallel {
+            results: result_rx,
+            pending: HashMap::new(),
+            next: 0,
+            current: Vec::new().into_iter(),
+            workers: workers
+        })
+    }
+
+    ///
+    /// Called once all workers have finished, ensuring none of them failed
+    /// part way through and silently dropped a batch
+    ///
+    fn finish(&mut self) -> Option<Built<T>> {
+        let mut panicked = !self.pending.is_empty();
+
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                panicked = true;
+            }
+        }
+
+        match panicked {
+            true => Some(Err(Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "Stream worker thread panicked")))),
+            false => None
+        }
+    }
+}
+
+impl<T: Send + 'static> Iterator for Parallel<T> {
+    type Item = Built<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(built) = self.current.next() {
+                return Some(built);
+            }
+
+            if let Some(batch) = self.pending.remove(&self.next) {
+                self.current = batch.into_iter();
+                self.next += 1;
+                continue;
+            }
+
+            match self.results.recv() {
+                Ok((seq, batch)) => {
+                    self.pending.insert(seq, batch);
+                },
+                Err(_) => {
+                    return self.finish();
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use crate::stream::{GeoStream, Origin};
+
+    #[test]
+    fn test_parallel_order() {
+        let path = std::env::temp_dir().join("pt2itp-parallel-order.geojson");
+
+        {
+            let mut file = std::fs::File::create(&path).unwrap();
+            for it in 0..5000 {
+                writeln!(file, r#"{{ "type": "Feature", "properties": {{ "it": {} }}, "geometry": null }}"#, it).unwrap();
+            }
+        }
+
+        let stream = Parallel::new(GeoStream::new(Some(path.to_str().unwrap().to_string())).unwrap().lines(), |(line, raw)| {
+            Ok(match GeoStream::parse(raw)? {
+                geojson::GeoJson::Feature(feat) => {
+                    let it = feat.properties.unwrap()["it"].as_i64().unwrap();
+
+                    match it % 7 {
+                        0 => Err(Rejection::new(&Origin::line(line), "EVALIDATION", format!("invalid {}", it))),
+                        _ => Ok(it)
+                    }
+                },
+                _ => Err(Rejection::new(&Origin::line(line), "EVALIDATION", String::from("Not a GeoJSON Feature")))
+            })
+        }).unwrap();
+
+        let mut expected = 0;
+        for built in stream {
+            match built.unwrap() {
+                Ok(it) => assert_eq!(it, expected),
+                Err(err) => {
+                    assert_eq!(err.message, format!("invalid {}", expected));
+                    assert_eq!(err.line, Some(expected as u64 + 1));
+                }
+            };
+
+            expected += 1;
+        }
+
+        assert_eq!(expected, 5000);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
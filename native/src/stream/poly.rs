@@ -1,26 +1,69 @@
 use std::convert::From;
 use std::iter::Iterator;
-use std::io::{Write, BufWriter};
-use std::fs::File;
 
-use crate::{stream::geo::GeoStream, Polygon};
+use crate::{Error, Progress, pg::binary, stream::Labelled, stream::rejection::{self, ErrorSink, Origin}, stream::geo::GeoStream, stream::gpkg::{self, GpkgStream}, stream::parallel::Built, Crs, Polygon};
 
 pub struct PolyStream {
-    input: GeoStream,
+    input: Box<dyn Iterator<Item = Built<Polygon>> + Send>,
     buffer: Option<Vec<u8>>, //Used by Read impl for storing partial features
-    errors: Option<BufWriter<File>>
+    header: bool, //Used by Read impl to track if the COPY header has been written
+    trailer: bool, //Used by Read impl to track if the COPY trailer has been written
+    errors: ErrorSink,
+    progress: Progress
 }
 
 impl PolyStream {
-    pub fn new(input: GeoStream, errors: Option<String>) -> Self {
-        PolyStream {
+    pub fn new(input: GeoStream, crs: Crs, errors: ErrorSink, progress: Progress) -> Result<Self, Error> {
+        let keep = errors.is_enabled();
+        let policy = errors.get_policy();
+
+        PolyStream::build(Box::new(input.lines().map(move |line| {
+            let (line, raw) = line?;
+
+            Ok(rejection::build(&Origin::line(line), policy.parse(raw)?, keep, |feat| Polygon::new(crs.feature(feat)?)))
+        })), errors, progress)
+    }
+
+    ///
+    /// Stream polygons from a GeoPackage if the input has a .gpkg extension, otherwise GeoJSON
+    ///
+    pub fn open(input: Option<String>, crs: Crs, errors: ErrorSink, progress: Progress) -> Result<Self, Error> {
+        match input.as_ref().map_or(false, |input| gpkg::is_gpkg(input)) {
+            true => {
+                let input = GpkgStream::new(input, gpkg::POLYGONS)?;
+                let crs = input.crs(&crs)?;
+
+                PolyStream::labelled(input, crs, errors, progress)
+            },
+            false => PolyStream::new(GeoStream::new(input)?, crs, errors, progress)
+        }
+    }
+
+    ///
+    /// Stream polygons from a GeoPackage input, rejecting
+    /// invalid features with their origin in the input
+    ///
+    pub fn labelled<S>(input: S, crs: Crs, errors: ErrorSink, progress: Progress) -> Result<Self, Error>
+    where
+        S: Iterator<Item = Result<Labelled, Error>> + Send + 'static
+    {
+        let keep = errors.is_enabled();
+        let policy = errors.get_policy();
+
+        PolyStream::build(Box::new(input.map(move |labelled| labelled.and_then(|(origin, feat)| {
+            Ok(rejection::build(&origin, policy.check(feat)?, keep, |feat| Polygon::new(crs.feature(feat)?)))
+        }))), errors, progress)
+    }
+
+    fn build(input: Box<dyn Iterator<Item = Built<Polygon>> + Send>, errors: ErrorSink, progress: Progress) -> Result<Self, Error> {
+        Ok(PolyStream {
             input: input,
             buffer: None,
-            errors: match errors {
-                None => None,
-                Some(path) => Some(BufWriter::new(File::create(path).unwrap()))
-            }
-        }
+            header: false,
+            trailer: false,
+            errors: errors.reset(),
+            progress: progress
+        })
     }
 }
 
@@ -34,12 +77,22 @@ impl std::io::Read for PolyStream {
             if self.buffer.is_some() {
                 write = self.buffer.take().unwrap();
             } else {
-                let feat = match self.next() {
-                    Some(feat) => feat.to_tsv(),
-                    None => String::from("")
+                let mut bytes = if !self.header {
+                    self.header = true;
+                    binary::HEADER.to_vec()
+                } else {
+                    match self.next() {
+                        Some(Ok(feat)) => feat.to_copy()?,
+                        Some(Err(err)) => { return Err(err.into()); },
+                        None => if !self.trailer {
+                            self.trailer = true;
+                            binary::TRAILER.to_vec()
+                        } else {
+                            Vec::new()
+                        }
+                    }
                 };
 
-                let mut bytes = feat.into_bytes();
                 if bytes.len() == 0 {
                     end = true;
                 } else {
@@ -65,28 +118,30 @@ impl std::io::Read for PolyStream {
 }
 
 impl Iterator for PolyStream {
-    type Item = Polygon;
+    type Item = Result<Polygon, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut next: Result<Polygon, String> = Err(String::from(""));
-
-        while next.is_err() {
-            next = match self.input.next() {
-                Some(potential) => match Polygon::new(potential) {
-                    Ok(potential) => Ok(potential),
-                    Err(err) => match self.errors {
-                        None => Err(err),
-                        Some(ref mut file) => {
-                            file.write(format!("{}\n", err).as_bytes()).unwrap();
-
-                            Err(err)
-                        }
-                    }
-                },
+        loop {
+            let built = match self.input.next() {
+                Some(Ok(built)) => built,
+                Some(Err(err)) => { return Some(Err(err)); },
                 None => { return None; }
             };
-        }
 
-        Some(next.unwrap())
+            match built {
+                Ok(feat) => {
+                    self.progress.feature();
+
+                    return Some(Ok(feat));
+                },
+                Err(err) => {
+                    self.progress.error();
+
+                    if let Err(err) = self.errors.write(&err) {
+                        return Some(Err(err));
+                    }
+                }
+            };
+        }
     }
 }
@@ -0,0 +1,351 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{Error, stream::geo::GeoStream};
+
+///
+/// Where within its input a feature was read from
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Origin {
+    /// Line number within a line delimited GeoJSON, FeatureCollection or CSV input
+    pub line: Option<u64>,
+
+    /// Identifier of the feature within an input without lines, ie: an OSM node or GeoPackage fid
+    pub id: Option<serde_json::Value>
+}
+
+impl Origin {
+    pub fn line(line: u64) -> Self {
+        Origin {
+            line: Some(line),
+            id: None
+        }
+    }
+
+    pub fn id(id: impl Into<serde_json::Value>) -> Self {
+        Origin {
+            line: None,
+            id: Some(id.into())
+        }
+    }
+}
+
+///
+/// A feature rejected by an input stream, written to the error sink as a single line of JSON
+///
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub line: Option<u64>,
+    pub id: Option<serde_json::Value>,
+
+    /// EPARSE if the feature could not be read, EVALIDATION if it was read but is invalid,
+    /// or REPAIRED if the feature was imported after its geometry was repaired
+    pub code: &'static str,
+
+    pub message: String,
+
+    /// The rejected feature, if it could be read
+    pub feature: Option<serde_json::Value>
+}
+
+impl Rejection {
+    pub fn new(origin: &Origin, code: &'static str, message: String) -> Self {
+        Rejection {
+            line: origin.line,
+            id: origin.id.clone(),
+            code: code,
+            message: message,
+            feature: None
+        }
+    }
+}
+
+///
+/// How lines of GeoJSON input, or features of a CSV, OSM PBF or GeoPackage input,
+/// that cannot be parsed are handled
+///
+/// Strict stops the stream at the first invalid line, Skip rejects every invalid
+/// line & Limit rejects invalid lines until more than the given number are read
+/// from a single input
+///
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    Strict,
+    Skip,
+    Limit(u64)
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy::Strict
+    }
+}
+
+impl Policy {
+    ///
+    /// Parse a line of GeoJSON input, returning an invalid line as an error to be
+    /// rejected unless the policy is strict, in which case it is a fatal error
+    ///
+    pub fn parse(&self, line: String) -> Result<Result<geojson::GeoJson, String>, Error> {
+        match GeoStream::parse(line) {
+            Ok(geojson) => Ok(Ok(geojson)),
+            Err(Error::Parse(message)) => self.check(Err(message)),
+            Err(err) => Err(err)
+        }
+    }
+
+    ///
+    /// Check a feature read from a labelled input, returning a feature that could not
+    /// be read as an error to be rejected unless the policy is strict
+    ///
+    pub fn check(&self, feat: Result<geojson::GeoJson, String>) -> Result<Result<geojson::GeoJson, String>, Error> {
+        match feat {
+            Err(message) if *self == Policy::Strict => Err(Error::Parse(message)),
+            feat => Ok(feat)
+        }
+    }
+}
+
+///
+/// Build a feature, rejecting it with EPARSE if it could not be read from the input
+/// or EVALIDATION if it is invalid
+///
+/// The rejected feature is only retained when `keep` is set, so that features are only
+/// copied ahead of being built when rejections are being recorded
+///
+pub fn build<T>(origin: &Origin, feat: Result<geojson::GeoJson, String>, keep: bool, build: impl FnOnce(geojson::GeoJson) -> Result<T, String>) -> Result<T, Rejection> {
+    let feat = match feat {
+        Ok(feat) => feat,
+        Err(message) => { return Err(Rejection::new(origin, "EPARSE", message)); }
+    };
+
+    let id = match feat {
+        geojson::GeoJson::Feature(ref feat) => match feat.id {
+            Some(geojson::feature::Id::Number(ref id)) => Some(serde_json::Value::Number(id.clone())),
+            Some(geojson::feature::Id::String(ref id)) => Some(serde_json::Value::String(id.clone())),
+            None => None
+        },
+        _ => None
+    };
+
+    let copy = match keep {
+        true => Some(feat.clone()),
+        false => None
+    };
+
+    build(feat).map_err(|message| {
+        let mut rejection = Rejection::new(origin, "EVALIDATION", message);
+
+        if id.is_some() {
+            rejection.id = id;
+        }
+
+        rejection.feature = copy.and_then(|copy| serde_json::to_value(copy).ok());
+
+        rejection
+    })
+}
+
+///
+/// Destination for rejected features, shared between the streams of a mode
+/// so that every rejection is written to a single file
+///
+/// ErrorSink::none() discards rejections so that streams can report unconditionally
+///
+/// The sink also holds the policy for invalid lines of input, counting the
+/// lines that could not be parsed to enforce the limit of Policy::Limit. Each
+/// stream counts its own input, see ErrorSink::reset
+///
+#[derive(Clone)]
+pub struct ErrorSink {
+    output: Option<Arc<Mutex<BufWriter<File>>>>,
+    policy: Policy,
+    invalid: Arc<AtomicU64>
+}
+
+impl ErrorSink {
+    pub fn new(path: Option<String>) -> Result<Self, Error> {
+        Ok(ErrorSink {
+            output: match path {
+                None => None,
+                Some(path) => Some(Arc::new(Mutex::new(BufWriter::new(File::create(path)?))))
+            },
+            policy: Policy::default(),
+            invalid: Arc::new(AtomicU64::new(0))
+        })
+    }
+
+    pub fn none() -> Self {
+        ErrorSink {
+            output: None,
+            policy: Policy::default(),
+            invalid: Arc::new(AtomicU64::new(0))
+        }
+    }
+
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    ///
+    /// Return a sink writing to the same output with a count of invalid lines of its
+    /// own, so that the limit of the policy applies to each input rather than to
+    /// every input of a mode
+    ///
+    pub fn reset(mut self) -> Self {
+        self.invalid = Arc::new(AtomicU64::new(0));
+        self
+    }
+
+    pub fn get_policy(&self) -> Policy {
+        self.policy
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.output.is_some()
+    }
+
+    ///
+    /// Write a rejection to the sink, returning an error once more invalid
+    /// lines have been rejected than the limit of the policy allows
+    ///
+    pub fn write(&self, rejection: &Rejection) -> Result<(), Error> {
+        self.output(rejection)?;
+
+        if rejection.code == "EPARSE" {
+            let invalid = self.invalid.fetch_add(1, Ordering::SeqCst) + 1;
+
+            if let Policy::Limit(limit) = self.policy {
+                if invalid > limit {
+                    return Err(Error::Parse(format!("More than {} invalid lines of input", limit)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn output(&self, rejection: &Rejection) -> Result<(), Error> {
+        let output = match self.output {
+            Some(ref output) => output,
+            None => { return Ok(()); }
+        };
+
+        let mut output = match output.lock() {
+            Ok(output) => output,
+            Err(poisoned) => poisoned.into_inner()
+        };
+
+        let mut line = serde_json::to_vec(rejection)?;
+        line.push(b'\n');
+
+        output.write_all(&line)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<(), Error> {
+        if let Some(ref output) = self.output {
+            match output.lock() {
+                Ok(mut output) => output.flush()?,
+                Err(poisoned) => poisoned.into_inner().flush()?
+            };
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(id: Option<i64>) -> geojson::GeoJson {
+        geojson::GeoJson::Feature(geojson::Feature {
+            bbox: None,
+            geometry: None,
+            id: id.map(|id| geojson::feature::Id::Number(serde_json::Number::from(id))),
+            properties: Some(serde_json::Map::new()),
+            foreign_members: None
+        })
+    }
+
+    #[test]
+    fn test_build() {
+        let built: Result<i64, Rejection> = build(&Origin::line(4), Ok(feature(Some(7))), true, |_| Err(String::from("Invalid")));
+
+        assert_eq!(serde_json::to_string(&built.unwrap_err()).unwrap(), r#"{"line":4,"id":7,"code":"EVALIDATION","message":"Invalid","feature":{"geometry":null,"id":7,"properties":{},"type":"Feature"}}"#);
+
+        let built: Result<i64, Rejection> = build(&Origin::id("way/2"), Ok(feature(None)), false, |_| Err(String::from("Invalid")));
+
+        assert_eq!(serde_json::to_string(&built.unwrap_err()).unwrap(), r#"{"line":null,"id":"way/2","code":"EVALIDATION","message":"Invalid","feature":null}"#);
+
+        let built: Result<i64, Rejection> = build(&Origin::line(2), Err(String::from("Invalid coordinates")), true, |_| Ok(1));
+
+        assert_eq!(built.unwrap_err(), Rejection::new(&Origin::line(2), "EPARSE", String::from("Invalid coordinates")));
+
+        assert_eq!(build(&Origin::line(1), Ok(feature(None)), true, |_| Ok(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_policy() {
+        assert_eq!(Policy::default(), Policy::Strict);
+
+        assert!(Policy::Strict.parse(String::from("{ \"type\": \"Feature\"")).is_err());
+        assert!(Policy::Skip.parse(String::from("{ \"type\": \"Feature\"")).unwrap().is_err());
+        assert!(Policy::Limit(1).parse(String::from("{ \"type\": \"Feature\"")).unwrap().is_err());
+        assert!(Policy::Strict.parse(String::from("{ \"type\": \"Feature\", \"properties\": {}, \"geometry\": null }")).unwrap().is_ok());
+
+        assert_eq!(Policy::Strict.check(Err(String::from("Invalid row"))).unwrap_err().to_string(), "Parse Error: Invalid row");
+        assert_eq!(Policy::Skip.check(Err(String::from("Invalid row"))).unwrap(), Err(String::from("Invalid row")));
+        assert!(Policy::Limit(1).check(Err(String::from("Invalid row"))).unwrap().is_err());
+
+        assert_eq!(serde_json::from_str::<Policy>("\"skip\"").unwrap(), Policy::Skip);
+        assert_eq!(serde_json::from_str::<Policy>("{ \"limit\": 10 }").unwrap(), Policy::Limit(10));
+    }
+
+    #[test]
+    fn test_error_sink_limit() {
+        let rejection = Rejection::new(&Origin::line(1), "EPARSE", String::from("Invalid GeoJSON"));
+
+        let sink = ErrorSink::none().policy(Policy::Limit(2));
+        assert!(sink.write(&Rejection::new(&Origin::line(1), "EVALIDATION", String::from("Invalid number"))).is_ok());
+        assert!(sink.write(&rejection).is_ok());
+        assert!(sink.clone().write(&rejection).is_ok());
+        assert_eq!(sink.write(&rejection).unwrap_err().to_string(), "Parse Error: More than 2 invalid lines of input");
+
+        let reset = sink.clone().reset();
+        assert!(reset.write(&rejection).is_ok());
+        assert!(reset.write(&rejection).is_ok());
+        assert!(reset.write(&rejection).is_err());
+
+        let sink = ErrorSink::none().policy(Policy::Skip);
+        for _ in 0..10 {
+            assert!(sink.write(&rejection).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_error_sink() {
+        let path = std::env::temp_dir().join("pt2itp-error-sink");
+
+        let sink = ErrorSink::new(Some(path.to_str().unwrap().to_string())).unwrap();
+        assert!(sink.is_enabled());
+
+        sink.clone().write(&Rejection::new(&Origin::line(1), "EPARSE", String::from("Invalid GeoJSON"))).unwrap();
+        sink.write(&Rejection::new(&Origin::id(3), "EVALIDATION", String::from("Invalid number"))).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"line\":1,\"id\":null,\"code\":\"EPARSE\",\"message\":\"Invalid GeoJSON\",\"feature\":null}\n{\"line\":null,\"id\":3,\"code\":\"EVALIDATION\",\"message\":\"Invalid number\",\"feature\":null}\n");
+
+        std::fs::remove_file(path).unwrap();
+
+        assert!(!ErrorSink::none().is_enabled());
+        assert!(ErrorSink::none().write(&Rejection::new(&Origin::line(1), "EPARSE", String::new())).is_ok());
+    }
+}
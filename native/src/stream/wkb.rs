@@ -0,0 +1,270 @@
+///
+/// Read a Well Known Binary geometry as a GeoJSON geometry value
+///
+/// ISO & EWKB Z/M variants are accepted but only the X/Y ordinates are kept.
+/// An empty point, encoded with NaN ordinates, is returned as None
+///
+pub fn read(wkb: &[u8]) -> Result<Option<geojson::Value>, String> {
+    let mut reader = Reader {
+        wkb: wkb,
+        pos: 0,
+        little: true
+    };
+
+    match reader.geometry()? {
+        geojson::Value::Point(ref pt) if pt.iter().any(|ord| ord.is_nan()) => Ok(None),
+        geom => Ok(Some(geom))
+    }
+}
+
+///
+/// Write a GeoJSON geometry value as little endian, 2D Well Known Binary
+///
+pub fn write(geom: &geojson::Value) -> Vec<u8> {
+    let mut wkb = Vec::new();
+
+    geometry(&mut wkb, geom);
+
+    wkb
+}
+
+struct Reader<'a> {
+    wkb: &'a [u8],
+    pos: usize,
+    little: bool
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.pos + len > self.wkb.len() {
+            return Err(String::from("Invalid WKB: Unexpected end of geometry"));
+        }
+
+        let bytes = &self.wkb[self.pos..self.pos + len];
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+
+        Ok(match self.little {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+
+        Ok(match self.little {
+            true => f64::from_le_bytes(bytes),
+            false => f64::from_be_bytes(bytes)
+        })
+    }
+
+    fn coord(&mut self, dims: usize) -> Result<Vec<f64>, String> {
+        let coord = vec![self.f64()?, self.f64()?];
+
+        for _ in 2..dims {
+            self.f64()?;
+        }
+
+        Ok(coord)
+    }
+
+    fn coords(&mut self, dims: usize) -> Result<Vec<Vec<f64>>, String> {
+        let len = self.u32()?;
+
+        (0..len).map(|_| self.coord(dims)).collect()
+    }
+
+    fn rings(&mut self, dims: usize) -> Result<Vec<Vec<Vec<f64>>>, String> {
+        let len = self.u32()?;
+
+        (0..len).map(|_| self.coords(dims)).collect()
+    }
+
+    fn geometry(&mut self) -> Result<geojson::Value, String> {
+        self.little = match self.bytes(1)?[0] {
+            0 => false,
+            1 => true,
+            order => { return Err(format!("Invalid WKB: Unknown byte order {}", order)); }
+        };
+
+        let kind = self.u32()?;
+
+        // EWKB flags the presence of Z, M & SRID in the high bits
+        let mut z = kind & 0x8000_0000 != 0;
+        let mut m = kind & 0x4000_0000 != 0;
+
+        if kind & 0x2000_0000 != 0 {
+            self.u32()?;
+        }
+
+        // ISO WKB instead offsets the type by 1000 for Z, 2000 for M & 3000 for ZM
+        let base = kind & 0x0FFF_FFFF;
+        z = z || base / 1000 == 1 || base / 1000 == 3;
+        m = m || base / 1000 == 2 || base / 1000 == 3;
+
+        let dims = 2 + z as usize + m as usize;
+
+        match base % 1000 {
+            1 => Ok(geojson::Value::Point(self.coord(dims)?)),
+            2 => Ok(geojson::Value::LineString(self.coords(dims)?)),
+            3 => Ok(geojson::Value::Polygon(self.rings(dims)?)),
+            4 => {
+                let len = self.u32()?;
+
+                Ok(geojson::Value::MultiPoint((0..len).map(|_| match self.geometry()? {
+                    geojson::Value::Point(pt) => Ok(pt),
+                    _ => Err(String::from("Invalid WKB: MultiPoint must contain Points"))
+                }).collect::<Result<_, String>>()?))
+            },
+            5 => {
+                let len = self.u32()?;
+
+                Ok(geojson::Value::MultiLineString((0..len).map(|_| match self.geometry()? {
+                    geojson::Value::LineString(ln) => Ok(ln),
+                    _ => Err(String::from("Invalid WKB: MultiLineString must contain LineStrings"))
+                }).collect::<Result<_, String>>()?))
+            },
+            6 => {
+                let len = self.u32()?;
+
+                Ok(geojson::Value::MultiPolygon((0..len).map(|_| match self.geometry()? {
+                    geojson::Value::Polygon(py) => Ok(py),
+                    _ => Err(String::from("Invalid WKB: MultiPolygon must contain Polygons"))
+                }).collect::<Result<_, String>>()?))
+            },
+            7 => {
+                let len = self.u32()?;
+
+                Ok(geojson::Value::GeometryCollection((0..len).map(|_| {
+                    Ok(geojson::Geometry::new(self.geometry()?))
+                }).collect::<Result<_, String>>()?))
+            },
+            _ => Err(format!("Invalid WKB: Unsupported geometry type {}", kind))
+        }
+    }
+}
+
+fn coord(wkb: &mut Vec<u8>, coord: &[f64]) {
+    wkb.extend_from_slice(&coord.get(0).cloned().unwrap_or(std::f64::NAN).to_le_bytes());
+    wkb.extend_from_slice(&coord.get(1).cloned().unwrap_or(std::f64::NAN).to_le_bytes());
+}
+
+fn coords(wkb: &mut Vec<u8>, coords: &[Vec<f64>]) {
+    wkb.extend_from_slice(&(coords.len() as u32).to_le_bytes());
+
+    for pt in coords {
+        coord(wkb, pt);
+    }
+}
+
+fn rings(wkb: &mut Vec<u8>, rings: &[Vec<Vec<f64>>]) {
+    wkb.extend_from_slice(&(rings.len() as u32).to_le_bytes());
+
+    for ring in rings {
+        coords(wkb, ring);
+    }
+}
+
+fn header(wkb: &mut Vec<u8>, kind: u32) {
+    wkb.push(1);
+    wkb.extend_from_slice(&kind.to_le_bytes());
+}
+
+fn geometry(wkb: &mut Vec<u8>, geom: &geojson::Value) {
+    match geom {
+        geojson::Value::Point(pt) => {
+            header(wkb, 1);
+            coord(wkb, pt);
+        },
+        geojson::Value::LineString(ln) => {
+            header(wkb, 2);
+            coords(wkb, ln);
+        },
+        geojson::Value::Polygon(py) => {
+            header(wkb, 3);
+            rings(wkb, py);
+        },
+        geojson::Value::MultiPoint(mpt) => {
+            header(wkb, 4);
+            wkb.extend_from_slice(&(mpt.len() as u32).to_le_bytes());
+
+            for pt in mpt {
+                geometry(wkb, &geojson::Value::Point(pt.clone()));
+            }
+        },
+        geojson::Value::MultiLineString(mln) => {
+            header(wkb, 5);
+            wkb.extend_from_slice(&(mln.len() as u32).to_le_bytes());
+
+            for ln in mln {
+                header(wkb, 2);
+                coords(wkb, ln);
+            }
+        },
+        geojson::Value::MultiPolygon(mpy) => {
+            header(wkb, 6);
+            wkb.extend_from_slice(&(mpy.len() as u32).to_le_bytes());
+
+            for py in mpy {
+                header(wkb, 3);
+                rings(wkb, py);
+            }
+        },
+        geojson::Value::GeometryCollection(geoms) => {
+            header(wkb, 7);
+            wkb.extend_from_slice(&(geoms.len() as u32).to_le_bytes());
+
+            for geom in geoms {
+                geometry(wkb, &geom.value);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wkb_roundtrip() {
+        for geom in vec![
+            geojson::Value::Point(vec![1.0, 2.0]),
+            geojson::Value::LineString(vec![vec![1.0, 2.0], vec![3.0, 4.0]]),
+            geojson::Value::Polygon(vec![vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 0.0]]]),
+            geojson::Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]]),
+            geojson::Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]]]),
+            geojson::Value::MultiPolygon(vec![vec![vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 0.0]]]]),
+            geojson::Value::GeometryCollection(vec![
+                geojson::Geometry::new(geojson::Value::MultiPoint(vec![vec![1.0, 2.0]])),
+                geojson::Geometry::new(geojson::Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]]]))
+            ])
+        ] {
+            assert_eq!(read(&write(&geom)).unwrap(), Some(geom));
+        }
+    }
+
+    #[test]
+    fn test_wkb_read() {
+        // Big endian ISO PointZ
+        let mut wkb = vec![0, 0, 0, 0x03, 0xE9];
+        wkb.extend_from_slice(&1.0f64.to_be_bytes());
+        wkb.extend_from_slice(&2.0f64.to_be_bytes());
+        wkb.extend_from_slice(&3.0f64.to_be_bytes());
+
+        assert_eq!(read(&wkb).unwrap(), Some(geojson::Value::Point(vec![1.0, 2.0])));
+
+        // Empty Point
+        assert_eq!(read(&write(&geojson::Value::Point(vec![std::f64::NAN, std::f64::NAN]))).unwrap(), None);
+
+        assert_eq!(read(&[1, 1, 0, 0]).unwrap_err(), "Invalid WKB: Unexpected end of geometry");
+        assert_eq!(read(&[1, 99, 0, 0, 0]).unwrap_err(), "Invalid WKB: Unsupported geometry type 99");
+    }
+}
@@ -1,11 +1,10 @@
 use postgis::ewkb::AsEwkbPoint;
-use postgis::ewkb::EwkbWrite;
-use regex::{Regex, RegexSet};
 
-use crate::{Context, Names, Name, hecate};
+use crate::{Context, Error, HouseNumber, Unit, Names, Name, hecate, pg::binary::Row};
+use super::{Grammar, place};
 
 /// A representation of a single Address
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Address {
     /// An optional identifier for the address
     pub id: Option<i64>,
@@ -13,7 +12,16 @@ pub struct Address {
     pub version: i64,
 
     /// The address number, can be numeric or semi-numeric (100 vs 100a)
-    pub number: String,
+    pub number: HouseNumber,
+
+    /// The apartment, suite or other unit at the address, if any
+    pub unit: Option<Unit>,
+
+    /// Normalized postcode of the address, if any
+    pub postcode: Option<String>,
+
+    /// Locality, ie: city or town, of the address, if any
+    pub locality: Option<String>,
 
     /// Vector of all street name synonyms
     pub names: Names,
@@ -46,7 +54,24 @@ impl Address {
             None => { return Err(String::from("Feature has no properties")); }
         };
 
-        let number = get_number(&mut props)?;
+        // Numbers are parsed with the grammar of the country, after splitting any trailing unit
+        let grammar = Grammar::new(&context);
+        let (number, unit) = Unit::split(&get_number(&mut props)?, grammar.hyphenated_units());
+        let number = grammar.parse(&number)?;
+
+        // A unit property takes precedence over a unit within the number
+        let unit = match get_unit(&mut props)? {
+            Some(unit) => Some(unit),
+            None => unit
+        };
+
+        if let Some(ref unit) = unit {
+            props.insert(String::from("unit"), serde_json::Value::String(unit.to_string()));
+        }
+
+        // Postcode & locality are read from the properties named by the context
+        let postcode = place::postcode(props.get(&context.postcode))?;
+        let locality = place::locality(props.get(&context.locality))?;
 
         let version = match feat.foreign_members {
             Some(mut props) => get_version(&mut props)?,
@@ -90,12 +115,15 @@ impl Address {
 
         names.set_source(String::from("address"));
 
-        let mut addr = Address {
+        Ok(Address {
             id: match feat.id {
                 Some(geojson::feature::Id::Number(id)) => id.as_i64(),
                 _ => None
             },
             number: number,
+            unit: unit,
+            postcode: postcode,
+            locality: locality,
             version: version,
             names: names,
             output: output,
@@ -103,17 +131,16 @@ impl Address {
             interpolate: interpolate,
             props: props,
             geom: geom
-        };
-
-        addr.std()?;
-
-        Ok(addr)
+        })
     }
 
     ///
     /// Construct an address instance via a Row JSON Value
     ///
-    pub fn from_value(value: serde_json::Value) -> Result<Self, String> {
+    /// The number is parsed with the grammar the address was imported with, as the
+    /// normalized number alone is ambiguous, ie: 37-14 is a range unless it is in Queens
+    ///
+    pub fn from_value(value: serde_json::Value, grammar: &Grammar) -> Result<Self, String> {
         let mut value = match value {
             serde_json::Value::Object(obj) => obj,
             _ => { return Err(String::from("Address::from_row value must be JSON Object")); }
@@ -162,7 +189,10 @@ impl Address {
 
         Ok(Address {
             id: get_id(&mut value)?,
-            number: get_number(&mut value)?,
+            number: grammar.parse(&get_number(&mut value)?)?,
+            unit: get_unit(&mut value)?,
+            postcode: place::postcode(value.get(&String::from("postcode")))?,
+            locality: place::locality(value.get(&String::from("locality")))?,
             version: get_version(&mut value)?,
             names: names,
             output: get_output(&mut value)?,
@@ -173,59 +203,37 @@ impl Address {
         })
     }
 
-    pub fn std(&mut self) -> Result<(), String> {
-        self.number = self.number.to_lowercase();
-
-        // Remove 1/2 Numbers from addresses as they are not currently supported
-        lazy_static! {
-            static ref HALF: Regex = Regex::new(r"\s1/2$").unwrap();
-            static ref UNIT: Regex = Regex::new(r"^(?P<num>\d+)\s(?P<unit>[a-z])$").unwrap();
-            static ref SUPPORTED: RegexSet = RegexSet::new(&[
-                r"^\d+[a-z]?$",
-                r"^(\d+)-(\d+)[a-z]?$",
-                r"^(\d+)([nsew])(\d+)[a-z]?$",
-                r"^([nesw])(\d+)([nesw]\d+)?$",
-                r"^\d+(к\d+)?(с\d+)?$"
-            ]).unwrap();
-        }
-
-        self.number = HALF.replace(self.number.as_str(), "").to_string();
-
-        // Transform '123 B' = '123B' so it is supported
-        self.number = UNIT.replace(self.number.as_str(), "$num$unit").to_string();
-
-        if !SUPPORTED.is_match(self.number.as_str()) {
-            return Err(String::from("Number is not a supported address/unit type"));
-        }
-
-        if self.number.len() > 10 {
-            return Err(String::from("Number should not exceed 10 chars"));
-        }
-
-        Ok(())
-    }
-
     ///
-    ///Return a PG Copyable String of the feature
+    /// Return a PG binary COPY row of the feature
     ///
-    ///name, number, source, props, geom
+    /// id, version, names, number, unit, postcode, locality, source, output, props, geom
     ///
-    pub fn to_tsv(self) -> String {
-        let geom = postgis::ewkb::Point::new(self.geom[0], self.geom[1], Some(4326)).as_ewkb().to_hex_ewkb();
+    pub fn to_copy(self) -> Result<Vec<u8>, Error> {
+        let geom = postgis::ewkb::Point::new(self.geom[0], self.geom[1], Some(4326));
+
+        let mut row = Row::new(11);
+        row.int8(self.id);
+        row.int8(Some(self.version));
+        row.jsonb(&self.names.names)?;
+        row.text(&self.number.to_string());
+        match self.unit {
+            Some(unit) => row.text(&unit.to_string()),
+            None => row.null()
+        };
+        match self.postcode {
+            Some(ref postcode) => row.text(postcode),
+            None => row.null()
+        };
+        match self.locality {
+            Some(ref locality) => row.text(locality),
+            None => row.null()
+        };
+        row.text(&self.source);
+        row.boolean(self.output);
+        row.jsonb(&self.props)?;
+        row.geometry(&geom.as_ewkb())?;
 
-        format!("{id}\t{version}\t{names}\t{number}\t{source}\t{output}\t{props}\t{geom}\n",
-            id = match self.id {
-                None => String::from(""),
-                Some(id) => id.to_string()
-            },
-            version = self.version,
-            names = serde_json::to_string(&self.names.names).unwrap_or(String::from("")),
-            output = self.output,
-            number = self.number,
-            source = self.source,
-            props = serde_json::value::Value::from(self.props),
-            geom = geom
-        )
+        Ok(row.finish())
     }
 
 
@@ -258,7 +266,7 @@ impl Address {
 
         self.props.insert(String::from("source"), serde_json::value::Value::String(self.source));
         self.props.insert(String::from("names"), serde_json::to_value(self.names.names).unwrap());
-        self.props.insert(String::from("number"), serde_json::value::Value::String(self.number));
+        self.props.insert(String::from("number"), serde_json::value::Value::String(self.number.to_string()));
 
         geojson::Feature {
             id: match self.id {
@@ -302,6 +310,19 @@ fn get_number(map: &mut serde_json::Map<String, serde_json::Value>) -> Result<St
     }
 }
 
+fn get_unit(map: &mut serde_json::Map<String, serde_json::Value>) -> Result<Option<Unit>, String> {
+    match map.get(&String::from("unit")) {
+        Some(unit) => match unit.clone() {
+            serde_json::value::Value::Null => Ok(None),
+            serde_json::value::Value::Number(unit) => Ok(Some(unit.to_string().parse()?)),
+            serde_json::value::Value::String(ref unit) if unit.trim().is_empty() => Ok(None),
+            serde_json::value::Value::String(unit) => Ok(Some(unit.parse()?)),
+            _ => Err(String::from("Unit property must be String or Numeric"))
+        },
+        None => Ok(None)
+    }
+}
+
 fn get_version(map: &mut serde_json::Map<String, serde_json::Value>) -> Result<i64, String> {
     match map.remove(&String::from("version")) {
         Some(version) => match version.as_i64() {
@@ -341,3 +362,46 @@ fn get_interpolate(map: &mut serde_json::Map<String, serde_json::Value>) -> Resu
         None => Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_address_from_value() {
+        let row = json!({
+            "id": 3,
+            "version": 2,
+            "names": [{
+                "display": "31st St",
+                "priority": 0,
+                "source": "address",
+                "tokenized": "31st st",
+                "tokenless": "31st",
+                "freq": 1
+            }],
+            "number": "37-14",
+            "unit": null,
+            "postcode": "11101",
+            "locality": null,
+            "source": "test",
+            "output": true,
+            "props": { "street": "31st St" },
+            "geom": "{\"type\":\"Point\",\"coordinates\":[-73.9,40.7]}"
+        });
+
+        let addr = Address::from_value(row.clone(), &Grammar::UsNy).unwrap();
+        assert_eq!(addr.id, Some(3));
+        assert_eq!(addr.version, 2);
+        assert_eq!(addr.number, Grammar::UsNy.parse("37-14").unwrap());
+        assert_eq!((addr.number.blocks, addr.number.base, addr.number.range), (vec![37], 14, None));
+        assert_eq!(addr.postcode, Some(String::from("11101")));
+        assert_eq!(addr.geom, vec![-73.9, 40.7]);
+
+        let addr = Address::from_value(row, &Grammar::Us).unwrap();
+        assert_eq!((addr.number.blocks, addr.number.base, addr.number.range), (Vec::new(), 37, Some(14)));
+
+        assert!(Address::from_value(json!({ "names": [] }), &Grammar::Default).is_err());
+    }
+}
@@ -1,18 +1,33 @@
 use std::collections::HashMap;
 use crate::text::Tokens;
+use crate::crs::Crs;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct InputContext {
     pub country: Option<String>,
     pub region: Option<String>,
-    pub languages: Option<Vec<String>>
+    pub languages: Option<Vec<String>>,
+
+    /// Coordinate reference system of the input, EPSG:4326 if not given
+    pub crs: Option<Crs>,
+
+    /// Name of the property holding the postcode of a feature, postcode if not given
+    pub postcode: Option<String>,
+
+    /// Name of the property holding the locality of a feature, city if not given
+    pub locality: Option<String>
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Context {
     pub country: String,
     pub region: Option<String>,
-    pub tokens: Tokens
+    pub tokens: Tokens,
+    pub crs: Crs,
+
+    /// Names of the properties the postcode & locality of a feature are read from
+    pub postcode: String,
+    pub locality: String
 }
 
 impl From<InputContext> for Context {
@@ -24,7 +39,17 @@ impl From<InputContext> for Context {
             Some(languages) =>  Tokens::generate(languages)
         };
 
-        Context::new(country, region, tokens)
+        let mut context = Context::new(country, region, tokens);
+        context.crs = input.crs.unwrap_or_default();
+
+        if let Some(postcode) = input.postcode {
+            context.postcode = postcode;
+        }
+        if let Some(locality) = input.locality {
+            context.locality = locality;
+        }
+
+        context
     }
 }
 
@@ -37,7 +62,10 @@ impl Context {
                 None => None,
                 Some(region) => Some(region.to_uppercase())
             },
-            tokens: tokens
+            tokens: tokens,
+            crs: Crs::default(),
+            postcode: String::from("postcode"),
+            locality: String::from("city")
         }
     }
 
@@ -148,13 +176,19 @@ mod tests {
         assert_eq!(Context::new(String::from("us"), None, Tokens::new(HashMap::new())), Context {
             country: String::from("US"),
             region: None,
-            tokens: Tokens::new(HashMap::new())
+            tokens: Tokens::new(HashMap::new()),
+            crs: Crs::default(),
+            postcode: String::from("postcode"),
+            locality: String::from("city")
         });
 
         assert_eq!(Context::new(String::from("uS"), Some(String::from("wv")), Tokens::new(HashMap::new())), Context {
             country: String::from("US"),
             region: Some(String::from("WV")),
-            tokens: Tokens::new(HashMap::new())
+            tokens: Tokens::new(HashMap::new()),
+            crs: Crs::default(),
+            postcode: String::from("postcode"),
+            locality: String::from("city")
         });
 
         let cntx = Context::new(String::from("uS"), Some(String::from("wv")), Tokens::new(HashMap::new()));
@@ -163,4 +197,19 @@ mod tests {
 
         assert_eq!(cntx.region_name(), Some(String::from("West Virginia")));
     }
+
+    #[test]
+    fn context_input_test() {
+        let input: InputContext = serde_json::from_str(r#"{ "country": "us", "region": "dc", "crs": "EPSG:26985" }"#).unwrap();
+        assert_eq!(Context::from(input).crs, Crs::new("EPSG:26985").unwrap());
+
+        let input: InputContext = serde_json::from_str(r#"{ "country": "us" }"#).unwrap();
+        assert!(Context::from(input).crs.is_geographic());
+
+        assert!(serde_json::from_str::<InputContext>(r#"{ "crs": "EPSG:0" }"#).is_err());
+
+        let input: InputContext = serde_json::from_str(r#"{ "country": "us", "postcode": "zip" }"#).unwrap();
+        let context = Context::from(input);
+        assert_eq!((context.postcode.as_str(), context.locality.as_str()), ("zip", "city"));
+    }
 }
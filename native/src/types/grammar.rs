@@ -0,0 +1,552 @@
+use regex::{Captures, Regex};
+
+use crate::Context;
+use super::HouseNumber;
+
+const UNSUPPORTED: &str = "Number is not a supported address/unit type";
+
+///
+/// The house number grammar of a country, used to validate & normalize
+/// the numbers of its addresses
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grammar {
+    /// Accepts the normalized numbers of every grammar, used where the country has no grammar
+    Default,
+
+    /// United States, including grid (123n456) & directional (n123w456) numbers
+    Us,
+
+    /// New York, which also has hyphenated Queens numbers (37-14)
+    UsNy,
+
+    /// Russia, with building (корпус) & structure (строение) numbers (12к2с1)
+    Ru,
+
+    /// Czechia & Slovakia, with conscription & orientation numbers (846/1)
+    Cz,
+
+    /// Italy, with suffixes (12/A, 5 bis) & red commercial numbers (42r)
+    It,
+
+    /// Germany & Austria, with suffix ranges (12a-c)
+    De,
+
+    /// Netherlands, with additions (12-III)
+    Nl,
+
+    /// Japan, with block numbers (1-2-3 or 1丁目2番3号)
+    Jp
+}
+
+lazy_static! {
+    static ref FRACTION: Regex = Regex::new(r"^(?P<number>.*\S)\s(?P<num>[1-9])/(?P<den>[1-9])$").unwrap();
+
+    static ref STANDARD: Regex = Regex::new(r"^(?P<base>\d+)(-(?P<range>\d+))?\s?(?P<suffix>[a-z])?$").unwrap();
+    static ref GRID: Regex = Regex::new(r"^(?P<base>\d+)(?P<dir>[nsew])(?P<grid>\d+)(?P<suffix>[a-z])?$").unwrap();
+    static ref DIRECTIONAL: Regex = Regex::new(r"^(?P<prefix>[nsew])(?P<base>\d+)((?P<dir>[nsew])(?P<grid>\d+))?$").unwrap();
+    static ref QUEENS: Regex = Regex::new(r"^(?P<block>\d+)\s*-\s*(?P<base>\d{2})(?P<suffix>[a-z])?$").unwrap();
+    static ref PADDED: Regex = Regex::new(r"^(?P<block>\d+)-(?P<base>0\d)(?P<suffix>[a-z])?$").unwrap();
+
+    static ref RU: Regex = Regex::new(r"^(?P<base>\d+)\s*((лит|литера)\.?\s*)?(?P<suffix>[а-яёa-z])?\s*((к|корп|корпус)\.?\s*(?P<k>\d+))?\s*((с|стр|строение)\.?\s*(?P<s>\d+))?$").unwrap();
+    static ref CZ: Regex = Regex::new(r"^((?P<conscription>\d+)\s*/\s*)?(?P<base>\d+)\s*(?P<suffix>[a-z])?$").unwrap();
+    static ref IT: Regex = Regex::new(r"^(?P<base>\d+)\s*(/\s*)?(?P<suffix>bis|ter|quater|rosso|[a-z])?$").unwrap();
+    static ref DE: Regex = Regex::new(r"^(?P<base>\d+)\s*((-|bis)\s*(?P<range>\d+)\s*)?(?P<suffix>[a-z](\s*-\s*[a-z])?)?$").unwrap();
+    static ref NL: Regex = Regex::new(r"^(?P<base>\d+)\s*(?P<suffix>[a-z])?((\s*-\s*|\s+)(?P<addition>[a-z0-9]{1,4}))?$").unwrap();
+    static ref JP: Regex = Regex::new(r"^(?P<blocks>(\d+-){0,2})(?P<base>\d+)$").unwrap();
+
+    static ref BUILDING: Regex = Regex::new(r"^\d+(к\d+)?(с\d+)?$").unwrap();
+
+    static ref DEFAULT: Regex = Regex::new(r"^(?P<base>\d+)(-(?P<range>\d+))?\s?(?P<suffix>[a-z]-[a-z]|bis|ter|quater|[a-zа-яё])?$").unwrap();
+    static ref BLOCKS: Regex = Regex::new(r"^(?P<blocks>(\d+-){2})(?P<base>\d+)$").unwrap();
+    static ref CONSCRIPTION: Regex = Regex::new(r"^(?P<conscription>\d+)/(?P<base>\d+)(?P<suffix>[a-z])?$").unwrap();
+    static ref ADDITION: Regex = Regex::new(r"^(?P<base>\d+)(?P<suffix>[a-zа-яё])?(?P<addition>к\d+(с\d+)?|с\d+|-[a-z0-9]+)$").unwrap();
+}
+
+impl Grammar {
+    pub fn new(context: &Context) -> Self {
+        match context.country.as_str() {
+            "US" => match context.region.as_ref().map(String::as_str) {
+                Some("NY") => Grammar::UsNy,
+                _ => Grammar::Us
+            },
+            "RU" => Grammar::Ru,
+            "CZ" | "SK" => Grammar::Cz,
+            "IT" => Grammar::It,
+            "DE" | "AT" => Grammar::De,
+            "NL" => Grammar::Nl,
+            "JP" => Grammar::Jp,
+            _ => Grammar::Default
+        }
+    }
+
+    ///
+    /// Can a hyphenated number be a unit, ie: 10-4, or is the hyphen part of the number
+    ///
+    pub fn hyphenated_units(&self) -> bool {
+        match self {
+            Grammar::Default | Grammar::Us => true,
+            _ => false
+        }
+    }
+
+    pub fn parse(&self, number: &str) -> Result<HouseNumber, String> {
+        let number = number.trim().to_lowercase();
+
+        // Every grammar accepts a trailing fraction, ie: 12 1/2
+        let (number, fraction) = fraction(number)?;
+
+        let mut hn = match self {
+            Grammar::Default => default(&number),
+            Grammar::Us => us(&number),
+            Grammar::UsNy => match QUEENS.captures(&number) {
+                Some(caps) => queens(&caps),
+                None => us(&number)
+            },
+            Grammar::Ru => ru(&number),
+            Grammar::Cz => cz(&number),
+            Grammar::It => it(&number),
+            Grammar::De => de(&number),
+            Grammar::Nl => nl(&number),
+            Grammar::Jp => jp(&number)
+        }.or_else(|err| match baseline(&number) {
+            Some(hn) => hn,
+            None => Err(err)
+        })?;
+
+        if hn.to_string().chars().count() > 10 {
+            return Err(String::from("Number should not exceed 10 chars"));
+        }
+
+        hn.fraction = fraction;
+
+        Ok(hn)
+    }
+}
+
+///
+/// Split a trailing fraction from a number, ie: 123 1/2
+///
+fn fraction(number: String) -> Result<(String, Option<(u32, u32)>), String> {
+    let caps = match FRACTION.captures(&number) {
+        Some(caps) => caps,
+        None => { return Ok((number.clone(), None)); }
+    };
+
+    let num: u32 = caps["num"].parse().unwrap();
+    let den: u32 = caps["den"].parse().unwrap();
+
+    if num >= den {
+        return Err(String::from(UNSUPPORTED));
+    }
+
+    Ok((caps["number"].to_string(), Some((num, den))))
+}
+
+fn int(value: &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(value) => Ok(value),
+        Err(_) => Err(String::from(UNSUPPORTED))
+    }
+}
+
+fn group(caps: &Captures, name: &str) -> Option<String> {
+    caps.name(name).map(|value| value.as_str().to_string())
+}
+
+fn direction(value: &str) -> char {
+    value.chars().next().unwrap()
+}
+
+fn standard(caps: &Captures) -> Result<HouseNumber, String> {
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+    hn.range = match caps.name("range") {
+        Some(range) => Some(int(range.as_str())?),
+        None => None
+    };
+    hn.suffix = group(caps, "suffix");
+
+    Ok(hn)
+}
+
+fn grid(number: &str) -> Option<Result<HouseNumber, String>> {
+    if let Some(caps) = GRID.captures(number) {
+        return Some(int(&caps["base"]).and_then(|base| {
+            let mut hn = HouseNumber::new(base);
+            hn.grid = Some((direction(&caps["dir"]), int(&caps["grid"])?));
+            hn.suffix = group(&caps, "suffix");
+            Ok(hn)
+        }));
+    }
+
+    DIRECTIONAL.captures(number).map(|caps| {
+        let mut hn = HouseNumber::new(int(&caps["base"])?);
+        hn.direction = Some(direction(&caps["prefix"]));
+        hn.grid = match (caps.name("dir"), caps.name("grid")) {
+            (Some(axis), Some(grid)) => Some((direction(axis.as_str()), int(grid.as_str())?)),
+            _ => None
+        };
+        Ok(hn)
+    })
+}
+
+///
+/// A hyphenated Queens number, where the block number is followed by a zero padded
+/// 2 digit number, ie: 104-01
+///
+fn queens(caps: &Captures) -> Result<HouseNumber, String> {
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+    hn.blocks = vec![int(&caps["block"])?];
+    hn.width = 2;
+    hn.suffix = group(caps, "suffix");
+
+    Ok(hn)
+}
+
+fn us(number: &str) -> Result<HouseNumber, String> {
+    if let Some(caps) = STANDARD.captures(number) {
+        return standard(&caps);
+    }
+
+    grid(number).unwrap_or_else(|| Err(String::from(UNSUPPORTED)))
+}
+
+fn ru(number: &str) -> Result<HouseNumber, String> {
+    let caps = match RU.captures(number) {
+        Some(caps) => caps,
+        None => { return Err(String::from(UNSUPPORTED)); }
+    };
+
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+    hn.suffix = group(&caps, "suffix");
+
+    let mut addition = String::new();
+    if let Some(k) = caps.name("k") {
+        addition.push_str(&format!("к{}", int(k.as_str())?));
+    }
+    if let Some(s) = caps.name("s") {
+        addition.push_str(&format!("с{}", int(s.as_str())?));
+    }
+
+    if !addition.is_empty() {
+        hn.addition = Some(addition);
+    }
+
+    Ok(hn)
+}
+
+fn cz(number: &str) -> Result<HouseNumber, String> {
+    let caps = match CZ.captures(number) {
+        Some(caps) => caps,
+        None => { return Err(String::from(UNSUPPORTED)); }
+    };
+
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+    hn.conscription = match caps.name("conscription") {
+        Some(conscription) => Some(int(conscription.as_str())?),
+        None => None
+    };
+    hn.suffix = group(&caps, "suffix");
+
+    Ok(hn)
+}
+
+fn it(number: &str) -> Result<HouseNumber, String> {
+    let caps = match IT.captures(number) {
+        Some(caps) => caps,
+        None => { return Err(String::from(UNSUPPORTED)); }
+    };
+
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+
+    // Red (rosso) numbers are commercial premises, conventionally written as 42r
+    hn.suffix = group(&caps, "suffix").map(|suffix| match suffix.as_str() {
+        "rosso" => String::from("r"),
+        _ => suffix
+    });
+
+    Ok(hn)
+}
+
+fn de(number: &str) -> Result<HouseNumber, String> {
+    let caps = match DE.captures(number) {
+        Some(caps) => caps,
+        None => { return Err(String::from(UNSUPPORTED)); }
+    };
+
+    let mut hn = standard(&caps)?;
+    hn.suffix = hn.suffix.map(|suffix| suffix.split_whitespace().collect());
+
+    Ok(hn)
+}
+
+fn nl(number: &str) -> Result<HouseNumber, String> {
+    let caps = match NL.captures(number) {
+        Some(caps) => caps,
+        None => { return Err(String::from(UNSUPPORTED)); }
+    };
+
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+    hn.suffix = group(&caps, "suffix");
+
+    if let Some(addition) = caps.name("addition") {
+        // A numeric addition greater than the number is a range, ie: 263-267
+        match addition.as_str().parse::<u64>() {
+            Ok(range) if range > hn.base && hn.suffix.is_none() => { hn.range = Some(range); },
+            _ => { hn.addition = Some(format!("-{}", addition.as_str())); }
+        };
+    }
+
+    Ok(hn)
+}
+
+fn jp(number: &str) -> Result<HouseNumber, String> {
+    // Full width digits & dashes are common, as are the 丁目 (chōme), 番 (ban) & 号 (gō) markers
+    let number: String = number.chars().map(|c| match c {
+        '０'..='９' => std::char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+        '－' | '−' | 'ー' | '‐' => '-',
+        _ => c
+    }).collect();
+
+    let number = number
+        .replace("丁目", "-")
+        .replace("番地", "-")
+        .replace("番", "-")
+        .replace("号", "");
+
+    let caps = match JP.captures(number.trim_end_matches('-')) {
+        Some(caps) => caps,
+        None => { return Err(String::from(UNSUPPORTED)); }
+    };
+
+    let mut hn = HouseNumber::new(int(&caps["base"])?);
+    hn.blocks = caps["blocks"].split('-').filter(|block| !block.is_empty()).map(int).collect::<Result<Vec<u64>, String>>()?;
+
+    Ok(hn)
+}
+
+///
+/// The numbers accepted for every country before grammars were country specific, ie: 10a,
+/// 10-12, 123n456, n123w456 & 12к2с1, accepted by each grammar so that no number that was
+/// imported before is rejected
+///
+fn baseline(number: &str) -> Option<Result<HouseNumber, String>> {
+    if let Some(caps) = STANDARD.captures(number) {
+        return Some(standard(&caps));
+    }
+
+    if BUILDING.is_match(number) {
+        return Some(ru(number));
+    }
+
+    grid(number)
+}
+
+fn default(number: &str) -> Result<HouseNumber, String> {
+    // Queens numbers are otherwise indistinguishable from ranges, unless zero padded
+    if let Some(caps) = PADDED.captures(number) {
+        return queens(&caps);
+    }
+
+    if let Some(caps) = DEFAULT.captures(number) {
+        return standard(&caps);
+    }
+
+    if let Some(hn) = grid(number) {
+        return hn;
+    }
+
+    if let Some(caps) = BLOCKS.captures(number) {
+        let mut hn = HouseNumber::new(int(&caps["base"])?);
+        hn.blocks = caps["blocks"].split('-').filter(|block| !block.is_empty()).map(int).collect::<Result<Vec<u64>, String>>()?;
+        return Ok(hn);
+    }
+
+    if let Some(caps) = CONSCRIPTION.captures(number) {
+        let mut hn = HouseNumber::new(int(&caps["base"])?);
+        hn.conscription = Some(int(&caps["conscription"])?);
+        hn.suffix = group(&caps, "suffix");
+        return Ok(hn);
+    }
+
+    if let Some(caps) = ADDITION.captures(number) {
+        let mut hn = HouseNumber::new(int(&caps["base"])?);
+        hn.suffix = group(&caps, "suffix");
+        hn.addition = group(&caps, "addition");
+        return Ok(hn);
+    }
+
+    Err(String::from(UNSUPPORTED))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::Tokens;
+
+    fn grammar(country: &str, region: Option<&str>) -> Grammar {
+        Grammar::new(&Context::new(String::from(country), region.map(String::from), Tokens::new(HashMap::new())))
+    }
+
+    ///
+    /// Assert each number is normalized to the expected number by the grammar, that the
+    /// normalized number is parsed back to the same number by the grammar, as when it is
+    /// read back from the database, & that it is unchanged by the default grammar
+    ///
+    fn assert_numbers(grammar: Grammar, numbers: Vec<(&str, &str)>) {
+        for (number, expected) in numbers {
+            let hn = grammar.parse(number).expect(number);
+
+            assert_eq!(hn.to_string(), expected, "{}", number);
+            assert_eq!(grammar.parse(expected).expect(expected), hn, "{}", expected);
+            assert_eq!(Grammar::Default.parse(expected).unwrap().to_string(), expected, "{}", expected);
+        }
+    }
+
+    #[test]
+    fn test_grammar_new() {
+        assert_eq!(grammar("us", None), Grammar::Us);
+        assert_eq!(grammar("us", Some("ny")), Grammar::UsNy);
+        assert_eq!(grammar("sk", None), Grammar::Cz);
+        assert_eq!(grammar("at", None), Grammar::De);
+        assert_eq!(grammar("fr", None), Grammar::Default);
+        assert_eq!(grammar("", None), Grammar::Default);
+    }
+
+    #[test]
+    fn test_grammar_us() {
+        assert_numbers(Grammar::Us, vec![
+            ("1600", "1600"),
+            ("221 B", "221b"),
+            ("1-3", "1-3"),
+            ("N89W16785", "n89w16785"),
+            ("W156", "w156"),
+            ("500n1200", "500n1200"),
+            ("123 1/2", "123 1/2")
+        ]);
+
+        // Building numbers were accepted for every country before grammars were country specific
+        assert_eq!(Grammar::Us.parse("12к2").unwrap().addition, Some(String::from("к2")));
+        assert!(Grammar::Us.parse("846/1").is_err());
+        assert!(Grammar::Us.hyphenated_units());
+    }
+
+    #[test]
+    fn test_grammar_queens() {
+        assert_numbers(Grammar::UsNy, vec![
+            ("37-14", "37-14"),
+            ("104-01", "104-01"),
+            ("42-10A", "42-10a"),
+            ("350", "350"),
+            ("10-120", "10-120")
+        ]);
+
+        let hn = Grammar::UsNy.parse("37-14").unwrap();
+        assert_eq!((hn.blocks, hn.base, hn.range), (vec![37], 14, None));
+
+        assert!(Grammar::UsNy.parse("37-14").unwrap() < Grammar::UsNy.parse("38-02").unwrap());
+        assert!(!Grammar::UsNy.hyphenated_units());
+    }
+
+    #[test]
+    fn test_grammar_ru() {
+        assert_numbers(Grammar::Ru, vec![
+            ("12", "12"),
+            ("12а", "12а"),
+            ("12к2", "12к2"),
+            ("12 к. 2 с. 1", "12к2с1"),
+            ("7 корп. 3 стр. 2", "7к3с2"),
+            ("5 стр 1", "5с1"),
+            ("14 лит. А", "14а"),
+            ("12 1/2", "12 1/2"),
+            ("10-12", "10-12")
+        ]);
+
+        // Grid numbers, fractions & ranges are accepted by every grammar
+        assert_eq!(Grammar::Ru.parse("n123w456").unwrap(), Grammar::Us.parse("n123w456").unwrap());
+        assert_eq!(Grammar::Ru.parse("12 1/2").unwrap().fraction, Some((1, 2)));
+        assert_eq!(Grammar::Ru.parse("10-12").unwrap().range, Some(12));
+    }
+
+    #[test]
+    fn test_grammar_cz() {
+        assert_numbers(Grammar::Cz, vec![
+            ("846/1", "846/1"),
+            ("186 / 25", "186/25"),
+            ("1502/7a", "1502/7a"),
+            ("846", "846"),
+            ("12 1/2", "12 1/2"),
+            ("10-12", "10-12")
+        ]);
+
+        let hn = Grammar::Cz.parse("846/1").unwrap();
+        assert_eq!((hn.conscription, hn.base), (Some(846), 1));
+
+        assert!(Grammar::Cz.parse("846/").is_err());
+    }
+
+    #[test]
+    fn test_grammar_it() {
+        assert_numbers(Grammar::It, vec![
+            ("12/A", "12a"),
+            ("12 a", "12a"),
+            ("5 bis", "5bis"),
+            ("7/ter", "7ter"),
+            ("42r", "42r"),
+            ("42 rosso", "42r"),
+            ("12 1/2", "12 1/2"),
+            ("10-12", "10-12")
+        ]);
+
+        assert!(Grammar::It.parse("snc").is_err());
+    }
+
+    #[test]
+    fn test_grammar_de() {
+        assert_numbers(Grammar::De, vec![
+            ("77", "77"),
+            ("12a", "12a"),
+            ("12 A", "12a"),
+            ("12a-c", "12a-c"),
+            ("12 a - c", "12a-c"),
+            ("43-45", "43-45"),
+            ("43 bis 45", "43-45"),
+            ("12 1/2", "12 1/2")
+        ]);
+
+        assert!(Grammar::De.parse("12-iii").is_err());
+    }
+
+    #[test]
+    fn test_grammar_nl() {
+        assert_numbers(Grammar::Nl, vec![
+            ("1-III", "1-iii"),
+            ("12 hs", "12-hs"),
+            ("12a", "12a"),
+            ("12A-1", "12a-1"),
+            ("263-267", "263-267"),
+            ("12 1/2", "12 1/2")
+        ]);
+
+        let hn = Grammar::Nl.parse("12-2").unwrap();
+        assert_eq!((hn.base, hn.addition), (12, Some(String::from("-2"))));
+    }
+
+    #[test]
+    fn test_grammar_jp() {
+        assert_numbers(Grammar::Jp, vec![
+            ("1-1-2", "1-1-2"),
+            ("１－２－３", "1-2-3"),
+            ("1丁目2番3号", "1-2-3"),
+            ("3丁目12-5", "3-12-5"),
+            ("4番地", "4"),
+            ("12 1/2", "12 1/2")
+        ]);
+
+        let hn = Grammar::Jp.parse("1丁目2番3号").unwrap();
+        assert_eq!((hn.blocks, hn.base), (vec![1, 2], 3));
+
+        assert!(Grammar::Jp.parse("1-2-3-4").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use super::Grammar;
+
+///
+/// Whether a house number falls on the even or odd side of a street
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd
+}
+
+///
+/// A parsed house number, ie: 123, 123b, 10-12, n123w456, 123 1/2, 12к2с1, 846/1 or 1-2-3
+///
+/// House numbers are normalized as they are parsed, so that two numbers are equal
+/// if they are written differently, ie: "123 B" & "123b"
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HouseNumber {
+    /// Leading cardinal direction of a grid number, ie: n of n123w456
+    pub direction: Option<char>,
+
+    /// Leading block numbers of a hyphenated number, ie: 37 of 37-14 or 1 & 2 of 1-2-3
+    pub blocks: Vec<u64>,
+
+    /// Conscription number preceding the orientation number, ie: 846 of 846/1
+    pub conscription: Option<u64>,
+
+    /// Numeric base of the number, ie: 123 of 123b, 10 of 10-12 or 14 of 37-14
+    pub base: u64,
+
+    /// Minimum number of digits of the base, which is zero padded, ie: 2 for 01 of 104-01
+    pub width: usize,
+
+    /// End of a range of numbers, ie: 12 of 10-12
+    pub range: Option<u64>,
+
+    /// Second axis of a grid number, ie: w456 of n123w456 or n456 of 123n456
+    pub grid: Option<(char, u64)>,
+
+    /// Alphabetic suffix, ie: b of 123b, a-c of 12a-c or bis of 5bis
+    pub suffix: Option<String>,
+
+    /// Numerator & denominator of a fractional number, ie: 1/2 of 123 1/2
+    pub fraction: Option<(u32, u32)>,
+
+    /// Building, structure or other addition following the number, including
+    /// any separator, ie: к2с1 of 12к2с1 or -iii of 12-iii
+    pub addition: Option<String>
+}
+
+impl HouseNumber {
+    ///
+    /// Create a house number with only a numeric base
+    ///
+    pub fn new(base: u64) -> Self {
+        HouseNumber {
+            direction: None,
+            blocks: Vec::new(),
+            conscription: None,
+            base: base,
+            width: 0,
+            range: None,
+            grid: None,
+            suffix: None,
+            fraction: None,
+            addition: None
+        }
+    }
+
+    pub fn parity(&self) -> Parity {
+        match self.base % 2 == 0 {
+            true => Parity::Even,
+            false => Parity::Odd
+        }
+    }
+
+    ///
+    /// Is the number a single numeric value without any other component
+    ///
+    pub fn is_numeric(&self) -> bool {
+        *self == HouseNumber::new(self.base)
+    }
+}
+
+///
+/// Parse a house number with the default grammar, which accepts the normalized
+/// form of a number from any country specific grammar
+///
+impl FromStr for HouseNumber {
+    type Err = String;
+
+    fn from_str(number: &str) -> Result<Self, Self::Err> {
+        Grammar::Default.parse(number)
+    }
+}
+
+impl fmt::Display for HouseNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(direction) = self.direction {
+            write!(f, "{}", direction)?;
+        }
+
+        if let Some(conscription) = self.conscription {
+            write!(f, "{}/", conscription)?;
+        }
+
+        for block in self.blocks.iter() {
+            write!(f, "{}-", block)?;
+        }
+
+        write!(f, "{:0width$}", self.base, width = self.width)?;
+
+        if let Some(range) = self.range {
+            write!(f, "-{}", range)?;
+        }
+
+        if let Some((direction, grid)) = self.grid {
+            write!(f, "{}{}", direction, grid)?;
+        }
+
+        if let Some(ref suffix) = self.suffix {
+            write!(f, "{}", suffix)?;
+        }
+
+        if let Some(ref addition) = self.addition {
+            write!(f, "{}", addition)?;
+        }
+
+        if let Some((num, den)) = self.fraction {
+            write!(f, " {}/{}", num, den)?;
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// Numbers are ordered by their blocks & numeric base, then by grid, fraction, suffix,
+/// range, addition & conscription number, so that 10 < 10-12 < 10a < 10 1/2 < 11
+///
+impl Ord for HouseNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.blocks.cmp(&other.blocks)
+            .then_with(|| self.base.cmp(&other.base))
+            .then_with(|| self.direction.cmp(&other.direction))
+            .then_with(|| self.grid.cmp(&other.grid))
+            .then_with(|| self.fraction.cmp(&other.fraction))
+            .then_with(|| self.suffix.cmp(&other.suffix))
+            .then_with(|| self.range.cmp(&other.range))
+            .then_with(|| self.addition.cmp(&other.addition))
+            .then_with(|| self.conscription.cmp(&other.conscription))
+            .then_with(|| self.width.cmp(&other.width))
+    }
+}
+
+impl PartialOrd for HouseNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl serde::Serialize for HouseNumber {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for HouseNumber {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        let number = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+        number.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hn(number: &str) -> HouseNumber {
+        number.parse().unwrap()
+    }
+
+    #[test]
+    fn test_housenumber_parse() {
+        assert_eq!(hn("123"), HouseNumber::new(123));
+        assert_eq!(hn(" 123 B ").suffix, Some(String::from("b")));
+        assert_eq!(hn("123 B"), hn("123b"));
+
+        let range = hn("10-12a");
+        assert_eq!((range.base, range.range, range.suffix), (10, Some(12), Some(String::from("a"))));
+
+        let grid = hn("N123W456");
+        assert_eq!((grid.direction, grid.base, grid.grid), (Some('n'), 123, Some(('w', 456))));
+
+        let grid = hn("123n456");
+        assert_eq!((grid.direction, grid.base, grid.grid), (None, 123, Some(('n', 456))));
+
+        assert_eq!(hn("123 1/2").fraction, Some((1, 2)));
+        assert_eq!(hn("12к2с1").addition, Some(String::from("к2с1")));
+
+        assert_eq!("123 c d".parse::<HouseNumber>().unwrap_err(), "Number is not a supported address/unit type");
+        assert_eq!("123 3/2".parse::<HouseNumber>().unwrap_err(), "Number is not a supported address/unit type");
+        assert_eq!("12345678901".parse::<HouseNumber>().unwrap_err(), "Number should not exceed 10 chars");
+        assert!("".parse::<HouseNumber>().is_err());
+    }
+
+    #[test]
+    fn test_housenumber_display() {
+        for number in vec!["123", "123b", "10-12a", "n123w456", "w12", "123n456", "123 1/2", "12к2с1", "846/1", "1-2-3", "12a-c", "12-iii"] {
+            assert_eq!(hn(number).to_string(), number);
+        }
+
+        assert_eq!(hn("0123 B").to_string(), "123b");
+        assert_eq!(serde_json::to_string(&hn("123 B")).unwrap(), "\"123b\"");
+        assert_eq!(serde_json::from_str::<HouseNumber>("\"10-12\"").unwrap(), hn("10-12"));
+    }
+
+    #[test]
+    fn test_housenumber_order() {
+        let mut numbers = vec![hn("11"), hn("10 1/2"), hn("10a"), hn("10-12"), hn("10"), hn("9b")];
+        numbers.sort();
+
+        assert_eq!(numbers.iter().map(|number| number.to_string()).collect::<Vec<String>>(), vec![
+            "9b", "10", "10-12", "10a", "10 1/2", "11"
+        ]);
+
+        assert_eq!(hn("12b").parity(), Parity::Even);
+        assert_eq!(hn("n123w456").parity(), Parity::Odd);
+        assert!(hn("12").is_numeric());
+        assert!(!hn("12b").is_numeric());
+    }
+}
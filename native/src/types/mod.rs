@@ -1,6 +1,10 @@
 mod address;
 mod network;
 mod polygon;
+mod housenumber;
+mod grammar;
+mod unit;
+mod place;
 
 mod name;
 mod context;
@@ -9,6 +13,10 @@ pub mod hecate;
 pub use self::address::Address;
 pub use self::network::Network;
 pub use self::polygon::Polygon;
+pub use self::housenumber::HouseNumber;
+pub use self::housenumber::Parity;
+pub use self::grammar::Grammar;
+pub use self::unit::Unit;
 
 pub use self::name::Name;
 pub use self::name::Names;
@@ -13,7 +13,7 @@ struct InputName {
     pub priority: i8
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Names {
     pub names: Vec<Name>
 }
@@ -104,7 +104,7 @@ impl Names {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Name {
     /// Street Name
     pub display: String,
@@ -133,14 +133,9 @@ impl Name {
     /// * `display` - A string containing the street name (Main St)
     ///
     /// ```
-    pub fn new(mut display: String, priority: i8, context: &Context) -> Self {
+    pub fn new(display: String, priority: i8, context: &Context) -> Self {
         let tokens = context.tokens.process(&display);
 
-        display = display
-            .replace(r#"""#, "")
-            .replace("\t", "")
-            .replace("\n", "");
-
         Name {
             display: display,
             priority: priority,
@@ -171,6 +166,9 @@ mod tests {
             tokenless: String::from("main st nw"),
             freq: 1
         });
+
+        // Names are sent to Postgres via binary COPY so are kept exactly as given
+        assert_eq!(Name::new(String::from("\"Main\"\tSt\nNW"), 0, &context).display, String::from("\"Main\"\tSt\nNW"));
     }
 
     #[test]
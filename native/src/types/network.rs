@@ -1,5 +1,5 @@
-use postgis::ewkb::EwkbWrite;
-use crate::{Context, text, Names};
+use crate::{Context, Error, text, Name, Names, pg::binary::Row};
+use super::place;
 
 #[derive(Debug)]
 ///
@@ -15,11 +15,20 @@ pub struct Network {
     /// String source/provider/timestamp for the given data
     pub source: String,
 
+    /// Normalized postcode of the network, if any
+    pub postcode: Option<String>,
+
+    /// Locality, ie: city or town, of the network, if any
+    pub locality: Option<String>,
+
     /// JSON representation of properties
     pub props: serde_json::Map<String, serde_json::Value>,
 
     /// Simple representation of MultiLineString
-    pub geom: Vec<geojson::LineStringType>
+    pub geom: Vec<geojson::LineStringType>,
+
+    /// Descriptions of the repairs made to the geometry by std(), to be reported to the error sink
+    pub repairs: Vec<String>
 }
 
 impl Network {
@@ -46,6 +55,9 @@ impl Network {
             None => String::from("")
         };
 
+        let postcode = place::postcode(props.get(&context.postcode))?;
+        let locality = place::locality(props.get(&context.locality))?;
+
         let geom = match feat.geometry {
             Some(geom) => match geom.value {
                 geojson::Value::LineString(ln) => vec![ln],
@@ -76,8 +88,11 @@ impl Network {
             },
             names: names,
             source: source,
+            postcode: postcode,
+            locality: locality,
             props: props,
-            geom: geom
+            geom: geom,
+            repairs: Vec::new()
         };
 
         net.std(&context)?;
@@ -85,6 +100,68 @@ impl Network {
         Ok(net)
     }
 
+    ///
+    /// Construct a network instance via a Row JSON Value
+    ///
+    pub fn from_value(value: serde_json::Value) -> Result<Self, String> {
+        let mut value = match value {
+            serde_json::Value::Object(obj) => obj,
+            _ => { return Err(String::from("Network::from_row value must be JSON Object")); }
+        };
+
+        let names: Vec<Name> = match value.remove(&String::from("names")) {
+            Some(names) => match serde_json::from_value(names) {
+                Ok(names) => names,
+                Err(err) => { return Err(format!("Names Conversion Error: {}", err.to_string())); }
+            },
+            None => { return Err(String::from("names key/value is required")); }
+        };
+
+        let props = match value.remove(&String::from("props")) {
+            Some(serde_json::Value::Object(obj)) => obj,
+            Some(_) => { return Err(String::from("props must be JSON Object")); },
+            None => { return Err(String::from("props key/value is required")); }
+        };
+
+        let source = match value.remove(&String::from("source")) {
+            Some(serde_json::Value::String(source)) => source,
+            _ => String::from("")
+        };
+
+        let geom = match value.remove(&String::from("geom")) {
+            Some(serde_json::Value::String(geom)) => match geom.parse::<geojson::GeoJson>() {
+                Ok(geojson::GeoJson::Geometry(geom)) => match geom.value {
+                    geojson::Value::LineString(ln) => vec![ln],
+                    geojson::Value::MultiLineString(mln) => mln,
+                    _ => { return Err(String::from("Geometry must be (Multi)LineString type")); }
+                },
+                Ok(_) => { return Err(String::from("Geometry must be (Multi)LineString type")); },
+                Err(err) => { return Err(format!("geom parse error: {}", err.to_string())); }
+            },
+            Some(_) => { return Err(String::from("geom only supports TEXT type")); },
+            None => { return Err(String::from("geom key/value is required")); }
+        };
+
+        Ok(Network {
+            id: match value.remove(&String::from("id")) {
+                Some(id) => match id.as_i64() {
+                    Some(id) => Some(id),
+                    None => { return Err(String::from("ID must be numeric")); }
+                },
+                None => None
+            },
+            names: Names {
+                names: names
+            },
+            source: source,
+            postcode: place::postcode(value.get(&String::from("postcode")))?,
+            locality: place::locality(value.get(&String::from("locality")))?,
+            props: props,
+            geom: geom,
+            repairs: Vec::new()
+        })
+    }
+
     pub fn std(&mut self, context: &Context) -> Result<(), String> {
         for name in self.names.names.iter() {
             if text::is_drivethrough(&name.display, &context) {
@@ -99,14 +176,106 @@ impl Network {
             }
         }
 
+        self.std_geom()?;
+
+        Ok(())
+    }
+
+    ///
+    /// Clean the geometry of the network, collapsing repeated vertices, splitting
+    /// lines that cross the antimeridian & dropping parts without 2 distinct vertices
+    ///
+    /// Each repair is recorded in self.repairs, a network with no remaining parts is invalid
+    ///
+    fn std_geom(&mut self) -> Result<(), String> {
+        let mut duplicates = 0;
+        let mut crossings = 0;
+
+        let mut geom: Vec<geojson::LineStringType> = Vec::with_capacity(self.geom.len());
+
+        for ln in self.geom.drain(..) {
+            let mut part: geojson::LineStringType = Vec::with_capacity(ln.len());
+
+            for pt in ln {
+                if pt.len() < 2 || !pt[0].is_finite() || !pt[1].is_finite() {
+                    return Err(String::from("Network has an invalid coordinate"));
+                }
+
+                let prev = match part.last() {
+                    Some(prev) => prev.clone(),
+                    None => {
+                        part.push(pt);
+                        continue;
+                    }
+                };
+
+                if prev[0] == pt[0] && prev[1] == pt[1] {
+                    duplicates += 1;
+                    continue;
+                }
+
+                if (pt[0] - prev[0]).abs() > 180.0 {
+                    crossings += 1;
+
+                    // Longitude at which the line leaves & re-enters, with the
+                    // latitude of the crossing interpolated between the two vertices
+                    let (exit, entry) = match prev[0] > pt[0] {
+                        true => (180.0, -180.0),
+                        false => (-180.0, 180.0)
+                    };
+
+                    let end = pt[0] + exit * 2.0;
+                    let lat = prev[1] + (pt[1] - prev[1]) * (exit - prev[0]) / (end - prev[0]);
+
+                    if prev[0] != exit || prev[1] != lat {
+                        part.push(vec![exit, lat]);
+                    }
+
+                    geom.push(part);
+
+                    part = vec![vec![entry, lat]];
+
+                    if pt[0] != entry || pt[1] != lat {
+                        part.push(pt);
+                    }
+
+                    continue;
+                }
+
+                part.push(pt);
+            }
+
+            geom.push(part);
+        }
+
+        let parts = geom.len();
+        geom.retain(|part| part.len() >= 2);
+        let degenerate = parts - geom.len();
+
+        if geom.is_empty() {
+            return Err(String::from("Network has no line with at least 2 distinct vertices"));
+        }
+
+        if duplicates > 0 {
+            self.repairs.push(format!("Repeated vertices removed: {}", duplicates));
+        }
+        if crossings > 0 {
+            self.repairs.push(format!("Antimeridian crossings split: {}", crossings));
+        }
+        if degenerate > 0 {
+            self.repairs.push(format!("Line parts without 2 distinct vertices dropped: {}", degenerate));
+        }
+
+        self.geom = geom;
+
         Ok(())
     }
 
     ///
-    /// Return a PG Copyable String of the feature
-    /// names, source, props, geom
+    /// Return a PG binary COPY row of the feature
+    /// names, source, postcode, locality, props, geom
     ///
-    pub fn to_tsv(self) -> String {
+    pub fn to_copy(self) -> Result<Vec<u8>, Error> {
         let mut twkb = postgis::twkb::MultiLineString {
             lines: Vec::with_capacity(self.geom.len()),
             ids: None
@@ -131,14 +300,36 @@ impl Network {
             geom: &twkb,
             srid: Some(4326),
             point_type: postgis::ewkb::PointType::Point
-        }.to_hex_ewkb();
-
-        format!("{names}\t{source}\t{props}\t{geom}\n",
-            names = serde_json::to_string(&self.names.names).unwrap_or(String::from("")),
-            source = self.source,
-            props = serde_json::value::Value::from(self.props),
-            geom = geom
-        )
+        };
+
+        let mut row = Row::new(6);
+        row.jsonb(&self.names.names)?;
+        row.text(&self.source);
+        match self.postcode {
+            Some(ref postcode) => row.text(postcode),
+            None => row.null()
+        };
+        match self.locality {
+            Some(ref locality) => row.text(locality),
+            None => row.null()
+        };
+        row.jsonb(&self.props)?;
+        row.geometry(&geom)?;
+
+        Ok(row.finish())
+    }
+}
+
+///
+/// Allows networks to be read directly from a pg::Cursor
+///
+impl<'de> serde::Deserialize<'de> for Network {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+
+        Network::from_value(value).map_err(serde::de::Error::custom)
     }
 }
 
@@ -147,6 +338,7 @@ mod tests {
     use super::*;
     use crate::{Tokens, Context};
     use std::collections::HashMap;
+    use serde_json::json;
 
     #[test]
     fn test_network_simple_geom() {
@@ -169,6 +361,139 @@ mod tests {
 
         let net = Network::new(feat, &context).unwrap();
 
-        assert_eq!(net.to_tsv(), "[{\"display\":\"Poremba Court Southwest\",\"priority\":0,\"source\":\"network\",\"tokenized\":\"poremba court southwest\",\"tokenless\":\"poremba court southwest\",\"freq\":1}]\t\t{\"id\":6052094,\"street\":[{\"display\":\"Poremba Court Southwest\",\"priority\":0}]}\t0105000020E610000001000000010200000003000000FCA5457D924053C09128B4ACFB6D4340F52F49658A4053C0CBA145B6F36D434009826CFE844053C0F7D676C9EE6D4340\n");
+        let mut expected = vec![0, 6];
+        for field in vec![
+            Some(String::from("\u{1}[{\"display\":\"Poremba Court Southwest\",\"priority\":0,\"source\":\"network\",\"tokenized\":\"poremba court southwest\",\"tokenless\":\"poremba court southwest\",\"freq\":1}]").into_bytes()),
+            Some(Vec::new()),
+            None,
+            None,
+            Some(String::from("\u{1}{\"id\":6052094,\"street\":[{\"display\":\"Poremba Court Southwest\",\"priority\":0}]}").into_bytes()),
+            Some((0..70).map(|i| u8::from_str_radix(&"0105000020E610000001000000010200000003000000FCA5457D924053C09128B4ACFB6D4340F52F49658A4053C0CBA145B6F36D434009826CFE844053C0F7D676C9EE6D4340"[i * 2..i * 2 + 2], 16).unwrap()).collect())
+        ] {
+            match field {
+                Some(field) => {
+                    expected.extend_from_slice(&(field.len() as i32).to_be_bytes());
+                    expected.extend(field);
+                },
+                None => expected.extend_from_slice(&(-1i32).to_be_bytes())
+            }
+        }
+
+        assert_eq!(net.to_copy().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_network_from_value() {
+        let net: Network = serde_json::from_value(json!({
+            "id": 12,
+            "names": [{
+                "display": "Main St",
+                "priority": 0,
+                "source": "network",
+                "tokenized": "main st",
+                "tokenless": "main",
+                "freq": 1
+            }],
+            "source": "test",
+            "postcode": "20001",
+            "locality": null,
+            "props": { "street": "Main St" },
+            "geom": "{\"type\":\"LineString\",\"coordinates\":[[-77.0,38.8],[-77.1,38.9]]}"
+        })).unwrap();
+
+        assert_eq!(net.id, Some(12));
+        assert_eq!(net.source, String::from("test"));
+        assert_eq!((net.postcode, net.locality), (Some(String::from("20001")), None));
+        assert_eq!(net.names.names[0].tokenized, String::from("main st"));
+        assert_eq!(net.geom, vec![vec![vec![-77.0, 38.8], vec![-77.1, 38.9]]]);
+
+        assert!(Network::from_value(json!({ "names": [] })).is_err());
+    }
+
+    #[test]
+    fn test_network_place() {
+        let feat: geojson::GeoJson = json!({
+            "type": "Feature",
+            "properties": {
+                "street": [{ "display": "Main St", "priority": 0 }],
+                "zip": "sw1a 1aa",
+                "city": " London "
+            },
+            "geometry": { "type": "LineString", "coordinates": [[-0.1, 51.5], [-0.2, 51.6]] }
+        }).to_string().parse().unwrap();
+
+        let mut context = Context::new(String::from("gb"), None, Tokens::new(HashMap::new()));
+        context.postcode = String::from("zip");
+
+        let net = Network::new(feat, &context).unwrap();
+
+        assert_eq!(net.postcode, Some(String::from("SW1A 1AA")));
+        assert_eq!(net.locality, Some(String::from("London")));
+        assert_eq!(net.props.get("zip"), Some(&json!("sw1a 1aa")));
+    }
+
+    fn network(geom: serde_json::Value) -> Result<Network, String> {
+        let feat: geojson::GeoJson = json!({
+            "type": "Feature",
+            "properties": { "street": [{ "display": "Main St", "priority": 0 }] },
+            "geometry": geom
+        }).to_string().parse().unwrap();
+
+        Network::new(feat, &Context::new(String::from("us"), None, Tokens::new(HashMap::new())))
+    }
+
+    #[test]
+    fn test_network_std_geom() {
+        let net = network(json!({
+            "type": "LineString",
+            "coordinates": [[-77.0, 38.8], [-77.1, 38.9]]
+        })).unwrap();
+
+        assert_eq!(net.geom, vec![vec![vec![-77.0, 38.8], vec![-77.1, 38.9]]]);
+        assert!(net.repairs.is_empty());
+
+        let net = network(json!({
+            "type": "MultiLineString",
+            "coordinates": [
+                [[-77.0, 38.8], [-77.0, 38.8], [-77.1, 38.9], [-77.1, 38.9]],
+                [[-77.2, 38.8], [-77.2, 38.8]],
+                [[-77.3, 38.8]]
+            ]
+        })).unwrap();
+
+        assert_eq!(net.geom, vec![vec![vec![-77.0, 38.8], vec![-77.1, 38.9]]]);
+        assert_eq!(net.repairs, vec![
+            String::from("Repeated vertices removed: 3"),
+            String::from("Line parts without 2 distinct vertices dropped: 2")
+        ]);
+
+        assert_eq!(network(json!({
+            "type": "LineString",
+            "coordinates": [[-77.0, 38.8], [-77.0, 38.8]]
+        })).unwrap_err(), "Network has no line with at least 2 distinct vertices");
+    }
+
+    #[test]
+    fn test_network_std_antimeridian() {
+        let net = network(json!({
+            "type": "LineString",
+            "coordinates": [[178.0, -16.0], [179.0, -16.0], [-179.0, -18.0], [-178.0, -18.0]]
+        })).unwrap();
+
+        assert_eq!(net.geom, vec![
+            vec![vec![178.0, -16.0], vec![179.0, -16.0], vec![180.0, -17.0]],
+            vec![vec![-180.0, -17.0], vec![-179.0, -18.0], vec![-178.0, -18.0]]
+        ]);
+        assert_eq!(net.repairs, vec![String::from("Antimeridian crossings split: 1")]);
+
+        let net = network(json!({
+            "type": "LineString",
+            "coordinates": [[-179.0, -16.0], [179.0, -18.0]]
+        })).unwrap();
+
+        assert_eq!(net.geom, vec![
+            vec![vec![-179.0, -16.0], vec![-180.0, -17.0]],
+            vec![vec![180.0, -17.0], vec![179.0, -18.0]]
+        ]);
     }
 }
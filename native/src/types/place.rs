@@ -0,0 +1,64 @@
+///
+/// Read the postcode of a feature from a property value, ie: "20001" or "SW1A 1AA"
+///
+/// Postcodes are uppercased with repeated whitespace collapsed, an empty or
+/// null value is treated as a feature without a postcode
+///
+pub fn postcode(value: Option<&serde_json::Value>) -> Result<Option<String>, String> {
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Number(postcode)) => Ok(Some(postcode.to_string())),
+        Some(serde_json::Value::String(postcode)) => Ok(normalize(postcode).map(|postcode| postcode.to_uppercase())),
+        Some(_) => Err(String::from("Postcode property must be String or Numeric"))
+    }
+}
+
+///
+/// Read the locality of a feature from a property value, ie: "Washington"
+///
+/// An empty or null value is treated as a feature without a locality
+///
+pub fn locality(value: Option<&serde_json::Value>) -> Result<Option<String>, String> {
+    match value {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(locality)) => Ok(normalize(locality)),
+        Some(_) => Err(String::from("Locality property must be String"))
+    }
+}
+
+fn normalize(value: &str) -> Option<String> {
+    let value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    match value.is_empty() {
+        true => None,
+        false => Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_postcode() {
+        assert_eq!(postcode(Some(&json!("20001"))), Ok(Some(String::from("20001"))));
+        assert_eq!(postcode(Some(&json!(20001))), Ok(Some(String::from("20001"))));
+        assert_eq!(postcode(Some(&json!(" sw1a   1aa "))), Ok(Some(String::from("SW1A 1AA"))));
+        assert_eq!(postcode(Some(&json!(" "))), Ok(None));
+        assert_eq!(postcode(Some(&json!(null))), Ok(None));
+        assert_eq!(postcode(None), Ok(None));
+
+        assert!(postcode(Some(&json!(["20001"]))).is_err());
+    }
+
+    #[test]
+    fn test_locality() {
+        assert_eq!(locality(Some(&json!(" Washington "))), Ok(Some(String::from("Washington"))));
+        assert_eq!(locality(Some(&json!("New  York"))), Ok(Some(String::from("New York"))));
+        assert_eq!(locality(Some(&json!(""))), Ok(None));
+        assert_eq!(locality(None), Ok(None));
+
+        assert!(locality(Some(&json!(12))).is_err());
+    }
+}
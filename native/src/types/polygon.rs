@@ -1,4 +1,4 @@
-use postgis::ewkb::EwkbWrite;
+use crate::{Error, pg::binary::Row, mem::geom};
 
 ///
 /// A representation of a single Address
@@ -36,6 +36,8 @@ impl Polygon {
             None => { return Err(String::from("Polygon must have geometry")); }
         };
 
+        let geom = Polygon::validate(geom)?;
+
         Ok(Polygon {
             id: match feat.id {
                 Some(geojson::feature::Id::Number(id)) => id.as_i64(),
@@ -47,10 +49,104 @@ impl Polygon {
     }
 
     ///
-    /// Return a PG Copyable String of the feature
+    /// Validate the geometry of a (Multi)Polygon, repairing it where possible
+    ///
+    /// Repeated vertices are removed, unclosed rings are closed and rings are
+    /// rewound so that exterior rings are counterclockwise & holes clockwise.
+    /// Holes & member polygons with fewer than 3 distinct vertices or no area are
+    /// dropped. Coordinates outside of lng/lat bounds, self-intersecting rings &
+    /// geometries with no remaining polygons cannot be repaired
+    ///
+    pub fn validate(geom: Vec<geojson::PolygonType>) -> Result<Vec<geojson::PolygonType>, String> {
+        let mut valid = Vec::with_capacity(geom.len());
+
+        for py in geom {
+            let mut rings = py.into_iter();
+
+            let outer = match rings.next() {
+                Some(outer) => match Polygon::ring(outer, true)? {
+                    Some(outer) => outer,
+                    None => { continue; }
+                },
+                None => { continue; }
+            };
+
+            let mut poly = vec![outer];
+
+            for hole in rings {
+                if let Some(hole) = Polygon::ring(hole, false)? {
+                    poly.push(hole);
+                }
+            }
+
+            valid.push(poly);
+        }
+
+        if valid.is_empty() {
+            return Err(String::from("Polygon has no rings with at least 3 distinct vertices"));
+        }
+
+        Ok(valid)
+    }
+
+    ///
+    /// Validate & repair a single ring, returning None if the ring is degenerate
+    ///
+    fn ring(ring: Vec<geojson::Position>, exterior: bool) -> Result<Option<Vec<geojson::Position>>, String> {
+        let mut valid: Vec<geojson::Position> = Vec::with_capacity(ring.len() + 1);
+
+        for pt in ring {
+            if pt.len() < 2 || !pt[0].is_finite() || !pt[1].is_finite() {
+                return Err(String::from("Polygon has an invalid coordinate"));
+            } else if pt[0] < -180.0 || pt[0] > 180.0 || pt[1] < -90.0 || pt[1] > 90.0 {
+                return Err(format!("Polygon coordinate outside of lng/lat bounds: [{}, {}]", pt[0], pt[1]));
+            }
+
+            if valid.last() != Some(&pt) {
+                valid.push(pt);
+            }
+        }
+
+        if valid.len() > 1 && valid.first() == valid.last() {
+            valid.pop();
+        }
+
+        if valid.len() < 3 {
+            return Ok(None);
+        }
+
+        valid.push(valid[0].clone());
+
+        let segments = valid.len() - 1;
+        for i in 0..segments {
+            // Adjacent segments always share a vertex, as do the first & last segments of the ring
+            for j in (i + 2)..segments {
+                if i == 0 && j == segments - 1 {
+                    continue;
+                }
+
+                if geom::segments_intersect(&valid[i], &valid[i + 1], &valid[j], &valid[j + 1]) {
+                    return Err(format!("Polygon ring self-intersects near [{}, {}]", valid[i][0], valid[i][1]));
+                }
+            }
+        }
+
+        let area = geom::signed_area(&valid);
+
+        if area == 0.0 {
+            return Ok(None);
+        } else if (area > 0.0) != exterior {
+            valid.reverse();
+        }
+
+        Ok(Some(valid))
+    }
+
+    ///
+    /// Return a PG binary COPY row of the feature
     /// props, geom
     ///
-    pub fn to_tsv(self) -> String {
+    pub fn to_copy(self) -> Result<Vec<u8>, Error> {
         let mut twkb = postgis::twkb::MultiPolygon {
             polygons: Vec::with_capacity(self.geom.len()),
             ids: None
@@ -83,11 +179,84 @@ impl Polygon {
             geom: &twkb,
             srid: Some(4326),
             point_type: postgis::ewkb::PointType::Point
-        }.to_hex_ewkb();
+        };
+
+        let mut row = Row::new(2);
+        row.jsonb(&self.props)?;
+        row.geometry(&geom)?;
+
+        Ok(row.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feat(geom: serde_json::Value) -> geojson::GeoJson {
+        let feat = serde_json::json!({
+            "type": "Feature",
+            "properties": {},
+            "geometry": geom
+        });
+
+        feat.to_string().parse::<geojson::GeoJson>().unwrap()
+    }
+
+    #[test]
+    fn test_polygon_repair() {
+        // Unclosed, clockwise, with a repeated vertex
+        let poly = Polygon::new(feat(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [[[0, 0], [0, 1], [0, 1], [1, 1], [1, 0]]]
+        }))).unwrap();
+
+        assert_eq!(poly.geom, vec![vec![vec![
+            vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 1.0], vec![0.0, 0.0]
+        ]]]);
+
+        // Counterclockwise hole is rewound, degenerate hole is dropped
+        let poly = Polygon::new(feat(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [
+                [[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]],
+                [[1, 1], [2, 1], [2, 2], [1, 2], [1, 1]],
+                [[3, 3], [3.5, 3.5], [3, 3]]
+            ]
+        }))).unwrap();
+
+        assert_eq!(poly.geom, vec![vec![
+            vec![vec![0.0, 0.0], vec![4.0, 0.0], vec![4.0, 4.0], vec![0.0, 4.0], vec![0.0, 0.0]],
+            vec![vec![1.0, 1.0], vec![1.0, 2.0], vec![2.0, 2.0], vec![2.0, 1.0], vec![1.0, 1.0]]
+        ]]);
+
+        // Collinear member polygon is dropped from the MultiPolygon
+        let poly = Polygon::new(feat(serde_json::json!({
+            "type": "MultiPolygon",
+            "coordinates": [
+                [[[0, 0], [1, 0], [2, 0], [0, 0]]],
+                [[[0, 0], [1, 0], [1, 1], [0, 0]]]
+            ]
+        }))).unwrap();
+
+        assert_eq!(poly.geom.len(), 1);
+    }
+
+    #[test]
+    fn test_polygon_invalid() {
+        assert_eq!(Polygon::new(feat(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [[[0, 0], [1, 1], [0, 0]]]
+        }))).unwrap_err(), "Polygon has no rings with at least 3 distinct vertices");
+
+        assert_eq!(Polygon::new(feat(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [[[0, 0], [2, 2], [2, 0], [0, 2], [0, 0]]]
+        }))).unwrap_err(), "Polygon ring self-intersects near [0, 0]");
 
-        format!("{props}\t{geom}\n",
-            props = serde_json::value::Value::from(self.props),
-            geom = geom
-        )
+        assert_eq!(Polygon::new(feat(serde_json::json!({
+            "type": "Polygon",
+            "coordinates": [[[0, 0], [181, 0], [1, 1], [0, 0]]]
+        }))).unwrap_err(), "Polygon coordinate outside of lng/lat bounds: [181, 0]");
     }
 }
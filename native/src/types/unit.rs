@@ -0,0 +1,143 @@
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+
+///
+/// An apartment, suite or other unit within the building at an address, ie: apt 4 or ste 200
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Unit {
+    /// Normalized type of the unit, ie: apt, ste, unit, fl or rm, None if untyped (#4 or 10-4)
+    pub kind: Option<String>,
+
+    /// Identifier of the unit, ie: 4 of apt 4
+    pub id: String
+}
+
+/// Unit designators that may precede the identifier of a unit
+const KIND: &str = r"(?P<kind>apartment|apt|suite|ste|unit|floor|fl|room|rm|#)\.?";
+
+lazy_static! {
+    static ref UNIT: Regex = Regex::new(&format!(r"^({}\s*)?(?P<id>[0-9a-z]+(-[0-9a-z]+)?)$", KIND)).unwrap();
+    static ref TRAILING: Regex = Regex::new(&format!(r"^(?P<number>.*?[0-9a-z])\s*,?\s*{}\s*(?P<id>[0-9a-z]+(-[0-9a-z]+)?)$", KIND)).unwrap();
+    static ref HYPHEN: Regex = Regex::new(r"^(?P<base>\d+)(?P<suffix>[a-z]?)-(?P<id>\d+)$").unwrap();
+}
+
+impl Unit {
+    pub fn new(kind: Option<&str>, id: &str) -> Self {
+        Unit {
+            kind: kind.and_then(|kind| match kind {
+                "apartment" | "apt" => Some(String::from("apt")),
+                "suite" | "ste" => Some(String::from("ste")),
+                "unit" => Some(String::from("unit")),
+                "floor" | "fl" => Some(String::from("fl")),
+                "room" | "rm" => Some(String::from("rm")),
+                _ => None
+            }),
+            id: id.to_string()
+        }
+    }
+
+    ///
+    /// Split a unit from the end of a house number, ie: "10 Apt 4", "10, #4"
+    /// or "10-4", returning the remaining number & the unit if one was found
+    ///
+    /// A hyphenated number is only a unit if hyphenated units are enabled & the part following
+    /// the hyphen is lower than the number, as a range of numbers is always ascending
+    ///
+    pub fn split(number: &str, hyphenated: bool) -> (String, Option<Unit>) {
+        let lower = number.trim().to_lowercase();
+
+        if let Some(caps) = TRAILING.captures(&lower) {
+            let kind = caps.name("kind").map(|kind| kind.as_str());
+
+            return (caps["number"].to_string(), Some(Unit::new(kind, &caps["id"])));
+        }
+
+        if !hyphenated {
+            return (number.to_string(), None);
+        }
+
+        if let Some(caps) = HYPHEN.captures(&lower) {
+            if let (Ok(base), Ok(id)) = (caps["base"].parse::<u64>(), caps["id"].parse::<u64>()) {
+                if id < base {
+                    return (format!("{}{}", &caps["base"], &caps["suffix"]), Some(Unit::new(None, &caps["id"])));
+                }
+            }
+        }
+
+        (number.to_string(), None)
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(unit: &str) -> Result<Self, Self::Err> {
+        let unit = unit.trim().to_lowercase();
+
+        match UNIT.captures(&unit) {
+            Some(caps) => Ok(Unit::new(caps.name("kind").map(|kind| kind.as_str()), &caps["id"])),
+            None => Err(String::from("Unit is not a supported unit type"))
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            Some(ref kind) => write!(f, "{} {}", kind, self.id),
+            None => write!(f, "{}", self.id)
+        }
+    }
+}
+
+impl serde::Serialize for Unit {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: serde::Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Unit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        let unit = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+        unit.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_parse() {
+        assert_eq!("Apt 4".parse::<Unit>().unwrap(), Unit::new(Some("apt"), "4"));
+        assert_eq!("Suite. 200".parse::<Unit>().unwrap(), Unit::new(Some("ste"), "200"));
+        assert_eq!("#4B".parse::<Unit>().unwrap(), Unit::new(None, "4b"));
+        assert_eq!("4".parse::<Unit>().unwrap(), Unit::new(None, "4"));
+        assert_eq!("Apartment 4".parse::<Unit>().unwrap().to_string(), "apt 4");
+
+        assert!("Apt 4 & 5".parse::<Unit>().is_err());
+        assert!("".parse::<Unit>().is_err());
+    }
+
+    #[test]
+    fn test_unit_split() {
+        assert_eq!(Unit::split("10 Apt 4", true), (String::from("10"), Some(Unit::new(Some("apt"), "4"))));
+        assert_eq!(Unit::split("10b, Ste 200", true), (String::from("10b"), Some(Unit::new(Some("ste"), "200"))));
+        assert_eq!(Unit::split("10 #4", true), (String::from("10"), Some(Unit::new(None, "4"))));
+        assert_eq!(Unit::split("10-4", true), (String::from("10"), Some(Unit::new(None, "4"))));
+        assert_eq!(Unit::split("37-14", false), (String::from("37-14"), None));
+        assert_eq!(Unit::split("37-14 Apt 2", false), (String::from("37-14"), Some(Unit::new(Some("apt"), "2"))));
+
+        assert_eq!(Unit::split("10-12", true), (String::from("10-12"), None));
+        assert_eq!(Unit::split("10 B", true), (String::from("10 B"), None));
+        assert_eq!(Unit::split("n123w456", true), (String::from("n123w456"), None));
+    }
+}
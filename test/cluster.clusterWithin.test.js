@@ -167,3 +167,71 @@ test('LinesStrings should be clustered', (t) => {
 });
 
 db.init(test);
+
+test('Orphan LineStrings are clustered', (t) => {
+    const pool = db.get();
+    const popQ = new Queue(1);
+
+    // POPULATE NETWORK
+    popQ.defer((done) => {
+        pool.query(`
+            BEGIN;
+            INSERT INTO network (id, names, geom) VALUES (1, '[{ "tokenized": "main st", "tokeneless": "main", "display": "Main Street", "freq": 1 }]', ST_SetSRID(ST_GeomFromGeoJSON('{"type": "MultiLineString","coordinates": [[[9.516735076904297,47.13276818606133],[9.519824981689451,47.132870369814995]]]}'), 4326));
+            INSERT INTO network (id, names, geom) VALUES (2, '[{ "tokenized": "main st", "tokeneless": "main", "display": "Main Street", "freq": 1 }]', ST_SetSRID(ST_GeomFromGeoJSON('{"type": "MultiLineString", "coordinates": [[[9.513999223709106,47.132695197545665],[9.512518644332886,47.132695197545665]]]}'), 4326));
+            INSERT INTO network (id, names, geom) VALUES (3, '[{ "tokenized": "side st", "tokeneless": "side", "display": "Side Street", "freq": 1 }]', ST_SetSRID(ST_GeomFromGeoJSON('{"type": "MultiLineString", "coordinates": [[[9.516735076904297,47.13276818606133],[9.516735076904297,47.134]]]}'), 4326));
+            COMMIT;
+        `, (err) => {
+            t.error(err);
+
+            pg_optimize();
+
+            return done();
+        });
+    });
+
+    popQ.defer((done) => {
+        cluster_net('pt_test');
+        done();
+    });
+
+    // Side Street is matched to an address cluster
+    popQ.defer((done) => {
+        pool.query(`
+            UPDATE network_cluster
+                SET address = 1
+                WHERE source_ids = ARRAY[3]::BIGINT[];
+        `, done);
+    });
+
+    popQ.defer((done) => {
+        cluster_net('pt_test', true);
+        done();
+    });
+
+    popQ.await((err) => {
+        t.error(err);
+
+        pool.query(`
+            SELECT
+                ST_AsGeoJSON(geom)::JSON as geom,
+                source_ids,
+                names
+            FROM
+                network_orphan_cluster
+            ORDER BY
+                id;
+        `, (err, res) => {
+            t.error(err);
+
+            t.equals(res.rows.length, 1, 'only unmatched networks are clustered');
+            t.deepEquals(res.rows[0].source_ids, ['1', '2']);
+            t.deepEquals(res.rows[0].geom, { type: 'MultiLineString', coordinates: [[[9.5167350769043, 47.1327681860613], [9.51982498168945, 47.132870369815]], [[9.51399922370911, 47.1326951975457], [9.51251864433289, 47.1326951975457]]] });
+            t.deepEquals(res.rows[0].names, [{ freq: 1, display: 'Main Street', tokenized: 'main st', tokeneless: 'main' }]);
+            pool.end(() => {
+                t.end();
+            });
+        });
+    });
+});
+
+db.init(test);
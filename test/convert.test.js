@@ -2,6 +2,7 @@
 
 const convert = require('../native/index.node').convert;
 const test = require('tape');
+const fs = require('fs');
 const path = require('path');
 const os = require('os');
 
@@ -79,3 +80,69 @@ test('Convert - Raw With Record Separator', (t) => {
 
     t.end();
 });
+
+test('Convert - FeatureCollection to GeoJSONSeq', (t) => {
+    convert({
+        input: path.resolve(__dirname, 'fixtures/convert.FeatureCollection'),
+        output: path.resolve(os.tmpdir(), 'convert.GeoJSONSeq'),
+        output_format: 'geojsonseq'
+    });
+
+    const lines = fs.readFileSync(path.resolve(os.tmpdir(), 'convert.GeoJSONSeq'), 'utf8').split('\n').filter((line) => line.length);
+
+    t.equals(lines.length, 24);
+
+    lines.forEach((line) => {
+        t.equals(line[0], '\x1E', 'record separator prefixed');
+        t.equals(JSON.parse(line.slice(1)).type, 'Feature');
+    });
+
+    t.end();
+});
+
+test('Convert - GeoJSONSeq to line delimited', (t) => {
+    convert({
+        input: path.resolve(__dirname, 'fixtures/convert.RawWithRecordSeparator'),
+        output: path.resolve(os.tmpdir(), 'convert.GeoJSONLD'),
+        input_format: 'geojsonseq',
+        output_format: 'geojsonld'
+    });
+
+    const lines = fs.readFileSync(path.resolve(os.tmpdir(), 'convert.GeoJSONLD'), 'utf8').split('\n').filter((line) => line.length);
+
+    t.equals(lines.length, 10);
+
+    lines.forEach((line) => {
+        t.equals(JSON.parse(line).type, 'Feature');
+    });
+
+    t.end();
+});
+
+test('Convert - GeoJSONSeq to FeatureCollection', (t) => {
+    convert({
+        input: path.resolve(os.tmpdir(), 'convert.GeoJSONSeq'),
+        output: path.resolve(os.tmpdir(), 'convert.GeoJSONSeq.json'),
+        input_format: 'geojsonseq',
+        output_format: 'featurecollection'
+    });
+
+    const res = JSON.parse(fs.readFileSync(path.resolve(os.tmpdir(), 'convert.GeoJSONSeq.json'), 'utf8'));
+
+    t.equals(res.type, 'FeatureCollection');
+    t.equals(res.features.length, 24);
+
+    t.end();
+});
+
+test('Convert - Invalid Format', (t) => {
+    t.throws(() => {
+        convert({
+            input: path.resolve(__dirname, 'fixtures/convert.Feature'),
+            output: path.resolve(os.tmpdir(), 'convert.Invalid.json'),
+            output_format: 'shapefile'
+        });
+    });
+
+    t.end();
+});
@@ -228,3 +228,46 @@ test('dedupe (hecate)', (t) => {
 
     t.end();
 });
+
+test('dedupe (hecate, memory)', (t) => {
+    try {
+        fs.unlinkSync('/tmp/dedupeout.geojson');
+    } catch (err) {
+        console.log('ok - no tmp files to clear');
+    }
+
+    t.doesNotThrow(() => {
+        dedupe({
+            db: 'pt_test',
+            backend: 'memory',
+            hecate: true,
+            input: './test/fixtures/dedupe.geojson',
+            output: '/tmp/dedupeout.geojson',
+            languages: 'en',
+            context: {
+                country: 'us',
+                region: 'dc'
+            }
+        });
+    }, 'dedupe runs without err');
+
+    const rl = new ReadLine('/tmp/dedupeout.geojson');
+
+    const output = {};
+
+    let line = rl.next();
+    while (line) {
+        line = JSON.parse(line);
+
+        output[line.id] = line;
+        line = rl.next();
+    }
+
+    t.deepEquals(Object.keys(output), [
+        '2', '7', '8' // ID 2,7 & 8 should be deleted (leaving 1) (duplicate geom/number/street)
+    ], 'output ids match the postgres backend');
+
+    t.equals(output[2].action, 'delete', 'feature 2 is deleted');
+
+    t.end();
+});
@@ -0,0 +1,65 @@
+'use strict';
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const test = require('tape');
+
+const {
+    import_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+test('map - import_addr reprojected input', (t) => {
+    const pool = db.get();
+    const input = path.resolve(os.tmpdir(), 'pt2itp-crs.geojson');
+    const errors = path.resolve(os.tmpdir(), 'pt2itp-crs-errors');
+
+    fs.writeFileSync(input, [
+        { type: 'Feature', properties: { number: '1600', street: 'Pennsylvania Ave NW' }, geometry: { type: 'Point', coordinates: [-8575605.39, 4707174.88] } },
+        { type: 'Feature', properties: { number: '1', street: 'Main St' }, geometry: { type: 'Point', coordinates: [0, 30000000] } }
+    ].map((feat) => JSON.stringify(feat)).join('\n'));
+
+    import_addr({
+        db: 'pt_test',
+        seq: true,
+        input: input,
+        context: { country: 'us', region: 'dc', crs: 'EPSG:3857' },
+        errors: errors
+    });
+
+    t.equals(JSON.parse(fs.readFileSync(errors, 'utf8')).message, 'Coordinates outside the valid range of EPSG:3857: [0, 30000000]', 'out of range coordinates rejected');
+
+    fs.unlinkSync(input);
+    fs.unlinkSync(errors);
+
+    pool.query('SELECT number, ST_X(geom) AS lon, ST_Y(geom) AS lat FROM address', (err, res) => {
+        t.error(err);
+
+        t.equals(res.rows.length, 1);
+        t.equals(res.rows[0].number, '1600');
+        t.ok(Math.abs(res.rows[0].lon - -77.0365) < 0.0001, 'longitude reprojected');
+        t.ok(Math.abs(res.rows[0].lat - 38.8977) < 0.0001, 'latitude reprojected');
+
+        pool.end();
+        t.end();
+    });
+});
+
+test('map - import_addr unsupported crs', (t) => {
+    t.throws(() => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: path.resolve(__dirname, './fixtures/copy.sample-input-address.geojson'),
+            context: { country: 'us', region: 'dc', crs: 'EPSG:0' }
+        });
+    }, /Unsupported CRS: EPSG:0/);
+
+    t.end();
+});
+
+db.init(test);
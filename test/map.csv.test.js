@@ -0,0 +1,90 @@
+'use strict';
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const test = require('tape');
+
+const {
+    import_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+test('map - import_addr csv invalid rows are strict by default', (t) => {
+    t.throws(() => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: path.resolve(__dirname, './fixtures/openaddresses.csv'),
+            context: { country: 'us', region: 'dc' },
+            csv: {}
+        });
+    }, /Invalid coordinates/, 'first invalid row stops the import');
+
+    t.end();
+});
+
+db.init(test);
+
+test('map - import_addr csv', (t) => {
+    const pool = db.get();
+    const errors = path.resolve(os.tmpdir(), 'pt2itp-csv-errors');
+
+    import_addr({
+        db: 'pt_test',
+        seq: true,
+        input: path.resolve(__dirname, './fixtures/openaddresses.csv'),
+        context: { country: 'us', region: 'dc' },
+        errors: errors,
+        invalid: 'skip',
+        csv: {}
+    });
+
+    t.deepEquals(JSON.parse(fs.readFileSync(errors, 'utf8')), {
+        line: 4,
+        id: null,
+        code: 'EPARSE',
+        message: 'Invalid coordinates: -77.0500,not-a-lat,10,Main St,,Washington,20001,3',
+        feature: null
+    }, 'invalid row written to errors with line number');
+
+    pool.query(`
+        SELECT
+            number,
+            names->0->>'display' AS street,
+            props->>'unit' AS unit,
+            props->>'postcode' AS postcode
+        FROM
+            address
+        ORDER BY
+            id
+    `, (err, res) => {
+        t.error(err);
+
+        t.deepEquals(res.rows, [{
+            number: '1600',
+            street: 'Pennsylvania Ave NW',
+            unit: null,
+            postcode: '20500'
+        }, {
+            number: '1',
+            street: 'First St "NE"',
+            unit: null,
+            postcode: '20543'
+        }, {
+            number: '12',
+            street: 'Main St',
+            unit: '4B',
+            postcode: '20001'
+        }], 'valid rows imported');
+
+        fs.unlinkSync(errors);
+        pool.end();
+        t.end();
+    });
+});
+
+db.init(test);
@@ -0,0 +1,89 @@
+'use strict';
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const test = require('tape');
+
+const {
+    import_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+test('map - import_addr invalid lines are strict by default', (t) => {
+    t.throws(() => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: path.resolve(__dirname, './fixtures/invalid-address.geojson'),
+            context: { country: 'us', region: 'dc' }
+        });
+    }, /Invalid GeoJSON/, 'first invalid line stops the import');
+
+    t.end();
+});
+
+db.init(test);
+
+test('map - import_addr invalid lines skipped', (t) => {
+    const pool = db.get();
+    const errors = path.resolve(os.tmpdir(), 'pt2itp-invalid-errors');
+
+    import_addr({
+        db: 'pt_test',
+        seq: true,
+        input: path.resolve(__dirname, './fixtures/invalid-address.geojson'),
+        context: { country: 'us', region: 'dc' },
+        errors: errors,
+        invalid: 'skip'
+    });
+
+    const rejected = fs.readFileSync(errors, 'utf8').trim().split('\n').map((line) => JSON.parse(line));
+
+    t.deepEquals(rejected.map((rejection) => [rejection.line, rejection.code]), [
+        [2, 'EPARSE'],
+        [4, 'EPARSE']
+    ], 'invalid lines written to errors with line number');
+
+    fs.unlinkSync(errors);
+
+    pool.query('SELECT number FROM address ORDER BY id', (err, res) => {
+        t.error(err);
+
+        t.deepEquals(res.rows, [{ number: '3' }, { number: '13' }], 'valid lines imported');
+
+        pool.end();
+        t.end();
+    });
+});
+
+db.init(test);
+
+test('map - import_addr invalid lines skipped up to a limit', (t) => {
+    t.doesNotThrow(() => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: path.resolve(__dirname, './fixtures/invalid-address.geojson'),
+            context: { country: 'us', region: 'dc' },
+            invalid: { limit: 2 }
+        });
+    }, 'invalid lines within the limit are skipped');
+
+    t.throws(() => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: path.resolve(__dirname, './fixtures/invalid-address.geojson'),
+            context: { country: 'us', region: 'dc' },
+            invalid: { limit: 1 }
+        });
+    }, /More than 1 invalid lines of input/, 'import stops once the limit is exceeded');
+
+    t.end();
+});
+
+db.init(test);
@@ -0,0 +1,69 @@
+'use strict';
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const test = require('tape');
+
+const {
+    import_addr,
+    cluster_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+test('map - import_addr postcode & locality', (t) => {
+    const pool = db.get();
+    const input = path.resolve(os.tmpdir(), 'pt2itp-place.geojson');
+
+    fs.writeFileSync(input, [
+        { type: 'Feature', properties: { number: '10', street: 'Main St', zip: '20001', city: 'Washington' }, geometry: { type: 'Point', coordinates: [-77.0365, 38.8977] } },
+        { type: 'Feature', properties: { number: '12', street: 'Main St', zip: ' 20002 ', city: ' Washington' }, geometry: { type: 'Point', coordinates: [-77.0366, 38.8977] } },
+        { type: 'Feature', properties: { number: '14', street: 'Main St', zip: 20001 }, geometry: { type: 'Point', coordinates: [-77.0367, 38.8977] } },
+        { type: 'Feature', properties: { number: '16', street: 'Main St', postcode: '20003' }, geometry: { type: 'Point', coordinates: [-77.0368, 38.8977] } }
+    ].map((feat) => JSON.stringify(feat)).join('\n'));
+
+    import_addr({
+        db: 'pt_test',
+        seq: true,
+        input: input,
+        context: { country: 'us', region: 'dc', postcode: 'zip' }
+    });
+
+    fs.unlinkSync(input);
+
+    pool.query(`SELECT number, postcode, locality FROM address ORDER BY id`, (err, res) => {
+        t.error(err);
+
+        t.deepEquals(res.rows, [
+            { number: '10', postcode: '20001', locality: 'Washington' },
+            { number: '12', postcode: '20002', locality: 'Washington' },
+            { number: '14', postcode: '20001', locality: null },
+            { number: '16', postcode: null, locality: null }
+        ], 'postcode & locality read from the configured properties');
+
+        pool.end();
+        t.end();
+    });
+});
+
+test('map - cluster_addr postcode & locality', (t) => {
+    const pool = db.get();
+
+    cluster_addr('pt_test', true);
+
+    pool.query(`SELECT postcode, locality FROM address_orphan_cluster`, (err, res) => {
+        t.error(err);
+
+        t.deepEquals(res.rows, [
+            { postcode: ['20001', '20002'], locality: ['Washington'] }
+        ], 'distinct postcodes & localities aggregated per cluster');
+
+        pool.end();
+        t.end();
+    });
+});
+
+db.init(test);
@@ -0,0 +1,61 @@
+'use strict';
+
+const path = require('path');
+const test = require('tape');
+
+const {
+    import_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+test('map - import_addr progress events', (t) => {
+    const events = [];
+
+    import_addr({
+        db: 'pt_test',
+        seq: true,
+        input: path.resolve(__dirname, './fixtures/copy.sample-input-address.geojson'),
+        context: { country: 'us', region: 'dc' }
+    }, (event) => {
+        events.push(event);
+    });
+
+    t.ok(events.length >= 4, 'events emitted');
+
+    t.deepEquals(events.map((event) => `${event.stage}:${event.complete}`).filter((event, i, all) => {
+        return all.indexOf(event) === i;
+    }), [
+        'address:false',
+        'address:true',
+        'address_index:false',
+        'address_index:true'
+    ], 'each stage begins and completes');
+
+    const imported = events.filter((event) => event.stage === 'address' && event.complete)[0];
+    t.ok(imported.processed > 0, 'features processed reported');
+    t.equals(imported.errors, 0, 'no errors reported');
+    t.equals(typeof imported.elapsed, 'number', 'elapsed reported');
+    t.equals(typeof imported.throughput, 'number', 'throughput reported');
+
+    t.end();
+});
+
+test('map - import_addr progress callback errors', (t) => {
+    t.throws(() => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: path.resolve(__dirname, './fixtures/copy.sample-input-address.geojson'),
+            context: { country: 'us', region: 'dc' }
+        }, () => {
+            throw new Error('callback failed');
+        });
+    }, /callback failed/, 'callback exceptions are rethrown');
+
+    t.end();
+});
+
+db.init(test);
@@ -0,0 +1,105 @@
+'use strict';
+
+const path = require('path');
+const test = require('tape');
+const Queue = require('d3-queue').queue;
+
+const {
+    import_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+const input = path.resolve(__dirname, './fixtures/copy.sample-input-address.geojson');
+
+test('map - resume skips completed stages', (t) => {
+    const pool = db.get();
+    const popQ = new Queue(1);
+
+    popQ.defer((done) => {
+        import_addr({
+            db: 'pt_test',
+            seq: true,
+            input: input,
+            context: { country: 'us', region: 'dc' }
+        });
+
+        pool.query(`
+            SELECT name, count FROM stages
+        `, (err, res) => {
+            t.error(err);
+
+            t.equals(res.rows.length, 1, 'one stage recorded');
+            t.equals(res.rows[0].name, 'address', 'address stage recorded');
+            t.ok(parseInt(res.rows[0].count) > 0, 'address stage row count recorded');
+
+            return done();
+        });
+    });
+
+    popQ.defer((done) => {
+        // Modify the table without altering the row count
+        pool.query(`
+            UPDATE address SET source = 'resume-marker'
+        `, (err) => {
+            t.error(err);
+
+            import_addr({
+                db: 'pt_test',
+                seq: true,
+                input: input,
+                context: { country: 'us', region: 'dc' },
+                resume: true
+            });
+
+            return done();
+        });
+    });
+
+    popQ.defer((done) => {
+        pool.query(`
+            SELECT count(*) FROM address WHERE source != 'resume-marker'
+        `, (err, res) => {
+            t.error(err);
+            t.equals(parseInt(res.rows[0].count), 0, 'address stage was skipped');
+
+            return done();
+        });
+    });
+
+    popQ.defer((done) => {
+        // A changed row count invalidates the stage
+        pool.query(`
+            DELETE FROM address WHERE id = 1
+        `, (err) => {
+            t.error(err);
+
+            import_addr({
+                db: 'pt_test',
+                seq: true,
+                input: input,
+                context: { country: 'us', region: 'dc' },
+                resume: true
+            });
+
+            pool.query(`
+                SELECT count(*) FROM address WHERE source = 'resume-marker'
+            `, (err, res) => {
+                t.error(err);
+                t.equals(parseInt(res.rows[0].count), 0, 'address stage was rerun');
+
+                return done();
+            });
+        });
+    });
+
+    popQ.awaitAll((err) => {
+        t.error(err);
+        pool.end();
+        t.end();
+    });
+});
+
+db.init(test);
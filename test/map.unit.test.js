@@ -0,0 +1,51 @@
+'use strict';
+
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const test = require('tape');
+
+const {
+    import_addr
+} = require('../native/index.node');
+
+const db = require('./lib/db');
+
+db.init(test);
+
+test('map - import_addr units', (t) => {
+    const pool = db.get();
+    const input = path.resolve(os.tmpdir(), 'pt2itp-unit.geojson');
+
+    fs.writeFileSync(input, [
+        { type: 'Feature', properties: { number: '10 Apt 4', street: 'Main St' }, geometry: { type: 'Point', coordinates: [-77.0365, 38.8977] } },
+        { type: 'Feature', properties: { number: '10-2', street: 'Main St' }, geometry: { type: 'Point', coordinates: [-77.0365, 38.8977] } },
+        { type: 'Feature', properties: { number: '10', unit: 'Suite 200', street: 'Main St' }, geometry: { type: 'Point', coordinates: [-77.0365, 38.8977] } },
+        { type: 'Feature', properties: { number: '10-12', street: 'Main St' }, geometry: { type: 'Point', coordinates: [-77.0365, 38.8977] } }
+    ].map((feat) => JSON.stringify(feat)).join('\n'));
+
+    import_addr({
+        db: 'pt_test',
+        seq: true,
+        input: input,
+        context: { country: 'us', region: 'dc' }
+    });
+
+    fs.unlinkSync(input);
+
+    pool.query(`SELECT number, unit, props->>'unit' AS prop FROM address ORDER BY id`, (err, res) => {
+        t.error(err);
+
+        t.deepEquals(res.rows, [
+            { number: '10', unit: 'apt 4', prop: 'apt 4' },
+            { number: '10', unit: '2', prop: '2' },
+            { number: '10', unit: 'ste 200', prop: 'ste 200' },
+            { number: '10-12', unit: null, prop: null }
+        ], 'units parsed from the number or unit property');
+
+        pool.end();
+        t.end();
+    });
+});
+
+db.init(test);
@@ -61,3 +61,50 @@ test('misc.hasDupAddressWithin', (t) => {
     t.equals(Misc.hasDupAddressWithin([1, 2, 1], [[0,0], [1,1], [1,1]]), false); // NOTE: Assumes ordered input!
     t.end();
 });
+
+test('poolConf - schema', (t) => {
+    t.deepEquals(Misc.poolConf('pt_test?schema=us_tx', 2), {
+        max: 2,
+        user: process.env.PGUSER || 'postgres',
+        database: 'pt_test',
+        idleTimeoutMillis: 30000,
+        schema: 'us_tx'
+    }, 'schema removed from database name');
+
+    t.deepEquals(Misc.poolConf('postgres://pt2itp@localhost/pt_test?sslmode=require&schema=us_ca', 2), {
+        max: 2,
+        connectionString: 'postgres://pt2itp@localhost/pt_test?sslmode=require',
+        idleTimeoutMillis: 30000,
+        schema: 'us_ca'
+    }, 'schema removed from connection string');
+
+    t.equals(Misc.withSchema('pt_test', 'us_tx'), 'pt_test?schema=us_tx');
+    t.equals(Misc.withSchema('pt_test', undefined), 'pt_test');
+
+    t.throws(() => {
+        Misc.poolConf('pt_test?schema=us;DROP TABLE address', 2);
+    }, /Invalid schema name/);
+
+    t.end();
+});
+
+test('invalidPolicy', (t) => {
+    t.equals(Misc.invalidPolicy(undefined, undefined), undefined, 'strict by default');
+    t.equals(Misc.invalidPolicy('skip', undefined), 'skip');
+    t.deepEquals(Misc.invalidPolicy('skip', '100'), { limit: 100 });
+    t.deepEquals(Misc.invalidPolicy(undefined, 0), { limit: 0 }, 'limit implies skip');
+
+    t.throws(() => {
+        Misc.invalidPolicy('lenient', undefined);
+    }, /Invalid --invalid policy/);
+
+    t.throws(() => {
+        Misc.invalidPolicy('strict', 10);
+    }, /cannot be used with --invalid=strict/);
+
+    t.throws(() => {
+        Misc.invalidPolicy('skip', 'ten');
+    }, /Invalid --invalid-limit/);
+
+    t.end();
+});